@@ -0,0 +1,12 @@
+// ヘッダーはOUT_DIRに生成し，ソースツリーには書き込まない．
+// チェックインしている include/quart_engine.h が正で，tests/c_api.rs で生成したものと一致するか確かめる．
+// APIを変えたら `cbindgen --config cbindgen.toml --output include/quart_engine.h` で更新する
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    cbindgen::generate(&crate_dir)
+        .expect("Failed to generate the C header")
+        .write_to_file(format!("{}/quart_engine.h", out_dir));
+}
@@ -0,0 +1,200 @@
+#ifndef QUART_ENGINE_H
+#define QUART_ENGINE_H
+
+/* このファイルはcbindgenで生成している．直接編集しないこと */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * 合法手の数の最大値（16マス×15駒）
+ */
+#define QUART_MAX_LEGAL_MOVES 240
+
+/**
+ * 関数の結果
+ */
+typedef enum QuartStatus {
+  QUART_STATUS_OK = 0,
+  /**
+   * 必要なポインタがNULLだった
+   */
+  QUART_STATUS_NULL_POINTER = 1,
+  /**
+   * 文字列やJSON，policyの設定などが読めなかった
+   */
+  QUART_STATUS_INVALID_ARGUMENT = 2,
+  /**
+   * すでに終局している
+   */
+  QUART_STATUS_GAME_OVER = 3,
+  /**
+   * 盤面の外に置こうとした
+   */
+  QUART_STATUS_OUT_OF_BOUNDS = 4,
+  /**
+   * すでに駒が置かれているセルに置こうとした
+   */
+  QUART_STATUS_CELL_OCCUPIED = 5,
+  /**
+   * 渡せない駒を渡そうとした
+   */
+  QUART_STATUS_INVALID_PIECE_INDEX = 6,
+  /**
+   * 渡せる駒があるのに駒を渡さなかった
+   */
+  QUART_STATUS_MISSING_PIECE = 7,
+  /**
+   * 書き込み先の配列が足りなかった
+   */
+  QUART_STATUS_BUFFER_TOO_SMALL = 8,
+  /**
+   * ライブラリの内部でpanicした
+   */
+  QUART_STATUS_PANIC = 9,
+} QuartStatus;
+
+/**
+ * 局面の状態
+ */
+typedef enum QuartGameStatus {
+  QUART_GAME_STATUS_IN_PROGRESS = 0,
+  QUART_GAME_STATUS_PLAYER1_WON = 1,
+  QUART_GAME_STATUS_PLAYER2_WON = 2,
+  QUART_GAME_STATUS_DRAW = 3,
+} QuartGameStatus;
+
+/**
+ * 局面
+ */
+typedef struct QuartGame QuartGame;
+
+/**
+ * 手を考えるpolicy
+ */
+typedef struct QuartPolicy QuartPolicy;
+
+/**
+ * 1手．piece_indexは相手に渡す駒のavailable_piecesでの位置で，渡さないときは-1
+ */
+typedef struct QuartMove {
+  uint8_t row;
+  uint8_t col;
+  int8_t piece_index;
+} QuartMove;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * ライブラリのバージョン．解放しない
+ */
+const char *quart_version(void);
+
+/**
+ * QuartStatusの説明．解放しない
+ */
+const char *quart_status_message(int32_t status);
+
+/**
+ * このスレッドで最後に失敗した関数のエラーの詳細．次の呼び出しまで有効で，解放しない
+ */
+const char *quart_last_error(void);
+
+/**
+ * quart_game_to_jsonなどで返した文字列を解放する
+ */
+void quart_string_free(char *text);
+
+/**
+ * 駒をランダムに1つ渡された初期局面を作る
+ */
+enum QuartStatus quart_game_new(struct QuartGame **out);
+
+/**
+ * 局面の文字列（例: "0a3./..../..../.... 5"）から作る
+ */
+enum QuartStatus quart_game_from_position(const char *position, struct QuartGame **out);
+
+/**
+ * quart_game_to_jsonの出力から作る
+ */
+enum QuartStatus quart_game_from_json(const char *json, struct QuartGame **out);
+
+enum QuartStatus quart_game_clone(const struct QuartGame *game, struct QuartGame **out);
+
+void quart_game_free(struct QuartGame *game);
+
+/**
+ * 局面をJSONにする．*outはquart_string_freeで解放する
+ */
+enum QuartStatus quart_game_to_json(const struct QuartGame *game, char **out);
+
+/**
+ * 局面を文字列にする．*outはquart_string_freeで解放する
+ */
+enum QuartStatus quart_game_to_position(const struct QuartGame *game, char **out);
+
+/**
+ * 手が合法ならQUART_STATUS_OK，そうでなければ理由を返す
+ */
+enum QuartStatus quart_game_validate_move(const struct QuartGame *game, struct QuartMove mv);
+
+/**
+ * 手を指す．不正な手なら局面は変わらない
+ */
+enum QuartStatus quart_game_play(struct QuartGame *game, struct QuartMove mv);
+
+/**
+ * 棋譜の表記（例: "c3:a"）で手を指す
+ */
+enum QuartStatus quart_game_play_notation(struct QuartGame *game, const char *text);
+
+/**
+ * 手を棋譜の表記にする．*outはquart_string_freeで解放する
+ */
+enum QuartStatus quart_game_format_move(const struct QuartGame *game,
+                                        struct QuartMove mv,
+                                        char **out);
+
+/**
+ * 合法手をmovesに書き込み，*countに合法手の数を入れる．
+ * capacityが足りなければ書ける分だけ書いてQUART_STATUS_BUFFER_TOO_SMALLを返す．
+ * capacityをQUART_MAX_LEGAL_MOVESにすれば必ず足りる
+ */
+enum QuartStatus quart_game_legal_moves(const struct QuartGame *game,
+                                        struct QuartMove *moves,
+                                        size_t capacity,
+                                        size_t *count);
+
+enum QuartStatus quart_game_status(const struct QuartGame *game, enum QuartGameStatus *out);
+
+/**
+ * 手番のプレイヤー（1または2）
+ */
+enum QuartStatus quart_game_current_player(const struct QuartGame *game, uint8_t *out);
+
+/**
+ * PolicyConfigのJSON（例: {"type": "alpha_beta", "max_time": 0.1}）からpolicyを作る
+ */
+enum QuartStatus quart_policy_new(const char *config, struct QuartPolicy **out);
+
+void quart_policy_free(struct QuartPolicy *policy);
+
+/**
+ * policyに次の手を考えさせる．move_timeは秒で，0以下ならpolicyの設定に任せる
+ */
+enum QuartStatus quart_policy_choose_move(struct QuartPolicy *policy,
+                                          const struct QuartGame *game,
+                                          double move_time,
+                                          struct QuartMove *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* QUART_ENGINE_H */
@@ -0,0 +1,458 @@
+//! quart-engineのC API
+//!
+//! 局面（QuartGame）とpolicy（QuartPolicy）は不透明なポインタとして扱い，
+//! それぞれ quart_game_free / quart_policy_free で解放する．
+//! 関数はpanicせずに QuartStatus を返し，失敗の詳細は quart_last_error で取れる．
+//! Rustが確保した文字列は quart_string_free で解放する．
+//!
+//! # Safety
+//!
+//! ポインタの引数は，NULLか，このライブラリが返した解放前のポインタ
+//! （文字列ならNUL終端のUTF-8）でなければならない．
+//! 1つのQuartGame/QuartPolicyを複数のスレッドから同時に使ってはいけない．
+#![allow(clippy::missing_safety_doc)]
+
+use quart_engine::game::action::Action;
+use quart_engine::game::player::Player;
+use quart_engine::game::{ActionList, Game, GameError};
+use quart_engine::policies::{Policy, PolicyConfig};
+use quart_engine::search::SearchLimits;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+
+/// 関数の結果
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuartStatus {
+    Ok = 0,
+    /// 必要なポインタがNULLだった
+    NullPointer = 1,
+    /// 文字列やJSON，policyの設定などが読めなかった
+    InvalidArgument = 2,
+    /// すでに終局している
+    GameOver = 3,
+    /// 盤面の外に置こうとした
+    OutOfBounds = 4,
+    /// すでに駒が置かれているセルに置こうとした
+    CellOccupied = 5,
+    /// 渡せない駒を渡そうとした
+    InvalidPieceIndex = 6,
+    /// 渡せる駒があるのに駒を渡さなかった
+    MissingPiece = 7,
+    /// 書き込み先の配列が足りなかった
+    BufferTooSmall = 8,
+    /// ライブラリの内部でpanicした
+    Panic = 9,
+}
+
+/// 局面の状態
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuartGameStatus {
+    InProgress = 0,
+    Player1Won = 1,
+    Player2Won = 2,
+    Draw = 3,
+}
+
+/// 1手．piece_indexは相手に渡す駒のavailable_piecesでの位置で，渡さないときは-1
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuartMove {
+    pub row: u8,
+    pub col: u8,
+    pub piece_index: i8,
+}
+
+/// 合法手の数の最大値（16マス×15駒）
+pub const QUART_MAX_LEGAL_MOVES: usize = 240;
+
+/// 局面
+pub struct QuartGame {
+    game: Game,
+}
+
+/// 手を考えるpolicy
+pub struct QuartPolicy {
+    policy: Box<dyn Policy>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(message: impl Into<String>) {
+    let message = CString::new(message.into().replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = message);
+}
+
+fn invalid_argument(message: impl Into<String>) -> QuartStatus {
+    set_last_error(message);
+    QuartStatus::InvalidArgument
+}
+
+impl From<GameError> for QuartStatus {
+    fn from(error: GameError) -> Self {
+        set_last_error(error.to_string());
+        match error {
+            GameError::GameOver => QuartStatus::GameOver,
+            GameError::OutOfBounds { .. } => QuartStatus::OutOfBounds,
+            GameError::CellOccupied { .. } => QuartStatus::CellOccupied,
+            GameError::InvalidPieceIndex { .. } => QuartStatus::InvalidPieceIndex,
+            GameError::MissingPiece => QuartStatus::MissingPiece,
+        }
+    }
+}
+
+/// panicをQuartStatus::Panicに変えて，Cにunwindさせない
+fn guard(f: impl FnOnce() -> Result<(), QuartStatus>) -> QuartStatus {
+    set_last_error("");
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => QuartStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Unknown panic".to_string());
+            set_last_error(format!("panic: {}", message));
+            QuartStatus::Panic
+        }
+    }
+}
+
+unsafe fn non_null<'a, T>(pointer: *const T) -> Result<&'a T, QuartStatus> {
+    pointer.as_ref().ok_or_else(|| {
+        set_last_error("Unexpected null pointer");
+        QuartStatus::NullPointer
+    })
+}
+
+unsafe fn non_null_mut<'a, T>(pointer: *mut T) -> Result<&'a mut T, QuartStatus> {
+    pointer.as_mut().ok_or_else(|| {
+        set_last_error("Unexpected null pointer");
+        QuartStatus::NullPointer
+    })
+}
+
+unsafe fn read_str<'a>(text: *const c_char) -> Result<&'a str, QuartStatus> {
+    if text.is_null() {
+        set_last_error("Unexpected null pointer");
+        return Err(QuartStatus::NullPointer);
+    }
+    CStr::from_ptr(text)
+        .to_str()
+        .map_err(|_| invalid_argument("String is not valid UTF-8"))
+}
+
+unsafe fn write_string(out: *mut *mut c_char, text: String) -> Result<(), QuartStatus> {
+    let out = non_null_mut(out)?;
+    *out = CString::new(text).unwrap().into_raw();
+    Ok(())
+}
+
+unsafe fn write_game(out: *mut *mut QuartGame, game: Game) -> Result<(), QuartStatus> {
+    let out = non_null_mut(out)?;
+    *out = Box::into_raw(Box::new(QuartGame { game }));
+    Ok(())
+}
+
+fn to_action(mv: QuartMove) -> Result<Action, QuartStatus> {
+    let piece_index = match mv.piece_index {
+        -1 => None,
+        index if index >= 0 => Some(index as usize),
+        index => return Err(invalid_argument(format!("Invalid piece index: {}", index))),
+    };
+    Ok(Action {
+        row: mv.row as usize,
+        col: mv.col as usize,
+        piece_index,
+    })
+}
+
+fn to_move(action: &Action) -> QuartMove {
+    QuartMove {
+        row: action.row as u8,
+        col: action.col as u8,
+        piece_index: action.piece_index.map_or(-1, |index| index as i8),
+    }
+}
+
+/// 合法手をすべて返す．最後の1駒を置く手は駒を渡さない
+fn legal_actions(game: &Game) -> ActionList {
+    if game.is_game_over() {
+        return ActionList::new();
+    }
+    game.action_list()
+}
+
+/// ライブラリのバージョン．解放しない
+#[no_mangle]
+pub extern "C" fn quart_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// QuartStatusの説明．解放しない
+#[no_mangle]
+pub extern "C" fn quart_status_message(status: i32) -> *const c_char {
+    let message: &'static str = match status {
+        0 => "ok\0",
+        1 => "null pointer\0",
+        2 => "invalid argument\0",
+        3 => "game is already over\0",
+        4 => "cell is out of bounds\0",
+        5 => "cell is already occupied\0",
+        6 => "invalid piece index\0",
+        7 => "a piece must be given to the opponent\0",
+        8 => "buffer too small\0",
+        9 => "internal panic\0",
+        _ => "unknown status\0",
+    };
+    message.as_ptr() as *const c_char
+}
+
+/// このスレッドで最後に失敗した関数のエラーの詳細．次の呼び出しまで有効で，解放しない
+#[no_mangle]
+pub extern "C" fn quart_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| last_error.borrow().as_ptr())
+}
+
+/// quart_game_to_jsonなどで返した文字列を解放する
+#[no_mangle]
+pub unsafe extern "C" fn quart_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// 駒をランダムに1つ渡された初期局面を作る
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_new(out: *mut *mut QuartGame) -> QuartStatus {
+    guard(|| write_game(out, Game::new()))
+}
+
+/// 局面の文字列（例: "0a3./..../..../.... 5"）から作る
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_from_position(
+    position: *const c_char,
+    out: *mut *mut QuartGame,
+) -> QuartStatus {
+    guard(|| {
+        let game = Game::from_position_string(read_str(position)?).map_err(invalid_argument)?;
+        write_game(out, game)
+    })
+}
+
+/// quart_game_to_jsonの出力から作る
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_from_json(
+    json: *const c_char,
+    out: *mut *mut QuartGame,
+) -> QuartStatus {
+    guard(|| {
+        let game: Game = serde_json::from_str(read_str(json)?)
+            .map_err(|error| invalid_argument(error.to_string()))?;
+        write_game(out, game)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_clone(
+    game: *const QuartGame,
+    out: *mut *mut QuartGame,
+) -> QuartStatus {
+    guard(|| write_game(out, non_null(game)?.game.clone()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_free(game: *mut QuartGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// 局面をJSONにする．*outはquart_string_freeで解放する
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_to_json(
+    game: *const QuartGame,
+    out: *mut *mut c_char,
+) -> QuartStatus {
+    guard(|| write_string(out, non_null(game)?.game.to_json()))
+}
+
+/// 局面を文字列にする．*outはquart_string_freeで解放する
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_to_position(
+    game: *const QuartGame,
+    out: *mut *mut c_char,
+) -> QuartStatus {
+    guard(|| write_string(out, non_null(game)?.game.to_position_string()))
+}
+
+/// 手が合法ならQUART_STATUS_OK，そうでなければ理由を返す
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_validate_move(
+    game: *const QuartGame,
+    mv: QuartMove,
+) -> QuartStatus {
+    guard(|| {
+        let game = &non_null(game)?.game;
+        game.validate_action(&to_action(mv)?)?;
+        Ok(())
+    })
+}
+
+/// 手を指す．不正な手なら局面は変わらない
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_play(game: *mut QuartGame, mv: QuartMove) -> QuartStatus {
+    guard(|| {
+        let game = &mut non_null_mut(game)?.game;
+        let action = to_action(mv)?;
+        game.play_turn(action.row, action.col, action.piece_index)?;
+        Ok(())
+    })
+}
+
+/// 棋譜の表記（例: "c3:a"）で手を指す
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_play_notation(
+    game: *mut QuartGame,
+    text: *const c_char,
+) -> QuartStatus {
+    guard(|| {
+        let game = &mut non_null_mut(game)?.game;
+        let action = game.parse_move(read_str(text)?).map_err(invalid_argument)?;
+        game.play_turn(action.row, action.col, action.piece_index)?;
+        Ok(())
+    })
+}
+
+/// 手を棋譜の表記にする．*outはquart_string_freeで解放する
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_format_move(
+    game: *const QuartGame,
+    mv: QuartMove,
+    out: *mut *mut c_char,
+) -> QuartStatus {
+    guard(|| {
+        let game = &non_null(game)?.game;
+        let action = to_action(mv)?;
+        game.validate_action(&action)?;
+        write_string(out, game.format_move(&action))
+    })
+}
+
+/// 合法手をmovesに書き込み，*countに合法手の数を入れる．
+/// capacityが足りなければ書ける分だけ書いてQUART_STATUS_BUFFER_TOO_SMALLを返す．
+/// capacityをQUART_MAX_LEGAL_MOVESにすれば必ず足りる
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_legal_moves(
+    game: *const QuartGame,
+    moves: *mut QuartMove,
+    capacity: usize,
+    count: *mut usize,
+) -> QuartStatus {
+    guard(|| {
+        let actions = legal_actions(&non_null(game)?.game);
+        *non_null_mut(count)? = actions.len();
+        if capacity > 0 {
+            non_null_mut(moves)?;
+            let moves = std::slice::from_raw_parts_mut(moves, capacity);
+            for (slot, action) in moves.iter_mut().zip(actions.iter()) {
+                *slot = to_move(action);
+            }
+        }
+        if capacity < actions.len() {
+            set_last_error(format!(
+                "{} moves do not fit in a buffer of {}",
+                actions.len(),
+                capacity
+            ));
+            return Err(QuartStatus::BufferTooSmall);
+        }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_status(
+    game: *const QuartGame,
+    out: *mut QuartGameStatus,
+) -> QuartStatus {
+    guard(|| {
+        let game = &non_null(game)?.game;
+        *non_null_mut(out)? = match game.judge_winner() {
+            Some(Player::Player1) => QuartGameStatus::Player1Won,
+            Some(Player::Player2) => QuartGameStatus::Player2Won,
+            None if game.is_game_over() => QuartGameStatus::Draw,
+            None => QuartGameStatus::InProgress,
+        };
+        Ok(())
+    })
+}
+
+/// 手番のプレイヤー（1または2）
+#[no_mangle]
+pub unsafe extern "C" fn quart_game_current_player(
+    game: *const QuartGame,
+    out: *mut u8,
+) -> QuartStatus {
+    guard(|| {
+        *non_null_mut(out)? = match non_null(game)?.game.current_player {
+            Player::Player1 => 1,
+            Player::Player2 => 2,
+        };
+        Ok(())
+    })
+}
+
+/// PolicyConfigのJSON（例: {"type": "alpha_beta", "max_time": 0.1}）からpolicyを作る
+#[no_mangle]
+pub unsafe extern "C" fn quart_policy_new(
+    config: *const c_char,
+    out: *mut *mut QuartPolicy,
+) -> QuartStatus {
+    guard(|| {
+        let config = PolicyConfig::from_json(read_str(config)?).map_err(invalid_argument)?;
+        let out = non_null_mut(out)?;
+        *out = Box::into_raw(Box::new(QuartPolicy {
+            policy: config.build(),
+        }));
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn quart_policy_free(policy: *mut QuartPolicy) {
+    if !policy.is_null() {
+        drop(Box::from_raw(policy));
+    }
+}
+
+/// policyに次の手を考えさせる．move_timeは秒で，0以下ならpolicyの設定に任せる
+#[no_mangle]
+pub unsafe extern "C" fn quart_policy_choose_move(
+    policy: *mut QuartPolicy,
+    game: *const QuartGame,
+    move_time: f64,
+    out: *mut QuartMove,
+) -> QuartStatus {
+    guard(|| {
+        let policy = &non_null(policy)?.policy;
+        let game = &non_null(game)?.game;
+        let out = non_null_mut(out)?;
+        if game.is_game_over() {
+            return Err(GameError::GameOver.into());
+        }
+        let limits = SearchLimits {
+            move_time: (move_time > 0.0).then_some(move_time),
+            ..SearchLimits::default()
+        };
+        let action = policy.action_with_limits(game, &limits, &mut |_| {});
+        game.validate_action(&action)?;
+        *out = to_move(&action);
+        Ok(())
+    })
+}
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+/// チェックインしているヘッダーがビルド時にcbindgenで生成したものと一致する
+#[test]
+fn test_header_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/quart_engine.h"));
+    let checked_in = include_str!("../include/quart_engine.h");
+    assert!(
+        generated == checked_in,
+        "include/quart_engine.h is out of date; run `cbindgen --config cbindgen.toml --output include/quart_engine.h` in capi/"
+    );
+}
+
+/// tests/test_capi.c をstaticライブラリとリンクしてビルドし，実行する
+#[test]
+fn test_c_program() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // テストのバイナリは target/<profile>/deps にあり，ライブラリはその1つ上にある
+    let target_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let library = target_dir.join("libquart_engine_c.a");
+    assert!(library.exists(), "{} not found", library.display());
+
+    let program = target_dir.join("test_capi");
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests/test_capi.c"))
+        .arg(&library)
+        .args(["-lpthread", "-ldl", "-lm"])
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .expect("Failed to run the C compiler");
+    assert!(status.success(), "Failed to compile tests/test_capi.c");
+
+    let output = Command::new(&program).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,190 @@
+/* C APIのテスト．tests/c_api.rs からビルドして実行する */
+#include <stdio.h>
+#include <string.h>
+
+#include "quart_engine.h"
+
+static int failures = 0;
+
+#define CHECK(condition)                                                   \
+  do {                                                                     \
+    if (!(condition)) {                                                    \
+      fprintf(stderr, "%s:%d: check failed: %s (last error: %s)\n",        \
+              __FILE__, __LINE__, #condition, quart_last_error());         \
+      failures++;                                                          \
+    }                                                                      \
+  } while (0)
+
+#define START_POSITION "..../..../..../.... 0"
+
+static QuartMove make_move(uint8_t row, uint8_t col, int8_t piece_index) {
+  QuartMove mv = {row, col, piece_index};
+  return mv;
+}
+
+static void test_legal_moves(void) {
+  QuartGame *game = NULL;
+  CHECK(quart_game_from_position(START_POSITION, &game) == QUART_STATUS_OK);
+
+  uint8_t player = 0;
+  CHECK(quart_game_current_player(game, &player) == QUART_STATUS_OK);
+  CHECK(player == 1);
+
+  size_t count = 0;
+  CHECK(quart_game_legal_moves(game, NULL, 0, &count) ==
+        QUART_STATUS_BUFFER_TOO_SMALL);
+  CHECK(count == 240);
+
+  QuartMove moves[QUART_MAX_LEGAL_MOVES];
+  CHECK(quart_game_legal_moves(game, moves, QUART_MAX_LEGAL_MOVES, &count) ==
+        QUART_STATUS_OK);
+  CHECK(count == 240);
+  for (size_t i = 0; i < count; i++) {
+    CHECK(quart_game_validate_move(game, moves[i]) == QUART_STATUS_OK);
+  }
+
+  quart_game_free(game);
+}
+
+static void test_illegal_moves(void) {
+  QuartGame *game = NULL;
+  CHECK(quart_game_from_position(START_POSITION, &game) == QUART_STATUS_OK);
+
+  CHECK(quart_game_validate_move(game, make_move(4, 0, 0)) ==
+        QUART_STATUS_OUT_OF_BOUNDS);
+  CHECK(quart_game_validate_move(game, make_move(0, 0, 15)) ==
+        QUART_STATUS_INVALID_PIECE_INDEX);
+  CHECK(quart_game_validate_move(game, make_move(0, 0, -1)) ==
+        QUART_STATUS_MISSING_PIECE);
+  CHECK(quart_game_play(game, make_move(0, 0, -2)) ==
+        QUART_STATUS_INVALID_ARGUMENT);
+
+  CHECK(quart_game_play(game, make_move(0, 0, 0)) == QUART_STATUS_OK);
+  CHECK(quart_game_play(game, make_move(0, 0, 0)) ==
+        QUART_STATUS_CELL_OCCUPIED);
+  CHECK(strlen(quart_last_error()) > 0);
+  CHECK(quart_game_play_notation(game, "e9:z") ==
+        QUART_STATUS_INVALID_ARGUMENT);
+
+  uint8_t player = 0;
+  CHECK(quart_game_current_player(game, &player) == QUART_STATUS_OK);
+  CHECK(player == 2);
+
+  quart_game_free(game);
+}
+
+static void test_play_until_quarto(void) {
+  QuartGame *game = NULL;
+  CHECK(quart_game_from_position(START_POSITION, &game) == QUART_STATUS_OK);
+
+  const char *moves[] = {"a1:1", "b1:2", "c1:3", "d1:4"};
+  for (size_t i = 0; i < 4; i++) {
+    QuartGameStatus status;
+    CHECK(quart_game_status(game, &status) == QUART_STATUS_OK);
+    CHECK(status == QUART_GAME_STATUS_IN_PROGRESS);
+    CHECK(quart_game_play_notation(game, moves[i]) == QUART_STATUS_OK);
+  }
+
+  QuartGameStatus status;
+  CHECK(quart_game_status(game, &status) == QUART_STATUS_OK);
+  CHECK(status == QUART_GAME_STATUS_PLAYER2_WON);
+  CHECK(quart_game_play(game, make_move(3, 3, 0)) == QUART_STATUS_GAME_OVER);
+
+  size_t count = 1;
+  CHECK(quart_game_legal_moves(game, NULL, 0, &count) == QUART_STATUS_OK);
+  CHECK(count == 0);
+
+  quart_game_free(game);
+}
+
+static void test_serialization(void) {
+  QuartGame *game = NULL;
+  CHECK(quart_game_from_position("0a3./..../..../.... 5", &game) ==
+        QUART_STATUS_OK);
+
+  char *position = NULL;
+  CHECK(quart_game_to_position(game, &position) == QUART_STATUS_OK);
+  CHECK(strcmp(position, "0a3./..../..../.... 5 2") == 0);
+
+  char *json = NULL;
+  CHECK(quart_game_to_json(game, &json) == QUART_STATUS_OK);
+  QuartGame *copy = NULL;
+  CHECK(quart_game_from_json(json, &copy) == QUART_STATUS_OK);
+  char *copy_position = NULL;
+  CHECK(quart_game_to_position(copy, &copy_position) == QUART_STATUS_OK);
+  CHECK(strcmp(position, copy_position) == 0);
+
+  char *text = NULL;
+  CHECK(quart_game_format_move(game, make_move(2, 2, 0), &text) ==
+        QUART_STATUS_OK);
+  CHECK(strcmp(text, "c3:1") == 0);
+
+  QuartGame *invalid = NULL;
+  CHECK(quart_game_from_json("{", &invalid) == QUART_STATUS_INVALID_ARGUMENT);
+  CHECK(quart_game_from_position("not a position", &invalid) ==
+        QUART_STATUS_INVALID_ARGUMENT);
+  CHECK(invalid == NULL);
+  CHECK(quart_game_from_position(NULL, &invalid) ==
+        QUART_STATUS_NULL_POINTER);
+  CHECK(quart_game_to_json(NULL, &json) == QUART_STATUS_NULL_POINTER);
+
+  quart_string_free(text);
+  quart_string_free(copy_position);
+  quart_string_free(json);
+  quart_string_free(position);
+  quart_game_free(copy);
+  quart_game_free(game);
+}
+
+static void test_policies(void) {
+  QuartPolicy *invalid = NULL;
+  CHECK(quart_policy_new("{\"type\": \"unknown\"}", &invalid) ==
+        QUART_STATUS_INVALID_ARGUMENT);
+
+  QuartPolicy *players[2] = {NULL, NULL};
+  CHECK(quart_policy_new("{\"type\": \"random\"}", &players[0]) ==
+        QUART_STATUS_OK);
+  CHECK(quart_policy_new("{\"type\": \"alpha_beta\", \"max_time\": 1.0}",
+                         &players[1]) == QUART_STATUS_OK);
+
+  QuartGame *game = NULL;
+  CHECK(quart_game_new(&game) == QUART_STATUS_OK);
+  QuartGameStatus status = QUART_GAME_STATUS_IN_PROGRESS;
+  for (int turn = 0; status == QUART_GAME_STATUS_IN_PROGRESS; turn++) {
+    QuartMove mv;
+    CHECK(quart_policy_choose_move(players[turn % 2], game, 0.01, &mv) ==
+          QUART_STATUS_OK);
+    CHECK(quart_game_play(game, mv) == QUART_STATUS_OK);
+    CHECK(quart_game_status(game, &status) == QUART_STATUS_OK);
+    CHECK(turn < 16);
+    if (turn >= 16) {
+      break;
+    }
+  }
+
+  QuartMove mv;
+  CHECK(quart_policy_choose_move(players[0], game, 0.01, &mv) ==
+        QUART_STATUS_GAME_OVER);
+
+  quart_game_free(game);
+  quart_policy_free(players[0]);
+  quart_policy_free(players[1]);
+}
+
+int main(void) {
+  printf("quart-engine %s\n", quart_version());
+  CHECK(strcmp(quart_status_message(QUART_STATUS_OK), "ok") == 0);
+
+  test_legal_moves();
+  test_illegal_moves();
+  test_play_until_quarto();
+  test_serialization();
+  test_policies();
+
+  if (failures > 0) {
+    fprintf(stderr, "%d checks failed\n", failures);
+    return 1;
+  }
+  printf("all checks passed\n");
+  return 0;
+}
@@ -0,0 +1,540 @@
+//! quart-engineのPythonバインディング．maturinでビルドする
+//!
+//! ```sh
+//! cd python
+//! maturin develop --release
+//! pytest tests
+//! ```
+//!
+//! ```python
+//! import quart_engine as qe
+//! game = qe.Game()
+//! policy = qe.Policy("alpha_beta", max_time=0.1)
+//! while not game.is_game_over():
+//!     game.play(policy.action(game))
+//! ```
+
+use numpy::ndarray::{Array1, Array3, Array4};
+use numpy::{IntoPyArray, PyArray1, PyArray3, PyArray4};
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyType;
+use quart_engine::game::action::Action;
+use quart_engine::game::{ActionList, Board, Game, Piece, Player};
+use quart_engine::policies::{Policy, PolicyConfig};
+use quart_engine::runner::{EndReason, ParallelRunner, Runner};
+use std::sync::Mutex;
+
+/// 特徴量の面の数．属性ごとの値(4 × 2) + 空きマス + 渡されている駒の属性(4)
+const NUM_PLANES: usize = 13;
+
+fn value_error(error: impl ToString) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+fn player_number(player: Player) -> u8 {
+    match player {
+        Player::Player1 => 1,
+        Player::Player2 => 2,
+    }
+}
+
+#[pyclass(name = "Piece", module = "quart_engine", eq, hash, frozen)]
+#[derive(Clone, Copy, PartialEq, Hash)]
+struct PyPiece {
+    bits: u8,
+}
+
+impl PyPiece {
+    fn piece(&self) -> Piece {
+        Piece::from_bits(self.bits)
+    }
+}
+
+impl From<Piece> for PyPiece {
+    fn from(piece: Piece) -> Self {
+        PyPiece { bits: piece.bits() }
+    }
+}
+
+#[pymethods]
+impl PyPiece {
+    /// 駒の4ビットの値（色 bit0，形 bit1，高さ bit2，表面 bit3）から作る
+    #[new]
+    fn new(bits: u8) -> PyResult<Self> {
+        if bits >= 16 {
+            return Err(value_error(format!("Piece bits must be 0-15: {}", bits)));
+        }
+        Ok(PyPiece { bits })
+    }
+
+    #[getter]
+    fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    #[getter]
+    fn color(&self) -> u8 {
+        self.piece().color()
+    }
+
+    #[getter]
+    fn shape(&self) -> u8 {
+        self.piece().shape()
+    }
+
+    #[getter]
+    fn height(&self) -> u8 {
+        self.piece().height()
+    }
+
+    #[getter]
+    fn surface(&self) -> u8 {
+        self.piece().surface()
+    }
+
+    fn __getnewargs__(&self) -> (u8,) {
+        (self.bits,)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Piece({:x})", self.bits)
+    }
+}
+
+#[pyclass(name = "Action", module = "quart_engine", eq, frozen)]
+#[derive(Clone, PartialEq)]
+struct PyAction {
+    action: Action,
+}
+
+#[pymethods]
+impl PyAction {
+    #[new]
+    #[pyo3(signature = (row, col, piece_index=None))]
+    fn new(row: usize, col: usize, piece_index: Option<usize>) -> Self {
+        PyAction {
+            action: Action {
+                row,
+                col,
+                piece_index,
+            },
+        }
+    }
+
+    #[getter]
+    fn row(&self) -> usize {
+        self.action.row
+    }
+
+    #[getter]
+    fn col(&self) -> usize {
+        self.action.col
+    }
+
+    #[getter]
+    fn piece_index(&self) -> Option<usize> {
+        self.action.piece_index
+    }
+
+    fn to_json(&self) -> String {
+        self.action.to_json()
+    }
+
+    fn __getnewargs__(&self) -> (usize, usize, Option<usize>) {
+        (self.action.row, self.action.col, self.action.piece_index)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Action(row={}, col={}, piece_index={:?})",
+            self.action.row, self.action.col, self.action.piece_index
+        )
+    }
+}
+
+#[pyclass(name = "Board", module = "quart_engine", frozen)]
+#[derive(Clone)]
+struct PyBoard {
+    board: Board,
+}
+
+#[pymethods]
+impl PyBoard {
+    fn piece_at(&self, row: usize, col: usize) -> PyResult<Option<PyPiece>> {
+        if row >= 4 || col >= 4 {
+            return Err(PyIndexError::new_err(format!("({}, {})", row, col)));
+        }
+        Ok(self.board.piece_at(row, col).map(PyPiece::from))
+    }
+
+    /// 4 × 4のリスト．空きマスはNone
+    fn to_list(&self) -> Vec<Vec<Option<PyPiece>>> {
+        self.board
+            .grid()
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.map(PyPiece::from)).collect())
+            .collect()
+    }
+
+    fn available_positions(&self) -> Vec<(usize, usize)> {
+        self.board.available_positions()
+    }
+
+    fn check_win(&self) -> bool {
+        self.board.check_win()
+    }
+
+    fn is_full(&self) -> bool {
+        self.board.is_full()
+    }
+
+    fn find_winning_cell(&self, piece: PyPiece) -> Option<(usize, usize)> {
+        self.board.find_winning_cell(piece.piece())
+    }
+}
+
+/// 対局の局面．pickleできる
+#[pyclass(name = "Game", module = "quart_engine")]
+#[derive(Clone)]
+struct PyGame {
+    game: Game,
+}
+
+#[pymethods]
+impl PyGame {
+    /// 局面の文字列から作る．省略した場合は駒をランダムに1つ渡された初期局面
+    #[new]
+    #[pyo3(signature = (position=None))]
+    fn new(position: Option<&str>) -> PyResult<Self> {
+        let game = match position {
+            Some(position) => Game::from_position_string(position).map_err(value_error)?,
+            None => Game::new(),
+        };
+        Ok(PyGame { game })
+    }
+
+    #[classmethod]
+    fn from_json(_cls: &Bound<'_, PyType>, json: &str) -> PyResult<Self> {
+        let game = serde_json::from_str(json).map_err(value_error)?;
+        Ok(PyGame { game })
+    }
+
+    fn to_json(&self) -> String {
+        self.game.to_json()
+    }
+
+    fn to_position_string(&self) -> String {
+        self.game.to_position_string()
+    }
+
+    #[getter]
+    fn board(&self) -> PyBoard {
+        PyBoard {
+            board: self.game.board,
+        }
+    }
+
+    #[getter]
+    fn available_pieces(&self) -> Vec<PyPiece> {
+        self.game
+            .available_pieces
+            .iter()
+            .map(|&piece| piece.into())
+            .collect()
+    }
+
+    #[getter]
+    fn selected_piece(&self) -> PyPiece {
+        self.game.selected_piece.into()
+    }
+
+    /// 手番のプレイヤー（1または2）
+    #[getter]
+    fn current_player(&self) -> u8 {
+        player_number(self.game.current_player)
+    }
+
+    /// 合法手．渡す駒が無い最後の手番ではpiece_indexがNoneになる
+    fn legal_actions(&self) -> Vec<PyAction> {
+        legal_actions(&self.game)
+            .iter()
+            .map(|&action| PyAction { action })
+            .collect()
+    }
+
+    /// 手を指す．不正な手ならValueError
+    fn play(&mut self, action: &PyAction) -> PyResult<()> {
+        let action = &action.action;
+        self.game
+            .play_turn(action.row, action.col, action.piece_index)
+            .map_err(value_error)
+    }
+
+    /// "c3:a"の記法で手を指す
+    fn play_move(&mut self, text: &str) -> PyResult<()> {
+        let action = self.game.parse_move(text).map_err(value_error)?;
+        self.game
+            .play_turn(action.row, action.col, action.piece_index)
+            .map_err(value_error)
+    }
+
+    fn format_move(&self, action: &PyAction) -> String {
+        self.game.format_move(&action.action)
+    }
+
+    fn is_game_over(&self) -> bool {
+        self.game.is_game_over()
+    }
+
+    /// 勝者（1または2）．引き分けや対局中ならNone
+    fn winner(&self) -> Option<u8> {
+        if !self.game.is_game_over() {
+            return None;
+        }
+        self.game.judge_winner().map(player_number)
+    }
+
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    /// 特徴量（float32，形は (13, 4, 4)）．
+    /// 0-7: 属性iが値vの駒があるセル（面 2i + v），8: 空きマス，9-12: 渡されている駒の属性（全セル同じ値）
+    fn features<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray3<f32>> {
+        Array3::from_shape_vec((NUM_PLANES, 4, 4), features(&self.game))
+            .unwrap()
+            .into_pyarray(py)
+    }
+
+    /// 渡せる駒（float32，形は (16,)．駒の4ビットの値の位置が1）
+    fn available_mask<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
+        let mut mask = vec![0.0; 16];
+        for piece in self.game.available_pieces.iter() {
+            mask[piece.bits() as usize] = 1.0;
+        }
+        Array1::from_vec(mask).into_pyarray(py)
+    }
+
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        let from_json = py.get_type::<PyGame>().getattr("from_json")?;
+        Ok((from_json, (self.game.to_json(),)))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Game({:?})", self.game.to_position_string())
+    }
+}
+
+fn legal_actions(game: &Game) -> ActionList {
+    if game.is_game_over() {
+        return ActionList::new();
+    }
+    game.action_list()
+}
+
+fn features(game: &Game) -> Vec<f32> {
+    let mut planes = vec![0.0; NUM_PLANES * 16];
+    for row in 0..4 {
+        for col in 0..4 {
+            let cell = row * 4 + col;
+            match game.board.piece_at(row, col) {
+                Some(piece) => {
+                    for attribute in 0..4 {
+                        let value = ((piece.bits() >> attribute) & 1) as usize;
+                        planes[(2 * attribute + value) * 16 + cell] = 1.0;
+                    }
+                }
+                None => planes[8 * 16 + cell] = 1.0,
+            }
+            for attribute in 0..4 {
+                let value = (game.selected_piece.bits() >> attribute) & 1;
+                planes[(9 + attribute) * 16 + cell] = value as f32;
+            }
+        }
+    }
+    planes
+}
+
+/// 複数の局面の特徴量をまとめる（float32，形は (N, 13, 4, 4)）
+#[pyfunction]
+fn features_batch<'py>(
+    py: Python<'py>,
+    games: Vec<PyRef<'py, PyGame>>,
+) -> Bound<'py, PyArray4<f32>> {
+    let data: Vec<f32> = games.iter().flat_map(|game| features(&game.game)).collect();
+    Array4::from_shape_vec((games.len(), NUM_PLANES, 4, 4), data)
+        .unwrap()
+        .into_pyarray(py)
+}
+
+/// 組み込みのpolicy．PolicyConfigと同じ名前とパラメータで作る．pickleできる
+#[pyclass(name = "Policy", module = "quart_engine")]
+struct PyPolicy {
+    config: PolicyConfig,
+    policy: Mutex<Box<dyn Policy>>,
+}
+
+impl PyPolicy {
+    fn from_config(config: PolicyConfig) -> Self {
+        PyPolicy {
+            policy: Mutex::new(config.build()),
+            config,
+        }
+    }
+}
+
+#[pymethods]
+impl PyPolicy {
+    /// 例: Policy("alpha_beta", max_time=0.1), Policy("mcs", max_time=0.05), Policy("random")
+    #[new]
+    #[pyo3(signature = (kind, **params))]
+    fn new(kind: &str, params: Option<&Bound<'_, pyo3::types::PyDict>>) -> PyResult<Self> {
+        let mut json = serde_json::Map::new();
+        json.insert("type".to_string(), kind.into());
+        if let Some(params) = params {
+            for (key, value) in params.iter() {
+                let key: String = key.extract()?;
+                let value = if let Ok(value) = value.extract::<i64>() {
+                    serde_json::Value::from(value)
+                } else if let Ok(value) = value.extract::<f64>() {
+                    serde_json::Value::from(value)
+                } else if let Ok(value) = value.extract::<String>() {
+                    serde_json::Value::from(value)
+                } else if value.is_none() {
+                    serde_json::Value::Null
+                } else {
+                    return Err(value_error(format!("Unsupported parameter: {}", key)));
+                };
+                json.insert(key, value);
+            }
+        }
+        let config = serde_json::from_value(json.into()).map_err(value_error)?;
+        Ok(PyPolicy::from_config(config))
+    }
+
+    #[classmethod]
+    fn from_json(_cls: &Bound<'_, PyType>, json: &str) -> PyResult<Self> {
+        Ok(PyPolicy::from_config(
+            PolicyConfig::from_json(json).map_err(value_error)?,
+        ))
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(&self.config).unwrap()
+    }
+
+    /// 次の手を考える．考えている間はGILを手放す
+    fn action(&self, py: Python<'_>, game: &PyGame) -> PyResult<PyAction> {
+        if game.game.is_game_over() {
+            return Err(value_error("Game is already over"));
+        }
+        let game = game.game.clone();
+        let action = py.detach(|| self.policy.lock().unwrap().action(&game));
+        Ok(PyAction { action })
+    }
+
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        let from_json = py.get_type::<PyPolicy>().getattr("from_json")?;
+        Ok((from_json, (self.to_json(),)))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Policy.from_json({:?})", self.to_json())
+    }
+}
+
+/// 対局の結果
+#[pyclass(name = "GameResult", module = "quart_engine", get_all, frozen)]
+struct PyGameResult {
+    /// 勝者（1または2）．引き分けならNone
+    winner: Option<u8>,
+    /// "quarto", "draw", "forfeit"のいずれか
+    reason: String,
+    /// 反則負けの詳細
+    detail: Option<String>,
+    /// 終局した局面
+    game: PyGame,
+}
+
+/// 2つのpolicyを対局させる
+#[pyclass(name = "Runner", module = "quart_engine")]
+struct PyRunner {
+    player1: PolicyConfig,
+    player2: PolicyConfig,
+    /// 1手の制限時間（秒）
+    #[pyo3(get, set)]
+    move_time_limit: Option<f64>,
+}
+
+#[pymethods]
+impl PyRunner {
+    #[new]
+    #[pyo3(signature = (player1, player2, move_time_limit=None))]
+    fn new(player1: &PyPolicy, player2: &PyPolicy, move_time_limit: Option<f64>) -> Self {
+        PyRunner {
+            player1: player1.config.clone(),
+            player2: player2.config.clone(),
+            move_time_limit,
+        }
+    }
+
+    /// 1局対局する．positionを指定するとその局面から始める
+    #[pyo3(signature = (position=None))]
+    fn run_game(&self, py: Python<'_>, position: Option<&str>) -> PyResult<PyGameResult> {
+        let game = match position {
+            Some(position) => Game::from_position_string(position).map_err(value_error)?,
+            None => Game::new(),
+        };
+        let (player1, player2) = (self.player1.clone(), self.player2.clone());
+        let move_time_limit = self.move_time_limit;
+        let (result, game) = py.detach(move || {
+            let mut runner = Runner::with_game(game, player1.build(), player2.build());
+            runner.move_time_limit = move_time_limit;
+            let result = runner.run_game();
+            (result, runner.game)
+        });
+        let (reason, detail) = match result.reason {
+            EndReason::Quarto => ("quarto", None),
+            EndReason::Draw => ("draw", None),
+            EndReason::Forfeit { reason, .. } => ("forfeit", Some(format!("{:?}", reason))),
+        };
+        Ok(PyGameResult {
+            winner: result.winner.map(player_number),
+            reason: reason.to_string(),
+            detail,
+            game: PyGame { game },
+        })
+    }
+
+    /// n_games局を並列に対局し，各局の勝者（1，2またはNone）を返す
+    fn run(&self, py: Python<'_>, n_games: usize) -> Vec<Option<u8>> {
+        let (player1, player2) = (self.player1.clone(), self.player2.clone());
+        let winners = py.detach(move || {
+            ParallelRunner::new(
+                Box::new(move || player1.build()),
+                Box::new(move || player2.build()),
+            )
+            .run(n_games)
+        });
+        winners
+            .into_iter()
+            .map(|winner| winner.map(player_number))
+            .collect()
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "quart_engine")]
+fn quart_engine_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPiece>()?;
+    m.add_class::<PyAction>()?;
+    m.add_class::<PyBoard>()?;
+    m.add_class::<PyGame>()?;
+    m.add_class::<PyPolicy>()?;
+    m.add_class::<PyGameResult>()?;
+    m.add_class::<PyRunner>()?;
+    m.add_function(wrap_pyfunction!(features_batch, m)?)?;
+    m.add("NUM_PLANES", NUM_PLANES)?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+import pickle
+
+import pytest
+
+import quart_engine as qe
+
+
+def test_piece_attributes():
+    piece = qe.Piece(0b1010)
+    assert (piece.color, piece.shape, piece.height, piece.surface) == (0, 1, 0, 1)
+    assert qe.Piece(3) == qe.Piece(3)
+    assert len({qe.Piece(3), qe.Piece(3), qe.Piece(4)}) == 2
+    with pytest.raises(ValueError):
+        qe.Piece(16)
+
+
+def test_game_from_position_string():
+    game = qe.Game("0a3./..../..../.... 5")
+    assert game.selected_piece == qe.Piece(5)
+    assert game.board.piece_at(0, 1) == qe.Piece(0xA)
+    assert game.board.piece_at(1, 1) is None
+    assert game.current_player == 2
+    assert len(game.available_pieces) == 12
+    with pytest.raises(ValueError):
+        qe.Game("not a position")
+
+
+def test_legal_actions_and_play():
+    game = qe.Game("..../..../..../.... 0")
+    actions = game.legal_actions()
+    assert len(actions) == 16 * 15
+    game.play(actions[0])
+    assert game.current_player == 2
+    with pytest.raises(ValueError):
+        game.play(actions[0])  # 埋まっているセルには置けない
+    game.play_move("d4:3")
+    assert game.to_position_string().startswith("0...")
+
+
+def test_last_move_has_no_piece():
+    game = qe.Game("0123/4567/89ab/cde. f")
+    assert game.is_game_over()  # 1行目が揃っている
+    assert game.legal_actions() == []
+    game = qe.Game("0156/2347/89cd/abe. f")
+    actions = game.legal_actions()
+    assert all(action.piece_index is None for action in actions)
+
+
+def test_pickle_round_trip():
+    game = qe.Game("0a3./..../..../.... 5")
+    loaded = pickle.loads(pickle.dumps(game))
+    assert loaded.to_json() == game.to_json()
+    action = qe.Action(1, 2, 3)
+    assert pickle.loads(pickle.dumps(action)) == action
+    assert pickle.loads(pickle.dumps(qe.Piece(7))) == qe.Piece(7)
+    policy = qe.Policy("alpha_beta", max_time=0.01)
+    assert pickle.loads(pickle.dumps(policy)).to_json() == policy.to_json()
+
+
+def test_policies_play_legal_moves():
+    for policy in [
+        qe.Policy("random"),
+        qe.Policy("one_step_look_ahead"),
+        qe.Policy("mcs", max_time=0.005),
+        qe.Policy("alpha_beta", max_time=0.005, max_depth=2),
+    ]:
+        game = qe.Game()
+        while not game.is_game_over():
+            game.play(policy.action(game))
+    with pytest.raises(ValueError):
+        qe.Policy("unknown")
+
+
+def test_runner():
+    runner = qe.Runner(qe.Policy("one_step_look_ahead"), qe.Policy("random"))
+    result = runner.run_game()
+    assert result.reason in ("quarto", "draw")
+    assert result.game.is_game_over()
+    winners = runner.run(4)
+    assert len(winners) == 4
+    assert all(winner in (1, 2, None) for winner in winners)
+
+
+def test_features():
+    np = pytest.importorskip("numpy")
+    game = qe.Game("0a3./..../..../.... 5")
+    features = game.features()
+    assert features.shape == (qe.NUM_PLANES, 4, 4)
+    assert features.dtype == np.float32
+    assert features[8].sum() == 13  # 空きマス
+    assert features[0, 0, 0] == 1  # 駒0は色の値が0
+    assert features[9].sum() == 16  # 渡されている駒5は色の値が1
+    assert game.available_mask().sum() == 12
+
+    batch = qe.features_batch([game, qe.Game()])
+    assert batch.shape == (2, qe.NUM_PLANES, 4, 4)
+    assert (batch[0] == features).all()
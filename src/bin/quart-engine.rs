@@ -0,0 +1,16 @@
+use quart_engine::engine::Engine;
+use quart_engine::evaluators::HandcraftedEvaluator;
+use quart_engine::policies::{AlphaBetaPolicy, Policy};
+use std::io::{stdin, stdout};
+
+// 標準入出力でQEIプロトコルを話すエンジン．policyはsetoption name Policyで変更できる
+fn main() {
+    let mut engine = Engine::new(
+        Box::new(|| Box::new(AlphaBetaPolicy::<HandcraftedEvaluator>::new())),
+        stdout(),
+    );
+    if let Err(error) = engine.run(stdin().lock()) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,32 @@
+use quart_engine::cli::Repl;
+use quart_engine::engine::START_POSITION;
+use quart_engine::game::Game;
+use std::io::{stdin, stdout};
+use std::process::exit;
+
+const USAGE: &str = "\
+Usage: quart-repl [--position <string>]
+
+Options:
+  --position <string>  start from a position string (default: the empty board with piece 0 to place)
+";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let position = match args.as_slice() {
+        [] => START_POSITION.to_string(),
+        [option, position] if option == "--position" => position.clone(),
+        _ => {
+            eprint!("{}", USAGE);
+            exit(1);
+        }
+    };
+    let game = Game::from_position_string(&position).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(1);
+    });
+    if let Err(error) = Repl::new(game).run(stdin().lock(), &mut stdout()) {
+        eprintln!("{}", error);
+        exit(1);
+    }
+}
@@ -0,0 +1,50 @@
+use quart_engine::server::{router, AppState};
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::Arc;
+
+const USAGE: &str = "\
+Usage: quart-server [--addr <host:port>] [--store <dir>]
+
+Options:
+  --addr <host:port>  address to listen on (default: 127.0.0.1:8080)
+  --store <dir>       save games as JSON files in this directory and load them on startup
+";
+
+#[tokio::main]
+async fn main() {
+    let mut addr = "127.0.0.1:8080".to_string();
+    let mut store = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--addr", Some(value)) => addr = value,
+            ("--store", Some(value)) => store = Some(PathBuf::from(value)),
+            _ => {
+                eprint!("{}", USAGE);
+                exit(1);
+            }
+        }
+    }
+
+    let state = match store {
+        Some(store) => AppState::with_store(store).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            exit(1);
+        }),
+        None => AppState::new(),
+    };
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Failed to listen on {}: {}", addr, error);
+            exit(1);
+        });
+    println!("Listening on http://{}", addr);
+    axum::serve(listener, router(Arc::new(state)))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .unwrap();
+}
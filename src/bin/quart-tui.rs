@@ -0,0 +1,79 @@
+use quart_engine::cli::GameRecord;
+use quart_engine::game::Game;
+use quart_engine::tui::{ui, App};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use std::process::exit;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: quart-tui [options]
+
+Options:
+  --load <path>        review a saved game (from quart play)
+  --position <string>  start from a position string, e.g. \"0a3./..../..../.... 5\"
+  --save <path>        file to save the game to with the s key (default: the loaded file)
+  --analysis           start the analysis immediately
+";
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut load = None;
+    let mut position = None;
+    let mut save = None;
+    let mut analysis = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--load" => load = Some(value()?),
+            "--position" => position = Some(value()?),
+            "--save" => save = Some(value()?),
+            "--analysis" => analysis = true,
+            "help" | "--help" | "-h" => {
+                print!("{}", USAGE);
+                return Ok(());
+            }
+            _ => return Err(format!("Unknown option: {}\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let mut app = match (&load, position) {
+        (Some(path), _) => App::from_record(&GameRecord::load(path)?)?,
+        (None, Some(position)) => App::new(Game::from_position_string(&position)?),
+        (None, None) => App::new(Game::new()),
+    };
+    if let Some(path) = save.or(load) {
+        app = app.with_save_path(&path);
+    }
+    app.set_analysis(analysis);
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result.map_err(|e| e.to_string())
+}
+
+fn event_loop(terminal: &mut ratatui::DefaultTerminal, app: &mut App) -> std::io::Result<()> {
+    while !app.should_quit() {
+        app.poll_analysis();
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        // 解析の結果を表示し直すために，キー入力が無くても定期的に描き直す
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+    }
+    Ok(())
+}
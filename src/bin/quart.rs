@@ -0,0 +1,79 @@
+use quart_engine::cli::{opponent_config, GameRecord, PlayOptions, PlaySession};
+use quart_engine::game::Game;
+use quart_engine::policies::PolicyConfig;
+use std::io::{stdin, stdout};
+use std::process::exit;
+
+const USAGE: &str = "\
+Usage: quart play [options]
+
+Options:
+  --opponent <name>    random, one-step, two-step, mcs, alpha-beta or human (default: alpha-beta)
+  --strength <1-5>     thinking time of mcs and alpha-beta (default: 3)
+  --engine-first       let the engine move first
+  --position <string>  start from a position string, e.g. \"0a3./..../..../.... 5\"
+  --load <path>        continue a saved game
+";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(|arg| arg.as_str()) {
+        Some("play") => play(&args[1..]),
+        Some("help" | "--help" | "-h") => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        exit(1);
+    }
+}
+
+fn play(args: &[String]) -> Result<(), String> {
+    let mut opponent = "alpha-beta".to_string();
+    let mut strength = 3;
+    let mut human_first = true;
+    let mut position = None;
+    let mut load = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--opponent" => opponent = value()?,
+            "--strength" => {
+                strength = value()?
+                    .parse()
+                    .map_err(|_| "Strength must be a number".to_string())?
+            }
+            "--engine-first" => human_first = false,
+            "--position" => position = Some(value()?),
+            "--load" => load = Some(value()?),
+            _ => return Err(format!("Unknown option: {}\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let options = PlayOptions {
+        opponent: opponent_config(&opponent, strength)?,
+        human_first,
+        hint: PolicyConfig::AlphaBeta {
+            max_time: 0.5,
+            max_depth: 16,
+            weights: Default::default(),
+        },
+    };
+    let mut session = match (load, position) {
+        (Some(path), _) => PlaySession::from_record(&GameRecord::load(&path)?, options)?,
+        (None, Some(position)) => PlaySession::new(Game::from_position_string(&position)?, options),
+        (None, None) => PlaySession::new(Game::new(), options),
+    };
+    session
+        .run(stdin().lock(), &mut stdout())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,42 @@
+use crate::book::OpeningBook;
+use crate::game::Game;
+use crate::policies::policy::Policy;
+
+/// policy同士の自己対局をn_games局行い，最初のmax_plies手で選ばれた手を定跡にする．
+/// 時間をかけて探索するpolicyを使うと，探索の結果が手の重み（選ばれた回数）として定跡に残る
+pub fn generate_from_self_play<P: Policy>(
+    policy: &P,
+    n_games: usize,
+    max_plies: usize,
+) -> OpeningBook {
+    let mut book = OpeningBook::new();
+    for _ in 0..n_games {
+        let mut game = Game::new();
+        for _ in 0..max_plies {
+            if game.is_game_over() {
+                break;
+            }
+            let action = policy.action(&game);
+            book.add(&game, &action, 1.0);
+            game.play_turn(action.row, action.col, action.piece_index)
+                .unwrap();
+        }
+    }
+    book
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::OneStepLookAheadPolicy;
+
+    #[test]
+    fn test_generate_from_self_play() {
+        let book = generate_from_self_play(&OneStepLookAheadPolicy::new(), 3, 2);
+        assert!(!book.is_empty(), "定跡に局面が追加されるはず");
+        assert!(
+            !book.moves(&Game::new()).is_empty(),
+            "最初の局面はどれも対称なので定跡に載っているはず"
+        );
+    }
+}
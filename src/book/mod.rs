@@ -0,0 +1,278 @@
+pub mod generator;
+
+pub use generator::generate_from_self_play;
+
+use crate::game::action::Action;
+use crate::game::symmetry::canonical_key;
+use crate::game::{Game, Piece};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 定跡の手．対称性で同一視した代表の局面(canonical_key)での置くセルと渡す駒で持つ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookMove {
+    /// 置くセル(row * 4 + col)
+    pub cell: u8,
+    /// 渡す駒の4ビットの値．渡す駒が無い場合はNone
+    pub piece: Option<u8>,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BookEntry {
+    // canonical_keyの16進数表記
+    key: String,
+    moves: Vec<BookMove>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BookFile {
+    entries: Vec<BookEntry>,
+}
+
+/// 局面ごとに候補手と重みを持つ定跡
+#[derive(Debug, Clone, Default)]
+pub struct OpeningBook {
+    entries: HashMap<u128, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        OpeningBook::default()
+    }
+
+    /// 定跡に載っている局面の数（対称な局面は1つと数える）
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 局面での手を重み付きで追加する．同じ手が既にあれば重みを足す
+    pub fn add(&mut self, game: &Game, action: &Action, weight: f64) {
+        let (key, symmetry) = canonical_key(game);
+        let (row, col) = symmetry.map_cell(action.row, action.col);
+        let piece = action
+            .piece_index
+            .map(|index| symmetry.map_piece(game.available_pieces[index]).bits());
+        let book_move = BookMove {
+            cell: (row * 4 + col) as u8,
+            piece,
+            weight,
+        };
+        self.insert(key, book_move);
+    }
+
+    fn insert(&mut self, key: u128, book_move: BookMove) {
+        let moves = self.entries.entry(key).or_default();
+        match moves
+            .iter_mut()
+            .find(|m| m.cell == book_move.cell && m.piece == book_move.piece)
+        {
+            Some(existing) => existing.weight += book_move.weight,
+            None => moves.push(book_move),
+        }
+    }
+
+    /// 別の定跡を取り込む．同じ局面の同じ手は重みを足す
+    pub fn merge(&mut self, other: &OpeningBook) {
+        for (&key, moves) in other.entries.iter() {
+            for book_move in moves.iter() {
+                self.insert(key, book_move.clone());
+            }
+        }
+    }
+
+    /// 局面に載っている手をこの局面でのActionに直して重みと一緒に返す
+    pub fn moves(&self, game: &Game) -> Vec<(Action, f64)> {
+        let (key, symmetry) = canonical_key(game);
+        let Some(moves) = self.entries.get(&key) else {
+            return vec![];
+        };
+        let inverse = symmetry.inverse();
+        moves
+            .iter()
+            .filter_map(|book_move| {
+                let (row, col) =
+                    inverse.map_cell(book_move.cell as usize / 4, book_move.cell as usize % 4);
+                let piece_index = match book_move.piece {
+                    Some(bits) => {
+                        let piece = inverse.map_piece(Piece::from_bits(bits));
+                        Some(game.available_pieces.iter().position(|&p| p == piece)?)
+                    }
+                    None => None,
+                };
+                Some((
+                    Action {
+                        row,
+                        col,
+                        piece_index,
+                    },
+                    book_move.weight,
+                ))
+            })
+            .collect()
+    }
+
+    /// 重みに比例する確率で手を選ぶ．重みはtemperature乗根をとってから使い，
+    /// temperatureが0なら最も重い手を選ぶ．局面が載っていなければNone
+    pub fn sample<R: Rng>(&self, game: &Game, temperature: f64, rng: &mut R) -> Option<Action> {
+        let moves: Vec<(Action, f64)> = self
+            .moves(game)
+            .into_iter()
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+        if moves.is_empty() {
+            return None;
+        }
+        if temperature <= 0.0 {
+            return moves
+                .into_iter()
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(action, _)| action);
+        }
+
+        let weights: Vec<f64> = moves
+            .iter()
+            .map(|(_, weight)| weight.powf(1.0 / temperature))
+            .collect();
+        let mut threshold = rng.gen::<f64>() * weights.iter().sum::<f64>();
+        for ((action, _), weight) in moves.iter().zip(weights.iter()) {
+            if threshold < *weight {
+                return Some(*action);
+            }
+            threshold -= weight;
+        }
+        moves.last().map(|(action, _)| *action)
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut entries: Vec<BookEntry> = self
+            .entries
+            .iter()
+            .map(|(key, moves)| BookEntry {
+                key: format!("{:x}", key),
+                moves: moves.clone(),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        serde_json::to_string(&BookFile { entries }).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let file: BookFile = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let mut book = OpeningBook::new();
+        for entry in file.entries {
+            let key = u128::from_str_radix(&entry.key, 16).map_err(|e| e.to_string())?;
+            for book_move in entry.moves {
+                book.insert(key, book_move);
+            }
+        }
+        Ok(book)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_json()).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::symmetry::Symmetry;
+    use crate::utils::rng;
+
+    #[test]
+    fn test_opening_book_finds_symmetric_position() {
+        let game = Game::new();
+        let action = Action {
+            row: 1,
+            col: 2,
+            piece_index: Some(3),
+        };
+        let mut book = OpeningBook::new();
+        book.add(&game, &action, 1.0);
+
+        // 対称な局面でも，対応する手が見つかるはず
+        let symmetry = Symmetry::all()[1234];
+        let transformed = symmetry.apply(&game);
+        let moves = book.moves(&transformed);
+        assert_eq!(moves.len(), 1, "対称な局面も定跡に載っているはず");
+        // 空の盤面のように局面自体が対称だと対応する手は1つに決まらないので，指した後の局面で比べる
+        let (found, _) = &moves[0];
+        let mut expected = game.clone();
+        expected
+            .play_turn(action.row, action.col, action.piece_index)
+            .unwrap();
+        let mut actual = transformed.clone();
+        actual
+            .play_turn(found.row, found.col, found.piece_index)
+            .unwrap();
+        assert_eq!(
+            canonical_key(&actual).0,
+            canonical_key(&expected).0,
+            "対応する手を指すはず"
+        );
+    }
+
+    #[test]
+    fn test_opening_book_merge_and_sample() {
+        let game = Game::new();
+        let heavy = Action {
+            row: 0,
+            col: 0,
+            piece_index: Some(0),
+        };
+        let light = Action {
+            row: 1,
+            col: 1,
+            piece_index: Some(0),
+        };
+        let mut book = OpeningBook::new();
+        book.add(&game, &heavy, 3.0);
+        let mut other = OpeningBook::new();
+        other.add(&game, &heavy, 2.0);
+        other.add(&game, &light, 1.0);
+        book.merge(&other);
+
+        let mut moves = book.moves(&game);
+        moves.sort_by(|a, b| b.1.total_cmp(&a.1));
+        assert_eq!(moves.len(), 2, "同じ手はまとめられるはず");
+        assert_eq!(moves[0].1, 5.0, "同じ手の重みは足されるはず");
+
+        let best = book.sample(&game, 0.0, &mut rng()).unwrap();
+        assert_eq!((best.row, best.col), (moves[0].0.row, moves[0].0.col));
+    }
+
+    #[test]
+    fn test_opening_book_json_round_trip() {
+        let game = Game::new();
+        let action = Action {
+            row: 2,
+            col: 3,
+            piece_index: Some(5),
+        };
+        let mut book = OpeningBook::new();
+        book.add(&game, &action, 1.5);
+
+        let loaded = OpeningBook::from_json(&book.to_json()).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            loaded.moves(&game),
+            book.moves(&game),
+            "読み込んでも同じ手になるはず"
+        );
+        assert!(
+            OpeningBook::from_json("{}").is_err(),
+            "不正なファイルはエラー"
+        );
+    }
+}
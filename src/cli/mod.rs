@@ -0,0 +1,106 @@
+pub mod play;
+pub mod repl;
+
+pub use play::{GameRecord, PlayOptions, PlaySession};
+pub use repl::Repl;
+
+use crate::evaluators::HandcraftedWeights;
+use crate::game::{Game, Piece};
+use crate::policies::{Allocation, PolicyConfig};
+
+/// 駒を属性の頭文字4文字で表す．色 B(黒)/W(白)，形 R(丸)/Q(四角)，高さ T(高)/S(低)，表面 H(穴あり)/F(穴なし)
+pub fn piece_name(piece: Piece) -> String {
+    [
+        if piece.color() == 0 { 'B' } else { 'W' },
+        if piece.shape() == 0 { 'R' } else { 'Q' },
+        if piece.height() == 0 { 'T' } else { 'S' },
+        if piece.surface() == 0 { 'H' } else { 'F' },
+    ]
+    .iter()
+    .collect()
+}
+
+/// 盤面と渡されている駒，渡せる駒を表示用の文字列にする
+pub fn render_game(game: &Game) -> String {
+    let mut text = String::from("     a      b      c      d\n");
+    for (row, cells) in game.board.grid().iter().enumerate() {
+        text += &format!("{} ", row + 1);
+        for cell in cells.iter() {
+            match cell {
+                Some(piece) => text += &format!(" {}:{:x}", piece_name(*piece), piece.bits()),
+                None => text += "   .   ",
+            }
+        }
+        text += "\n";
+    }
+    text += &format!(
+        "Piece to place: {}:{:x}\n",
+        piece_name(game.selected_piece),
+        game.selected_piece.bits()
+    );
+    let pieces: Vec<String> = game
+        .available_pieces
+        .iter()
+        .map(|piece| format!("{}:{:x}", piece_name(*piece), piece.bits()))
+        .collect();
+    text += &format!("Pieces to give: {}\n", pieces.join(" "));
+    text
+}
+
+/// 対戦相手の名前と強さ(1-5)からpolicyの設定を作る．強さは探索するpolicyの1手の思考時間になる．
+/// 人間が指す場合はNone
+pub fn opponent_config(name: &str, strength: u32) -> Result<Option<PolicyConfig>, String> {
+    let max_time = match strength {
+        1 => 0.05,
+        2 => 0.2,
+        3 => 0.5,
+        4 => 1.0,
+        5 => 3.0,
+        _ => return Err(format!("Strength must be 1-5: {}", strength)),
+    };
+    let config = match name {
+        "human" => return Ok(None),
+        "random" => PolicyConfig::Random,
+        "one-step" => PolicyConfig::OneStepLookAhead,
+        "two-step" => PolicyConfig::TwoStepLookAhead,
+        "mcs" => PolicyConfig::Mcs {
+            max_time,
+            play_out_depth: None,
+            max_play_outs: None,
+            allocation: Allocation::default(),
+            weights: HandcraftedWeights::default(),
+        },
+        "alpha-beta" => PolicyConfig::AlphaBeta {
+            max_time,
+            max_depth: 16,
+            weights: HandcraftedWeights::default(),
+        },
+        _ => return Err(format!("Unknown opponent: {}", name)),
+    };
+    Ok(Some(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_game() {
+        let game = Game::from_position_string("0a3./..../..../.... 5").unwrap();
+        let text = render_game(&game);
+        assert!(text.contains("1  BRTH:0 BQTF:a WQTH:3"), "{}", text);
+        assert!(text.contains("Piece to place: WRSH:5"));
+        assert_eq!(piece_name(Piece::from_bits(15)), "WQSF");
+    }
+
+    #[test]
+    fn test_opponent_config() {
+        assert_eq!(opponent_config("human", 3), Ok(None));
+        assert!(matches!(
+            opponent_config("alpha-beta", 1),
+            Ok(Some(PolicyConfig::AlphaBeta { max_time, .. })) if max_time == 0.05
+        ));
+        assert!(opponent_config("unknown", 3).is_err());
+        assert!(opponent_config("mcs", 6).is_err());
+    }
+}
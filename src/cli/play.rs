@@ -0,0 +1,346 @@
+use crate::cli::render_game;
+use crate::game::{Game, Player};
+use crate::policies::{Policy, PolicyConfig};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+
+const HELP: &str = "\
+Commands:
+  <move>       place the piece and give one, e.g. c3:a (column a-d, row 1-4, piece 0-f)
+  undo         take back the last move (and the engine's reply)
+  hint         ask the engine for a move
+  board        show the board
+  save <path>  save the game to a file
+  help         show this help
+  quit         exit
+";
+
+/// 保存する棋譜．開始局面の文字列と手の文字列の列
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub start: String,
+    pub moves: Vec<String>,
+}
+
+impl GameRecord {
+    /// 開始局面から手を順に指し，各手の後の局面を開始局面も含めて返す
+    pub fn replay(&self) -> Result<Vec<Game>, String> {
+        let mut games = vec![Game::from_position_string(&self.start)?];
+        for text in self.moves.iter() {
+            let mut game = games.last().unwrap().clone();
+            let action = game.parse_move(text)?;
+            game.play_turn(action.row, action.col, action.piece_index)
+                .map_err(|e| format!("{}: {}", text, e))?;
+            games.push(game);
+        }
+        Ok(games)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+}
+
+pub struct PlayOptions {
+    /// 対戦相手のpolicy．Noneなら人間同士で対局する
+    pub opponent: Option<PolicyConfig>,
+    /// 開始局面で手番の側を人間が持つか
+    pub human_first: bool,
+    /// ヒントに使うpolicy
+    pub hint: PolicyConfig,
+}
+
+/// 端末で人間が対局するためのセッション
+pub struct PlaySession {
+    // 開始局面からの各手の後の局面．最後が現在の局面
+    history: Vec<Game>,
+    moves: Vec<String>,
+    opponent: Option<Box<dyn Policy>>,
+    engine_player: Option<Player>,
+    hint: Box<dyn Policy>,
+}
+
+impl PlaySession {
+    pub fn new(start: Game, options: PlayOptions) -> Self {
+        let engine_player = options.opponent.as_ref().map(|_| {
+            if options.human_first {
+                start.current_player.opponent()
+            } else {
+                start.current_player
+            }
+        });
+        PlaySession {
+            history: vec![start],
+            moves: vec![],
+            opponent: options.opponent.map(|config| config.build()),
+            engine_player,
+            hint: options.hint.build(),
+        }
+    }
+
+    /// 保存した棋譜の続きから対局する．人間が持つ側は開始局面で決める
+    pub fn from_record(record: &GameRecord, options: PlayOptions) -> Result<Self, String> {
+        let games = record.replay()?;
+        let mut session = PlaySession::new(games[0].clone(), options);
+        session.history = games;
+        session.moves = record.moves.clone();
+        Ok(session)
+    }
+
+    pub fn game(&self) -> &Game {
+        self.history.last().unwrap()
+    }
+
+    pub fn record(&self) -> GameRecord {
+        GameRecord {
+            start: self.history[0].to_position_string(),
+            moves: self.moves.clone(),
+        }
+    }
+
+    /// inputからコマンドを読んで対局を進め，outputに盤面や結果を書く．quitかinputの終わりで終了する
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> std::io::Result<()> {
+        write!(output, "{}", HELP)?;
+        let mut lines = input.lines();
+        let mut show_board = true;
+        loop {
+            if !self.game().is_game_over() && self.is_engine_turn() {
+                match self.play_engine_move() {
+                    Ok(text) => writeln!(output, "Engine plays {}", text)?,
+                    Err(error) => {
+                        // 同じ局面で聞き直しても同じ手を返しかねないので，以降は人間が両方の手を指す
+                        writeln!(output, "Engine played an illegal move: {}", error)?;
+                        writeln!(
+                            output,
+                            "The engine is disabled; enter moves for both players"
+                        )?;
+                        self.opponent = None;
+                        self.engine_player = None;
+                    }
+                }
+                show_board = true;
+                continue;
+            }
+            if show_board {
+                write!(output, "\n{}", render_game(self.game()))?;
+                if self.game().is_game_over() {
+                    writeln!(output, "{}", self.result_message())?;
+                }
+                show_board = false;
+            }
+
+            write!(
+                output,
+                "{}> ",
+                self.player_label(self.game().current_player)
+            )?;
+            output.flush()?;
+            let Some(line) = lines.next() else {
+                writeln!(output)?;
+                break;
+            };
+            let line = line?;
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (None, _) => {}
+                (Some("quit" | "exit"), _) => break,
+                (Some("help"), _) => write!(output, "{}", HELP)?,
+                (Some("board"), _) => show_board = true,
+                (Some("undo"), _) => {
+                    if self.undo() {
+                        show_board = true;
+                    } else {
+                        writeln!(output, "Nothing to undo")?;
+                    }
+                }
+                (Some("hint"), _) => {
+                    if self.game().is_game_over() {
+                        writeln!(output, "The game is over")?;
+                    } else {
+                        let action = self.hint.action(self.game());
+                        writeln!(output, "Hint: {}", self.game().format_move(&action))?;
+                    }
+                }
+                (Some("save"), Some(path)) => match self.record().save(path) {
+                    Ok(()) => writeln!(output, "Saved to {}", path)?,
+                    Err(error) => writeln!(output, "Failed to save: {}", error)?,
+                },
+                (Some("save"), None) => writeln!(output, "Usage: save <path>")?,
+                (Some(text), _) => match self.play(text) {
+                    Ok(()) => show_board = true,
+                    Err(error) => writeln!(output, "Illegal move: {}", error)?,
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn is_engine_turn(&self) -> bool {
+        self.engine_player == Some(self.game().current_player)
+    }
+
+    // エンジンに手を考えさせて指し，指した手を返す
+    fn play_engine_move(&mut self) -> Result<String, String> {
+        let opponent = self.opponent.as_ref().ok_or("No engine")?;
+        let action = opponent.action(self.game());
+        self.game()
+            .validate_action(&action)
+            .map_err(|e| format!("{:?}: {}", action, e))?;
+        let text = self.game().format_move(&action);
+        self.play(&text)?;
+        Ok(text)
+    }
+
+    fn play(&mut self, text: &str) -> Result<(), String> {
+        let mut game = self.game().clone();
+        let action = game.parse_move(text)?;
+        let text = game.format_move(&action);
+        game.play_turn(action.row, action.col, action.piece_index)?;
+        self.moves.push(text);
+        self.history.push(game);
+        Ok(())
+    }
+
+    // 人間の手番に戻るまで手を戻す．戻せる手が無ければfalse
+    fn undo(&mut self) -> bool {
+        if self.moves.is_empty() {
+            return false;
+        }
+        loop {
+            self.moves.pop();
+            self.history.pop();
+            if self.moves.is_empty() || !self.is_engine_turn() {
+                return true;
+            }
+        }
+    }
+
+    fn player_label(&self, player: Player) -> String {
+        let name = match player {
+            Player::Player1 => "Player1",
+            Player::Player2 => "Player2",
+        };
+        match self.engine_player {
+            Some(engine) if engine == player => format!("{} (engine)", name),
+            Some(_) => format!("{} (you)", name),
+            None => name.to_string(),
+        }
+    }
+
+    fn result_message(&self) -> String {
+        match self.game().judge_winner() {
+            Some(winner) => format!("Quarto! {} wins", self.player_label(winner)),
+            None => "Draw".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::action::Action;
+
+    fn options(opponent: Option<PolicyConfig>) -> PlayOptions {
+        PlayOptions {
+            opponent,
+            human_first: true,
+            hint: PolicyConfig::OneStepLookAhead,
+        }
+    }
+
+    fn start() -> Game {
+        Game::from_position_string("..../..../..../.... 0").unwrap()
+    }
+
+    #[test]
+    fn test_play_session_human_vs_human() {
+        let mut session = PlaySession::new(start(), options(None));
+        let input = "a1:1\nb2:z\na1:2\nhint\nundo\nundo\nundo\nc3:2\nquit\n";
+        let mut output = vec![];
+        session.run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("Illegal move"), "{}", output);
+        assert!(output.contains("Hint: "));
+        assert!(output.contains("Nothing to undo"), "手が無ければ戻せない");
+        assert_eq!(session.record().moves, vec!["c3:2".to_string()]);
+    }
+
+    #[test]
+    fn test_play_session_against_engine() {
+        let mut session = PlaySession::new(start(), options(Some(PolicyConfig::Random)));
+        let mut output = vec![];
+        session.run("a1:1\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Engine plays"), "{}", output);
+        assert_eq!(session.record().moves.len(), 2, "エンジンが応じるはず");
+
+        assert!(session.undo());
+        assert!(session.moves.is_empty(), "エンジンの手も一緒に戻すはず");
+    }
+
+    // 常に左上に置こうとするpolicy
+    struct IllegalPolicy {}
+
+    impl Policy for IllegalPolicy {
+        fn new() -> Self {
+            IllegalPolicy {}
+        }
+
+        fn action(&self, _game: &Game) -> Action {
+            Action {
+                row: 0,
+                col: 0,
+                piece_index: Some(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_play_session_reports_illegal_engine_move() {
+        let mut session = PlaySession::new(start(), options(Some(PolicyConfig::Random)));
+        session.opponent = Some(Box::new(IllegalPolicy {}));
+        let mut output = vec![];
+        session
+            .run("a1:1\nb1:2\nquit\n".as_bytes(), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.contains("Engine played an illegal move"),
+            "{}",
+            output
+        );
+        assert_eq!(
+            session.record().moves,
+            vec!["a1:1".to_string(), "b1:2".to_string()],
+            "エンジンを止めた後は人間が続けて指せるはず"
+        );
+    }
+
+    #[test]
+    fn test_game_record_round_trip() {
+        let mut session = PlaySession::new(start(), options(None));
+        session.play("a1:1").unwrap();
+        session.play("b1:2").unwrap();
+        let record = session.record();
+
+        let path = std::env::temp_dir().join("quart_engine_play_test.json");
+        let path = path.to_str().unwrap();
+        record.save(path).unwrap();
+        let loaded = GameRecord::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded, record);
+
+        let resumed = PlaySession::from_record(&loaded, options(None)).unwrap();
+        assert_eq!(
+            resumed.game().to_position_string(),
+            session.game().to_position_string()
+        );
+    }
+}
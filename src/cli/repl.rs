@@ -0,0 +1,422 @@
+use crate::cli::render_game;
+use crate::engine::{format_info, parse_policy, START_POSITION};
+use crate::evaluators::{Evaluator, HandcraftedEvaluator};
+use crate::game::perft::{perft, perft_divide};
+use crate::game::symmetry::canonical_key;
+use crate::game::Game;
+use crate::policies::PolicyConfig;
+use crate::search::SearchLimits;
+use crate::solver::ProofNumberSolver;
+use crate::tablebase::Tablebase;
+use crate::utils::now;
+use std::io::{BufRead, Write};
+
+const HELP: &str = "\
+Commands:
+  position startpos|<string> [moves <move>...]  set the position
+  position json <json>      set the position from JSON
+  new                       start position with a random piece to place
+  show                      show the board and the position string
+  json                      print the position as JSON
+  moves                     list the legal moves
+  play <move>...            play moves, e.g. play c3:a d4:1
+  undo [n]                  take back n moves (default: 1)
+  policy [<name>|<json>]    show or set the policy for go (random, one-step, two-step, mcs, alpha-beta)
+  go [movetime <s>] [nodes <n>] [depth <d>]  run the policy and print its search statistics
+  eval [<weights.json>]     evaluate the position with the handcrafted evaluator
+  solve [<max nodes>]       prove or disprove a win with the proof-number solver
+  tablebase <path>          load a tablebase file used by eval
+  perft <depth>             count the move sequences of the given length
+  divide <depth>            perft for each legal move
+  canonical                 show the symmetry-canonical form of the position
+  help                      show this help
+  quit                      exit
+";
+
+/// 開発者がエンジンを調べるための対話的なコマンドライン
+pub struct Repl {
+    // 最後が現在の局面．undoで戻す
+    history: Vec<Game>,
+    policy: PolicyConfig,
+    tablebase: Option<Tablebase>,
+}
+
+impl Repl {
+    pub fn new(game: Game) -> Self {
+        Repl {
+            history: vec![game],
+            policy: parse_policy("alpha-beta").unwrap(),
+            tablebase: None,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        self.history.last().unwrap()
+    }
+
+    /// inputからコマンドを読んで実行する．quitかinputの終わりで終了する
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> std::io::Result<()> {
+        writeln!(output, "Type help for the list of commands")?;
+        let mut lines = input.lines();
+        loop {
+            write!(output, "quart> ")?;
+            output.flush()?;
+            let Some(line) = lines.next() else {
+                writeln!(output)?;
+                break;
+            };
+            let line = line?;
+            let command = line.split_whitespace().next();
+            if matches!(command, Some("quit" | "exit")) {
+                break;
+            }
+            if let Err(error) = self.execute(&line, output) {
+                writeln!(output, "Error: {}", error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 1行のコマンドを実行し，結果をoutputに書く
+    pub fn execute<W: Write>(&mut self, line: &str, output: &mut W) -> Result<(), String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else {
+            return Ok(());
+        };
+        let text = match command {
+            "help" => HELP.to_string(),
+            "position" => {
+                self.history = parse_position(line.trim_start()["position".len()..].trim(), args)?;
+                self.show()
+            }
+            "new" => {
+                self.history = vec![Game::new()];
+                self.show()
+            }
+            "show" | "board" | "d" => self.show(),
+            "json" => self.game().to_json() + "\n",
+            "moves" => self.moves(),
+            "play" => {
+                if args.is_empty() {
+                    return Err("Usage: play <move>...".to_string());
+                }
+                let mut game = self.game().clone();
+                let mut games = vec![];
+                for text in args {
+                    let action = game.parse_move(text)?;
+                    game.play_turn(action.row, action.col, action.piece_index)
+                        .map_err(|e| format!("Illegal move {}: {}", text, e))?;
+                    games.push(game.clone());
+                }
+                self.history.extend(games);
+                self.show()
+            }
+            "undo" => {
+                let n: usize = parse_arg(args.first(), 1)?;
+                if n >= self.history.len() {
+                    return Err(format!("Only {} moves to undo", self.history.len() - 1));
+                }
+                self.history.truncate(self.history.len() - n);
+                self.show()
+            }
+            "policy" => {
+                if !args.is_empty() {
+                    self.policy = parse_policy(line.trim_start()["policy".len()..].trim())?;
+                }
+                serde_json::to_string(&self.policy).unwrap() + "\n"
+            }
+            "go" => return self.go(args, output),
+            "eval" => self.eval(args.first().copied())?,
+            "solve" => self.solve(parse_arg(args.first(), 1_000_000)?)?,
+            "tablebase" => {
+                let path = args.first().ok_or("Usage: tablebase <path>")?;
+                let tablebase = Tablebase::load(path)?;
+                let text = format!("Loaded {} positions\n", tablebase.len());
+                self.tablebase = Some(tablebase);
+                text
+            }
+            "perft" => {
+                let depth = parse_arg(args.first(), 1)?;
+                let start = now();
+                let nodes = perft(self.game(), depth);
+                let elapsed = now() - start;
+                format!(
+                    "perft {}: {} ({:.3}s, {:.0} nodes/s)\n",
+                    depth,
+                    nodes,
+                    elapsed,
+                    nodes as f64 / elapsed.max(1e-6)
+                )
+            }
+            "divide" => {
+                let depth = parse_arg(args.first(), 1)?;
+                let game = self.game();
+                let divide = perft_divide(game, depth);
+                let mut text = String::new();
+                for (action, nodes) in &divide {
+                    text += &format!("{}: {}\n", game.format_move(action), nodes);
+                }
+                let total: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+                text + &format!("{} moves, {} nodes\n", divide.len(), total)
+            }
+            "canonical" => {
+                let (key, symmetry) = canonical_key(self.game());
+                format!(
+                    "key: {:#x}\nposition: {}\nsymmetry: {:?}\n",
+                    key,
+                    symmetry.apply(self.game()).to_position_string(),
+                    symmetry
+                )
+            }
+            _ => return Err(format!("Unknown command: {} (type help)", command)),
+        };
+        write!(output, "{}", text).map_err(|e| e.to_string())
+    }
+
+    fn show(&self) -> String {
+        let game = self.game();
+        let mut text = render_game(game);
+        text += &format!("Position: {}\n", game.to_position_string());
+        match game.judge_winner() {
+            Some(winner) => text += &format!("{:?} won\n", winner),
+            None if game.is_game_over() => text += "Draw\n",
+            None => {}
+        }
+        text
+    }
+
+    fn moves(&self) -> String {
+        let game = self.game();
+        if game.is_game_over() {
+            return "0 moves (the game is over)\n".to_string();
+        }
+        let moves: Vec<String> = game
+            .legal_actions()
+            .map(|action| game.format_move(&action))
+            .collect();
+        format!("{} moves\n{}\n", moves.len(), moves.join(" "))
+    }
+
+    fn go<W: Write>(&mut self, args: &[&str], output: &mut W) -> Result<(), String> {
+        let game = self.game().clone();
+        if game.is_game_over() {
+            return Err("The game is over".to_string());
+        }
+        let mut limits = SearchLimits::default();
+        let mut tokens = args.iter();
+        while let Some(&token) = tokens.next() {
+            let value = tokens
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", token))?;
+            let invalid = || format!("Invalid value for {}: {}", token, value);
+            match token {
+                "movetime" => limits.move_time = Some(value.parse().map_err(|_| invalid())?),
+                "nodes" => limits.nodes = Some(value.parse().map_err(|_| invalid())?),
+                "depth" => limits.depth = Some(value.parse().map_err(|_| invalid())?),
+                _ => return Err(format!("Unknown go parameter: {}", token)),
+            }
+        }
+        // ノード数や深さだけを指定したときは時間では打ち切らない
+        if limits.move_time.is_none() && (limits.nodes.is_some() || limits.depth.is_some()) {
+            limits.infinite = true;
+        }
+
+        let policy = self.policy.build();
+        let start = now();
+        let mut write_error = None;
+        let action = policy.action_with_limits(&game, &limits, &mut |info| {
+            if let Err(error) = writeln!(output, "{}", format_info(&game, info)) {
+                write_error = Some(error);
+            }
+        });
+        if let Some(error) = write_error {
+            return Err(error.to_string());
+        }
+        writeln!(
+            output,
+            "bestmove {} ({:.3}s)",
+            game.format_move(&action),
+            now() - start
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    fn eval(&self, weights: Option<&str>) -> Result<String, String> {
+        let game = self.game();
+        let evaluator = match weights {
+            Some(path) => HandcraftedEvaluator::from_file(path)?,
+            None => HandcraftedEvaluator::default(),
+        };
+        let mut text = format!("handcrafted: {}\n", evaluator.evaluate(game));
+        text += &format!(
+            "safe pieces: {}\nthree-piece lines: {}\n",
+            HandcraftedEvaluator::count_safe_pieces(game),
+            game.board.count_three_piece_lines()
+        );
+        if let Some((row, col)) = game.board.find_winning_cell(game.selected_piece) {
+            text += &format!("winning cell: {}{}\n", (b'a' + col as u8) as char, row + 1);
+        }
+        if let Some(tablebase) = &self.tablebase {
+            text += &match tablebase.best_action(game) {
+                Ok((action, outcome)) => format!(
+                    "tablebase: {:?} (best {})\n",
+                    outcome,
+                    game.format_move(&action)
+                ),
+                Err(miss) => format!("tablebase: not found ({})\n", miss),
+            };
+        }
+        Ok(text)
+    }
+
+    fn solve(&self, max_nodes: usize) -> Result<String, String> {
+        let solver = ProofNumberSolver {
+            max_nodes,
+            ..ProofNumberSolver::default()
+        };
+        let start = now();
+        let result = solver.solve(self.game())?;
+        let mut text = format!(
+            "{:?} (iterations {}, nodes {}, {:.3}s)\n",
+            result.proof,
+            result.iterations,
+            result.nodes,
+            now() - start
+        );
+        if let Some(action) = &result.best_action {
+            text += &format!("winning move: {}\n", self.game().format_move(action));
+        }
+        Ok(text)
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: Option<&&str>, default: T) -> Result<T, String> {
+    match arg {
+        Some(arg) => arg.parse().map_err(|_| format!("Invalid number: {}", arg)),
+        None => Ok(default),
+    }
+}
+
+// "startpos"，"json <json>"，"<局面の文字列>"のいずれかに"moves <手>..."を続けたもの．
+// movesの手もundoで戻せるように途中の局面をすべて返す
+fn parse_position(rest: &str, args: &[&str]) -> Result<Vec<Game>, String> {
+    if let Some(json) = rest.strip_prefix("json") {
+        let game = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {}", e))?;
+        return Ok(vec![game]);
+    }
+    let moves_index = args
+        .iter()
+        .position(|&token| token == "moves")
+        .unwrap_or(args.len());
+    let mut game = match &args[..moves_index] {
+        [] => return Err("Usage: position startpos|<string>|json <json> [moves <move>...]".into()),
+        ["startpos"] => Game::from_position_string(START_POSITION)?,
+        position => Game::from_position_string(&position.join(" "))?,
+    };
+    let mut history = vec![game.clone()];
+    for text in args.iter().skip(moves_index + 1) {
+        let action = game.parse_move(text)?;
+        game.play_turn(action.row, action.col, action.piece_index)
+            .map_err(|e| format!("Illegal move {}: {}", text, e))?;
+        history.push(game.clone());
+    }
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execute(repl: &mut Repl, line: &str) -> Result<String, String> {
+        let mut output = vec![];
+        repl.execute(line, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    fn repl() -> Repl {
+        Repl::new(Game::from_position_string(START_POSITION).unwrap())
+    }
+
+    #[test]
+    fn test_position_play_and_undo() {
+        let mut repl = repl();
+        let text = execute(&mut repl, "position startpos moves a1:1 b1:2").unwrap();
+        assert!(text.contains("Position: 01../..../..../.... 2 1"));
+        execute(&mut repl, "play c1:3").unwrap();
+        assert_eq!(repl.game().to_position_string(), "012./..../..../.... 3 2");
+        execute(&mut repl, "undo 2").unwrap();
+        assert_eq!(repl.game().to_position_string(), "0.../..../..../.... 1 2");
+        assert!(execute(&mut repl, "undo 2").is_err());
+        assert!(execute(&mut repl, "play a1:5").is_err());
+
+        let json = execute(&mut repl, "json").unwrap();
+        execute(&mut repl, &format!("position json {}", json.trim())).unwrap();
+        assert_eq!(repl.game().to_position_string(), "0.../..../..../.... 1 2");
+        execute(&mut repl, "position 0a3./..../..../.... 5").unwrap();
+        assert_eq!(repl.game().available_pieces.len(), 12);
+    }
+
+    #[test]
+    fn test_moves_and_perft() {
+        let mut repl = repl();
+        assert!(execute(&mut repl, "moves")
+            .unwrap()
+            .starts_with("240 moves"));
+        let text = execute(&mut repl, "perft 2").unwrap();
+        assert!(text.starts_with("perft 2: 50400 "), "{}", text);
+        let text = execute(&mut repl, "divide 2").unwrap();
+        assert!(text.starts_with("a1:1: 210\n"), "{}", text);
+        assert!(text.ends_with("240 moves, 50400 nodes\n"), "{}", text);
+
+        execute(&mut repl, "position 012./..../..../.... 3").unwrap();
+        assert!(execute(&mut repl, "eval")
+            .unwrap()
+            .contains("winning cell: d1"));
+        execute(&mut repl, "play d1").unwrap();
+        assert!(execute(&mut repl, "moves").unwrap().starts_with("0 moves"));
+        assert!(execute(&mut repl, "perft 1")
+            .unwrap()
+            .starts_with("perft 1: 0 "));
+    }
+
+    #[test]
+    fn test_go_and_solve() {
+        let mut repl = repl();
+        execute(&mut repl, "position 012./..../..../.... 3").unwrap();
+        let text = execute(&mut repl, "go depth 2").unwrap();
+        assert!(text.contains("bestmove d1"), "{}", text);
+
+        execute(&mut repl, "policy random").unwrap();
+        let text = execute(&mut repl, r#"policy {"type": "one_step_look_ahead"}"#).unwrap();
+        assert_eq!(text, "{\"type\":\"one_step_look_ahead\"}\n");
+        assert!(execute(&mut repl, "go").unwrap().contains("bestmove d1"));
+        assert!(execute(&mut repl, "policy unknown").is_err());
+
+        let text = execute(&mut repl, "solve").unwrap();
+        assert!(text.starts_with("Proven"), "{}", text);
+    }
+
+    #[test]
+    fn test_canonical() {
+        let mut repl = repl();
+        execute(&mut repl, "position ...0/..../..../.... 1").unwrap();
+        let corner = execute(&mut repl, "canonical").unwrap();
+        execute(&mut repl, "position ..../..../..../0... 1").unwrap();
+        let other_corner = execute(&mut repl, "canonical").unwrap();
+        // 対称な局面はキーと代表の局面が同じになる（変換は異なる）
+        let lines = |text: &str| text.lines().take(2).map(String::from).collect::<Vec<_>>();
+        assert_eq!(lines(&other_corner), lines(&corner));
+        assert!(execute(&mut repl, "foo").is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let mut output = vec![];
+        repl()
+            .run("moves\nfoo\nquit\nmoves\n".as_bytes(), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("240 moves").count(), 1, "quitで終了するはず");
+        assert!(output.contains("Error: Unknown command: foo"));
+    }
+}
@@ -0,0 +1,212 @@
+use crate::game::{Game, Player};
+use serde::{Deserialize, Serialize};
+
+/// 持ち時間の方式（時間はすべて秒）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimeControl {
+    /// 1局の持ち時間だけがあり，使い切ったら負け
+    SuddenDeath { total: f64 },
+    /// 持ち時間に加えて，1手指すごとにincrementだけ持ち時間が増える
+    Fischer { initial: f64, increment: f64 },
+    /// 1手ごとにper_moveまで考えられ，余った時間は持ち越さない
+    FixedPerMove { per_move: f64 },
+}
+
+/// policyに渡す，手番側から見た時計の状態
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockInfo {
+    pub time_control: TimeControl,
+    /// 自分の残り時間
+    pub remaining: f64,
+    /// 相手の残り時間
+    pub opponent_remaining: f64,
+}
+
+impl ClockInfo {
+    /// この手に使える時間の上限
+    pub fn time_limit(&self) -> f64 {
+        match self.time_control {
+            TimeControl::FixedPerMove { per_move } => per_move,
+            _ => self.remaining,
+        }
+    }
+
+    /// 指した後に増える時間
+    pub fn increment(&self) -> f64 {
+        match self.time_control {
+            TimeControl::Fischer { increment, .. } => increment,
+            _ => 0.0,
+        }
+    }
+}
+
+/// 対局の両者の時計
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameClock {
+    pub time_control: TimeControl,
+    // Player1, Player2の残り時間
+    remaining: [f64; 2],
+}
+
+impl GameClock {
+    pub fn new(time_control: TimeControl) -> Self {
+        let initial = match time_control {
+            TimeControl::SuddenDeath { total } => total,
+            TimeControl::Fischer { initial, .. } => initial,
+            TimeControl::FixedPerMove { per_move } => per_move,
+        };
+        GameClock {
+            time_control,
+            remaining: [initial; 2],
+        }
+    }
+
+    pub fn remaining(&self, player: Player) -> f64 {
+        self.remaining[player_index(player)]
+    }
+
+    pub fn info(&self, player: Player) -> ClockInfo {
+        ClockInfo {
+            time_control: self.time_control,
+            remaining: self.remaining(player),
+            opponent_remaining: self.remaining(player.opponent()),
+        }
+    }
+
+    /// playerが1手にelapsed秒使ったとして時計を進める．時間切れならfalseを返す
+    pub fn charge(&mut self, player: Player, elapsed: f64) -> bool {
+        let remaining = &mut self.remaining[player_index(player)];
+        match self.time_control {
+            TimeControl::SuddenDeath { .. } => {
+                *remaining -= elapsed;
+                *remaining >= 0.0
+            }
+            TimeControl::Fischer { increment, .. } => {
+                *remaining -= elapsed;
+                if *remaining < 0.0 {
+                    return false;
+                }
+                *remaining += increment;
+                true
+            }
+            TimeControl::FixedPerMove { per_move } => elapsed <= per_move,
+        }
+    }
+}
+
+fn player_index(player: Player) -> usize {
+    match player {
+        Player::Player1 => 0,
+        Player::Player2 => 1,
+    }
+}
+
+/// 残り時間から1手に使う時間を決める．
+/// 序盤はどこに置いても大差なく，終盤は探索がすぐに読み切れるので，中盤の手ほど多く時間を配分する
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeManager {
+    /// 時間切れを避けるために残しておく時間（秒）
+    pub safety_margin: f64,
+    /// 空きマス数ごとの時間の配分の重み（添字は空きマス数）
+    pub weights: [f64; 17],
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        let mut weights = [1.0; 17];
+        for (empty_cells, weight) in weights.iter_mut().enumerate() {
+            *weight = match empty_cells {
+                14..=16 => 0.5,
+                8..=13 => 2.0,
+                _ => 1.0,
+            };
+        }
+        TimeManager {
+            safety_margin: 0.05,
+            weights,
+        }
+    }
+}
+
+impl TimeManager {
+    /// この手に使う時間（秒）
+    pub fn allocate(&self, game: &Game, clock: &ClockInfo) -> f64 {
+        let usable = (clock.time_limit() - self.safety_margin).max(0.0);
+        if let TimeControl::FixedPerMove { .. } = clock.time_control {
+            return usable;
+        }
+
+        // 自分がこれから指す手（空きマス数が2ずつ減る）に重みに比例して残り時間を配る
+        let empty_cells = game.board.empty_positions().len();
+        let future_weight: f64 = (1..=empty_cells)
+            .rev()
+            .step_by(2)
+            .map(|empty_cells| self.weights[empty_cells])
+            .sum();
+        let share = usable * self.weights[empty_cells] / future_weight.max(f64::EPSILON);
+        // 指した後に増える時間は，この手で使ってしまってよい
+        (share + clock.increment()).min(usable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_clock_fischer() {
+        let mut clock = GameClock::new(TimeControl::Fischer {
+            initial: 1.0,
+            increment: 0.5,
+        });
+        assert!(clock.charge(Player::Player1, 0.8));
+        assert!((clock.remaining(Player::Player1) - 0.7).abs() < 1e-9);
+        assert_eq!(
+            clock.remaining(Player::Player2),
+            1.0,
+            "相手の時計は進まないはず"
+        );
+        assert!(
+            !clock.charge(Player::Player1, 0.8),
+            "持ち時間を超えたら時間切れ"
+        );
+    }
+
+    #[test]
+    fn test_game_clock_sudden_death_and_fixed() {
+        let mut clock = GameClock::new(TimeControl::SuddenDeath { total: 1.0 });
+        assert!(clock.charge(Player::Player2, 0.6));
+        assert!(!clock.charge(Player::Player2, 0.6));
+
+        let mut clock = GameClock::new(TimeControl::FixedPerMove { per_move: 0.5 });
+        assert!(clock.charge(Player::Player1, 0.4));
+        assert!(
+            clock.charge(Player::Player1, 0.4),
+            "余った時間は持ち越さない"
+        );
+        assert!(!clock.charge(Player::Player1, 0.6));
+        assert_eq!(clock.info(Player::Player1).time_limit(), 0.5);
+    }
+
+    #[test]
+    fn test_time_manager_prefers_middlegame() {
+        let time_manager = TimeManager::default();
+        let clock = GameClock::new(TimeControl::SuddenDeath { total: 10.0 }).info(Player::Player1);
+        let mut game = Game::new();
+        let opening = time_manager.allocate(&game, &clock);
+        for (row, col) in [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)] {
+            game.play_turn(row, col, Some(0)).unwrap();
+        }
+        let middlegame = time_manager.allocate(&game, &clock);
+        assert!(middlegame > opening, "中盤の方が多く時間を使うはず");
+        assert!(middlegame < 10.0 - time_manager.safety_margin);
+
+        let fixed =
+            GameClock::new(TimeControl::FixedPerMove { per_move: 1.0 }).info(Player::Player1);
+        assert_eq!(
+            time_manager.allocate(&game, &fixed),
+            1.0 - time_manager.safety_margin
+        );
+    }
+}
@@ -0,0 +1,488 @@
+//! 標準入出力で他のプログラムから操作するための行単位のテキストプロトコル（QEI: Quarto Engine Interface）．
+//!
+//! GUI → エンジン
+//! - `qei`: 識別情報と設定項目を返し，最後に`qeiok`を返す
+//! - `isready`: `readyok`を返す
+//! - `setoption name <名前> value <値>`: 設定を変更する
+//! - `qeinewgame`: 局面を初期局面に戻す
+//! - `position startpos [moves <手>...]`: 初期局面（駒0を渡された空の盤面）から手を進めた局面にする
+//! - `position string <局面の文字列> [moves <手>...]`: 局面の文字列から手を進めた局面にする
+//! - `go [movetime <ms>] [wtime <ms>] [btime <ms>] [winc <ms>] [binc <ms>] [nodes <n>] [depth <n>] [infinite]`:
+//!   思考を始める．wtime/winc は先手(Player1)，btime/binc は後手(Player2)の持ち時間．
+//!   `infinite`なら読み切っても`stop`が来るまで`bestmove`を返さない
+//! - `stop`: 思考を止めてすぐに`bestmove`を返させる
+//!
+//!   思考中に`setoption`/`qeinewgame`/`position`/`go`が来たら，思考を止めて`bestmove`を返してから処理する
+//! - `quit`: 終了する
+//!
+//! エンジン → GUI
+//! - `info depth <n> score cp <x>|mate <n> nodes <n> time <ms> nps <n> pv <手>`: 思考の途中経過
+//! - `info string <文字列>`: エラーなどのメッセージ
+//! - `bestmove <手>`: 思考の結果．局面が終局していれば`bestmove (none)`
+//!
+//! 手は`Game::format_move`の記法で書く（例: `c3:a`）
+
+use crate::cli::opponent_config;
+use crate::clock::{ClockInfo, TimeControl};
+use crate::game::{Game, Player};
+use crate::policies::{Policy, PolicyConfig};
+use crate::runner::PolicyFactory;
+use crate::search::{SearchInfo, SearchLimits};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// 初期局面．最初に渡す駒はどれを選んでも対称なので駒0とする
+pub const START_POSITION: &str = "..../..../..../.... 0";
+
+const DEFAULT_POLICY: &str = "alpha-beta";
+const DEFAULT_MOVE_TIME: u64 = 1000;
+
+/// プロトコルを話すエンジン．policyを作る関数を受け取るので，どのpolicyでも包める
+pub struct Engine<W: Write + Send + 'static> {
+    output: Arc<Mutex<W>>,
+    factory: PolicyFactory,
+    policy: Option<Box<dyn Policy>>,
+    game: Game,
+    /// 時間の指定が無いgoで使う時間（ミリ秒）
+    move_time: u64,
+    search: Option<Search>,
+}
+
+// 思考中のスレッド．終わるとpolicyを返す
+struct Search {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Box<dyn Policy>>,
+}
+
+impl<W: Write + Send + 'static> Engine<W> {
+    pub fn new(factory: PolicyFactory, output: W) -> Self {
+        Engine {
+            output: Arc::new(Mutex::new(output)),
+            policy: Some(factory()),
+            factory,
+            game: Game::from_position_string(START_POSITION).unwrap(),
+            move_time: DEFAULT_MOVE_TIME,
+            search: None,
+        }
+    }
+
+    /// 入力が終わるかquitが来るまでコマンドを処理する．どちらの場合も思考中なら止める
+    pub fn run<R: BufRead>(&mut self, input: R) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?)? {
+                break;
+            }
+        }
+        self.stop();
+        Ok(())
+    }
+
+    /// 1行のコマンドを処理する．quitならfalseを返す．思考が終わるのを待って入力を止めることはない
+    pub fn handle(&mut self, line: &str) -> io::Result<bool> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return Ok(true);
+        };
+        match command {
+            "qei" => self.identify()?,
+            "isready" => self.send("readyok")?,
+            "setoption" => {
+                self.stop();
+                if let Err(error) = self.set_option(args) {
+                    self.send(&format!("info string {}", error))?;
+                }
+            }
+            "qeinewgame" => {
+                self.stop();
+                self.game = Game::from_position_string(START_POSITION).unwrap();
+            }
+            "position" => {
+                self.stop();
+                match parse_position(args) {
+                    Ok(game) => self.game = game,
+                    Err(error) => self.send(&format!("info string {}", error))?,
+                }
+            }
+            "go" => {
+                self.stop();
+                match self.parse_go(args) {
+                    Ok(limits) => self.go(limits, args.contains(&"infinite"))?,
+                    Err(error) => self.send(&format!("info string {}", error))?,
+                }
+            }
+            "stop" => self.stop(),
+            "quit" => return Ok(false),
+            _ => self.send(&format!("info string Unknown command: {}", command))?,
+        }
+        Ok(true)
+    }
+
+    /// 思考が終わるのを待ってから出力先を返す
+    pub fn into_output(mut self) -> W {
+        self.wait();
+        match Arc::try_unwrap(self.output) {
+            Ok(output) => output.into_inner().unwrap(),
+            Err(_) => unreachable!("思考が終われば出力先を持つのはエンジンだけ"),
+        }
+    }
+
+    fn identify(&mut self) -> io::Result<()> {
+        self.send(&format!(
+            "id name quart-engine {}",
+            env!("CARGO_PKG_VERSION")
+        ))?;
+        self.send("id author AlphaQuart")?;
+        self.send(&format!(
+            "option name Policy type string default {}",
+            DEFAULT_POLICY
+        ))?;
+        self.send(&format!(
+            "option name MoveTime type spin default {} min 1 max 3600000",
+            DEFAULT_MOVE_TIME
+        ))?;
+        self.send("qeiok")
+    }
+
+    fn set_option(&mut self, args: &[&str]) -> Result<(), String> {
+        let value_index = args.iter().position(|&token| token == "value");
+        let (name, value) = match (args.first(), value_index) {
+            (Some(&"name"), Some(index)) => (args[1..index].join(" "), args[index + 1..].join(" ")),
+            _ => return Err("Usage: setoption name <name> value <value>".to_string()),
+        };
+        match name.to_lowercase().as_str() {
+            "policy" => {
+                let config = parse_policy(&value)?;
+                self.factory = Box::new(move || config.build());
+                self.policy = Some((self.factory)());
+            }
+            "movetime" => {
+                self.move_time = value
+                    .parse()
+                    .map_err(|_| format!("Invalid MoveTime: {}", value))?;
+            }
+            _ => return Err(format!("Unknown option: {}", name)),
+        }
+        Ok(())
+    }
+
+    fn parse_go(&self, args: &[&str]) -> Result<SearchLimits, String> {
+        let mut limits = SearchLimits::default();
+        let mut times = [None; 2];
+        let mut increments = [0.0; 2];
+        let mut tokens = args.iter();
+        while let Some(&token) = tokens.next() {
+            if token == "infinite" {
+                limits.infinite = true;
+                continue;
+            }
+            let value: u64 = tokens
+                .next()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| format!("Missing or invalid value for {}", token))?;
+            let seconds = value as f64 / 1000.0;
+            match token {
+                "movetime" => limits.move_time = Some(seconds),
+                "wtime" => times[0] = Some(seconds),
+                "btime" => times[1] = Some(seconds),
+                "winc" => increments[0] = seconds,
+                "binc" => increments[1] = seconds,
+                "nodes" => limits.nodes = Some(value),
+                "depth" => limits.depth = Some(value as usize),
+                _ => return Err(format!("Unknown go parameter: {}", token)),
+            }
+        }
+
+        let (me, opponent) = match self.game.current_player {
+            Player::Player1 => (0, 1),
+            Player::Player2 => (1, 0),
+        };
+        if let Some(remaining) = times[me] {
+            let time_control = if increments[me] > 0.0 {
+                TimeControl::Fischer {
+                    initial: remaining,
+                    increment: increments[me],
+                }
+            } else {
+                TimeControl::SuddenDeath { total: remaining }
+            };
+            limits.clock = Some(ClockInfo {
+                time_control,
+                remaining,
+                opponent_remaining: times[opponent].unwrap_or(remaining),
+            });
+        }
+        // 時間の指定が無ければ，ノード数や深さの指定があればそこまで，無ければMoveTimeだけ考える
+        if limits.move_time.is_none() && limits.clock.is_none() && !limits.infinite {
+            if limits.nodes.is_some() || limits.depth.is_some() {
+                limits.infinite = true;
+            } else {
+                limits.move_time = Some(self.move_time as f64 / 1000.0);
+            }
+        }
+        Ok(limits)
+    }
+
+    // wait_for_stopならstopが来るまでbestmoveを返さない
+    fn go(&mut self, limits: SearchLimits, wait_for_stop: bool) -> io::Result<()> {
+        if self.game.is_game_over() {
+            self.send("info string Game is already over")?;
+            return self.send("bestmove (none)");
+        }
+        let policy = self.policy.take().unwrap_or_else(|| (self.factory)());
+        let output = self.output.clone();
+        let game = self.game.clone();
+        let stop = limits.stop.clone();
+        let handle = thread::spawn(move || {
+            let action = policy.action_with_limits(&game, &limits, &mut |info| {
+                let _ = send(&output, &format_info(&game, info));
+            });
+            while wait_for_stop && !limits.is_stopped() {
+                thread::park();
+            }
+            let _ = send(&output, &format!("bestmove {}", game.format_move(&action)));
+            policy
+        });
+        self.search = Some(Search { stop, handle });
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(search) = &self.search {
+            search.stop.store(true, Ordering::Relaxed);
+            search.handle.thread().unpark();
+        }
+        self.wait();
+    }
+
+    // 思考中なら終わるのを待つ．policyがパニックした場合は作り直す
+    fn wait(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        match search.handle.join() {
+            Ok(policy) => self.policy = Some(policy),
+            Err(_) => {
+                let _ = self.send("info string Search panicked");
+                let _ = self.send("bestmove (none)");
+                self.policy = Some((self.factory)());
+            }
+        }
+    }
+
+    fn send(&self, line: &str) -> io::Result<()> {
+        send(&self.output, line)
+    }
+}
+
+fn send<W: Write>(output: &Mutex<W>, line: &str) -> io::Result<()> {
+    let mut output = output.lock().unwrap();
+    writeln!(output, "{}", line)?;
+    output.flush()
+}
+
+/// Policyの値を読む．policyの名前（random, one-step, two-step, mcs, alpha-beta）か，PolicyConfigのJSON
+pub(crate) fn parse_policy(value: &str) -> Result<PolicyConfig, String> {
+    if value.trim_start().starts_with('{') {
+        return PolicyConfig::from_json(value);
+    }
+    opponent_config(value, 3)?.ok_or_else(|| format!("Invalid policy: {}", value))
+}
+
+fn parse_position(args: &[&str]) -> Result<Game, String> {
+    let moves_index = args
+        .iter()
+        .position(|&token| token == "moves")
+        .unwrap_or(args.len());
+    let mut game = match args.split_first() {
+        Some((&"startpos", _)) => Game::from_position_string(START_POSITION)?,
+        Some((&"string", position)) => {
+            Game::from_position_string(&position[..moves_index - 1].join(" "))?
+        }
+        _ => {
+            return Err("Usage: position startpos|string <position> [moves <move>...]".to_string())
+        }
+    };
+    for text in args.iter().skip(moves_index + 1) {
+        let action = game.parse_move(text)?;
+        game.play_turn(action.row, action.col, action.piece_index)
+            .map_err(|e| format!("Illegal move {}: {}", text, e))?;
+    }
+    Ok(game)
+}
+
+pub(crate) fn format_info(game: &Game, info: &SearchInfo) -> String {
+    let mut line = format!("info depth {}", info.depth);
+    match (info.mate, info.score) {
+        (Some(mate), _) => line += &format!(" score mate {}", mate),
+        (None, Some(score)) => line += &format!(" score cp {}", score),
+        (None, None) => {}
+    }
+    let millis = (info.elapsed * 1000.0) as u64;
+    line += &format!(
+        " nodes {} time {} nps {}",
+        info.nodes,
+        millis,
+        (info.nodes as f64 / info.elapsed.max(1e-3)) as u64
+    );
+    if let Some(action) = &info.best_action {
+        line += &format!(" pv {}", game.format_move(action));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::{AlphaBetaPolicy, MCSPolicy, OneStepLookAheadPolicy, RandomPolicy};
+
+    fn run_engine(factory: PolicyFactory, input: &str) -> Vec<String> {
+        let mut engine = Engine::new(factory, Vec::new());
+        engine.run(input.as_bytes()).unwrap();
+        String::from_utf8(engine.into_output())
+            .unwrap()
+            .lines()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    fn alpha_beta() -> PolicyFactory {
+        Box::new(|| Box::new(AlphaBetaPolicy::<crate::evaluators::HandcraftedEvaluator>::new()))
+    }
+
+    #[test]
+    fn test_engine_handshake() {
+        let lines = run_engine(alpha_beta(), "qei\nisready\nquit\n");
+        assert!(lines[0].starts_with("id name quart-engine"));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("option name Policy")));
+        let qeiok = lines.iter().position(|line| line == "qeiok").unwrap();
+        assert_eq!(lines[qeiok + 1], "readyok");
+    }
+
+    #[test]
+    fn test_engine_go_depth_reports_info_and_bestmove() {
+        // 入力が終わると思考を止めるので，handleで送ってから思考が終わるのを待つ
+        let mut engine = Engine::new(alpha_beta(), Vec::new());
+        engine
+            .handle("position string 0a3./..../..../.... 5 moves a2:6")
+            .unwrap();
+        engine.handle("go depth 2").unwrap();
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(
+            lines.iter().any(|line| line.starts_with("info depth 2")),
+            "{:?}",
+            lines
+        );
+        let bestmove = lines.last().unwrap();
+        assert!(bestmove.starts_with("bestmove "), "{:?}", lines);
+
+        // 返した手はその局面で合法なはず
+        let game =
+            parse_position(&["string", "0a3./..../..../....", "5", "moves", "a2:6"]).unwrap();
+        let action = game.parse_move(&bestmove["bestmove ".len()..]).unwrap();
+        assert!(game.validate_action(&action).is_ok());
+    }
+
+    #[test]
+    fn test_engine_stop_infinite_search() {
+        let mut engine = Engine::new(alpha_beta(), Vec::new());
+        engine.handle("position startpos").unwrap();
+        engine.handle("go infinite").unwrap();
+        thread::sleep(std::time::Duration::from_millis(50));
+        engine.handle("stop").unwrap();
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        assert!(
+            output.lines().last().unwrap().starts_with("bestmove "),
+            "stopで思考を止めて手を返すはず"
+        );
+    }
+
+    #[test]
+    fn test_engine_infinite_search_waits_for_stop() {
+        // 読み切れる局面でも，stopが来るまでbestmoveを返さない
+        let mut engine = Engine::new(alpha_beta(), Vec::new());
+        engine
+            .handle("position string c827/50a4/be9./1d.. 3")
+            .unwrap();
+        engine.handle("go infinite").unwrap();
+        thread::sleep(std::time::Duration::from_millis(200));
+        let output = String::from_utf8(engine.output.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains("bestmove"), "{}", output);
+        engine.handle("stop").unwrap();
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        assert!(
+            output.lines().last().unwrap().starts_with("bestmove "),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_engine_go_infinite_with_mcs() {
+        let factory: PolicyFactory =
+            Box::new(|| Box::new(MCSPolicy::<OneStepLookAheadPolicy>::new()));
+        let mut engine = Engine::new(factory, Vec::new());
+        engine.handle("position startpos moves a1:1").unwrap();
+        engine.handle("go infinite").unwrap();
+        thread::sleep(std::time::Duration::from_millis(50));
+        engine.handle("stop").unwrap();
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        assert!(
+            output.lines().last().unwrap().starts_with("bestmove "),
+            "stopでMCSも思考を止めて手を返すはず"
+        );
+    }
+
+    #[test]
+    fn test_engine_position_during_infinite_search() {
+        // 思考中のpositionで入力を止めると，stopを読めずに止まってしまう
+        let (sender, receiver) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            let lines = run_engine(
+                alpha_beta(),
+                "position startpos\ngo infinite\nposition startpos moves a1:1\n\
+                 go infinite\nisready\n",
+            );
+            let _ = sender.send(lines);
+        });
+        let lines = receiver
+            .recv_timeout(std::time::Duration::from_secs(30))
+            .expect("思考中のコマンドで止まってはいけない");
+        let bestmoves: Vec<&String> = lines
+            .iter()
+            .filter(|line| line.starts_with("bestmove "))
+            .collect();
+        assert_eq!(bestmoves.len(), 2, "{:?}", lines);
+        assert!(lines.contains(&"readyok".to_string()));
+
+        // 2回目の思考はa1:1の後の局面で行われる
+        let game = parse_position(&["startpos", "moves", "a1:1"]).unwrap();
+        let action = game.parse_move(&bestmoves[1]["bestmove ".len()..]).unwrap();
+        assert!(game.validate_action(&action).is_ok());
+    }
+
+    #[test]
+    fn test_engine_reports_errors() {
+        let lines = run_engine(
+            Box::new(|| Box::new(RandomPolicy::new())),
+            "position startpos moves a1:1 a1:2\nsetoption name Policy value unknown\nfoo\n\
+             position string 0123/..../..../.... 4\ngo movetime 10\n",
+        );
+        assert!(
+            lines[0].starts_with("info string Illegal move a1:2"),
+            "{:?}",
+            lines
+        );
+        assert!(
+            lines[1].starts_with("info string Unknown opponent"),
+            "{:?}",
+            lines
+        );
+        assert_eq!(lines[2], "info string Unknown command: foo");
+        assert_eq!(lines[4], "bestmove (none)", "終局した局面では手を返せない");
+    }
+}
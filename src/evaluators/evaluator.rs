@@ -0,0 +1,20 @@
+use crate::game::Game;
+
+/// 評価値の絶対値の上限．探索で勝ち負けが確定した局面のscoreと区別するため，これより大きな値は返さない
+pub const EVAL_LIMIT: i32 = 100_000;
+
+pub trait Evaluator: Send {
+    /// 手番側（selected_pieceを置く側）から見た局面の評価値を返す．
+    /// 正なら手番側が有利で，絶対値はEVAL_LIMIT以下
+    fn evaluate(&self, game: &Game) -> i32;
+}
+
+/// すべての局面を互角とみなす評価関数
+#[derive(Debug, Clone, Default)]
+pub struct NeutralEvaluator {}
+
+impl Evaluator for NeutralEvaluator {
+    fn evaluate(&self, _game: &Game) -> i32 {
+        0
+    }
+}
@@ -0,0 +1,146 @@
+use crate::evaluators::evaluator::{Evaluator, EVAL_LIMIT};
+use crate::game::Game;
+use serde::{Deserialize, Serialize};
+
+/// HandcraftedEvaluatorの各特徴量の重み
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandcraftedWeights {
+    /// selected_pieceを置いてすぐに勝てる
+    pub immediate_win: f64,
+    /// 渡しても相手がすぐには勝てない駒の数
+    pub safe_pieces: f64,
+    /// 3つのピースが共通の属性を持ち，残り1マスが空いているラインの数
+    pub three_piece_lines: f64,
+    /// 安全な駒の数が奇数なら+1，偶数なら-1（安全な駒が無ければ0）
+    pub safe_parity: f64,
+}
+
+impl Default for HandcraftedWeights {
+    fn default() -> Self {
+        HandcraftedWeights {
+            immediate_win: 1000.0,
+            safe_pieces: 10.0,
+            three_piece_lines: -5.0,
+            safe_parity: 30.0,
+        }
+    }
+}
+
+/// Boardのビットボードから計算した特徴量の線形和で局面を評価する
+#[derive(Debug, Clone, Default)]
+pub struct HandcraftedEvaluator {
+    pub weights: HandcraftedWeights,
+}
+
+impl HandcraftedEvaluator {
+    /// JSON形式の設定ファイルから重みを読み込む．書かれていない重みはデフォルト値になる
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let weights: HandcraftedWeights = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Ok(HandcraftedEvaluator { weights })
+    }
+
+    // 渡しても相手がすぐには勝てない駒の数
+    pub fn count_safe_pieces(game: &Game) -> usize {
+        game.available_pieces
+            .iter()
+            .filter(|&&piece| game.board.find_winning_cell(piece).is_none())
+            .count()
+    }
+}
+
+impl Evaluator for HandcraftedEvaluator {
+    fn evaluate(&self, game: &Game) -> i32 {
+        let weights = &self.weights;
+        let mut score = 0.0;
+
+        if game.board.find_winning_cell(game.selected_piece).is_some() {
+            score += weights.immediate_win;
+        }
+
+        // 手番側から安全な駒を渡し合うので，安全な駒が奇数なら最後に渡せない状況になるのは相手
+        let safe_pieces = Self::count_safe_pieces(game);
+        score += weights.safe_pieces * safe_pieces as f64;
+        if safe_pieces > 0 {
+            let parity = if safe_pieces % 2 == 1 { 1.0 } else { -1.0 };
+            score += weights.safe_parity * parity;
+        }
+
+        score += weights.three_piece_lines * game.board.count_three_piece_lines() as f64;
+
+        (score.round() as i32).clamp(-EVAL_LIMIT, EVAL_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Piece;
+
+    #[test]
+    fn test_handcrafted_evaluator_prefers_immediate_win() {
+        let mut game = Game::new();
+        let quiet_score = HandcraftedEvaluator::default().evaluate(&game);
+
+        game.board
+            .place_piece(0, 0, Piece::new(0, 1, 0, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 1, Piece::new(0, 1, 0, 1))
+            .unwrap();
+        game.board
+            .place_piece(0, 2, Piece::new(0, 1, 1, 0))
+            .unwrap();
+        game.selected_piece = Piece::new(1, 1, 1, 1);
+
+        let winning_score = HandcraftedEvaluator::default().evaluate(&game);
+        assert!(
+            winning_score > quiet_score,
+            "すぐ勝てる局面は高く評価されるはず"
+        );
+    }
+
+    #[test]
+    fn test_handcrafted_evaluator_counts_safe_pieces() {
+        let mut game = Game::new();
+        game.board
+            .place_piece(0, 0, Piece::new(0, 0, 0, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 1, Piece::new(1, 0, 1, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 2, Piece::new(0, 0, 1, 1))
+            .unwrap();
+        game.available_pieces = vec![Piece::new(1, 0, 0, 1), Piece::new(1, 1, 1, 0)];
+
+        assert_eq!(
+            HandcraftedEvaluator::count_safe_pieces(&game),
+            1,
+            "形が丸の駒は(0, 3)に置かれると負けるので安全ではない"
+        );
+    }
+
+    #[test]
+    fn test_handcrafted_evaluator_from_json() {
+        let evaluator = HandcraftedEvaluator::from_json(r#"{"safe_pieces": 3.5}"#).unwrap();
+        assert_eq!(
+            evaluator.weights.safe_pieces, 3.5,
+            "書かれた重みが読み込まれるはず"
+        );
+        assert_eq!(
+            evaluator.weights.immediate_win,
+            HandcraftedWeights::default().immediate_win,
+            "書かれていない重みはデフォルト値になるはず"
+        );
+        assert!(
+            HandcraftedEvaluator::from_json("{").is_err(),
+            "不正なJSONはエラーになるはず"
+        );
+    }
+}
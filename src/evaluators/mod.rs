@@ -0,0 +1,5 @@
+pub mod evaluator;
+pub mod handcrafted_evaluator;
+
+pub use evaluator::{Evaluator, NeutralEvaluator, EVAL_LIMIT};
+pub use handcrafted_evaluator::{HandcraftedEvaluator, HandcraftedWeights};
@@ -10,6 +10,7 @@ const WINNING_MASKS: [u16; 10] = [
 ];
 
 #[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "BoardFields")]
 pub struct Board {
     color_board: u16,
     shape_board: u16,
@@ -18,6 +19,34 @@ pub struct Board {
     empty_cells: u16, // 空のセルを管理するためのビットボード
 }
 
+// Serializeで書き出した形式から読み込むための中間表現
+#[derive(Deserialize)]
+struct BoardFields {
+    grid: [[Option<Piece>; 4]; 4],
+}
+
+impl TryFrom<BoardFields> for Board {
+    type Error = String;
+
+    fn try_from(fields: BoardFields) -> Result<Self, Self::Error> {
+        let mut board = Board::new();
+        for (row, grid_row) in fields.grid.iter().enumerate() {
+            for (col, cell) in grid_row.iter().enumerate() {
+                if let Some(piece) = cell {
+                    board.place_piece(row, col, *piece)?;
+                }
+            }
+        }
+        Ok(board)
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Board {
     pub fn new() -> Self {
         Board {
@@ -66,29 +95,39 @@ impl Board {
         false
     }
 
-    // ピースが置かれていないセルの位置を高速に取得
-    pub fn available_positions(&self) -> Vec<(usize, usize)> {
-        let mut positions = Vec::with_capacity(16); // 最大16セルの空きがある可能性があるため、あらかじめ容量を確保
-        let mut empty_cells = self.empty_cells;
-
-        while empty_cells != 0 {
-            let pos = empty_cells.trailing_zeros() as usize; // 1が立っている最初のビット位置を取得
-            let row = pos / 4;
-            let col = pos % 4;
-            positions.push((row, col));
-            empty_cells &= empty_cells - 1; // 1が立っているビットをクリア
+    // 3つのピースが共通の属性を持ち，残りの1マスが空いているラインの数
+    pub fn count_three_piece_lines(&self) -> u32 {
+        let attribute_boards = [
+            self.color_board,
+            self.shape_board,
+            self.height_board,
+            self.surface_board,
+        ];
+        let mut count = 0;
+        for &mask in WINNING_MASKS.iter() {
+            let occupied = mask & !self.empty_cells;
+            if occupied.count_ones() != 3 {
+                continue;
+            }
+            if attribute_boards
+                .iter()
+                .any(|&board| board & occupied == 0 || board & occupied == occupied)
+            {
+                count += 1;
+            }
         }
+        count
+    }
 
-        positions
+    // ピースが置かれていないセルの位置（Vecが要らなければempty_positionsを使う）
+    pub fn available_positions(&self) -> Vec<(usize, usize)> {
+        self.empty_positions().collect()
     }
 
     // 勝利できるセルを探す
     pub fn find_winning_cell(&self, piece: Piece) -> Option<(usize, usize)> {
-        // 空いているセルを取得
-        let available_positions = self.available_positions();
-
         // 各空きセルに対してピースを置いてみる
-        for (row, col) in available_positions {
+        for (row, col) in self.empty_positions() {
             // 仮想的にピースを置いた状態を作成
             let mut tmp_board = *self;
             tmp_board.place_piece(row, col, piece).ok()?; // ピースを置く
@@ -107,37 +146,49 @@ impl Board {
         self.empty_cells == 0
     }
 
+    // 空いているセルのビットマスク（row * 4 + colのビットが立つ）
+    pub fn empty_cells(&self) -> u16 {
+        self.empty_cells
+    }
+
+    // 指定したセルに置かれているピースを返す（空の場合はNone）
+    pub fn piece_at(&self, row: usize, col: usize) -> Option<Piece> {
+        let position = 1 << (row * 4 + col);
+
+        if self.empty_cells & position != 0 {
+            return None;
+        }
+
+        // ピースが配置されている場合
+        let color = if self.color_board & position != 0 {
+            0 // color_boardにビットが立っている場合は0（黒）
+        } else {
+            1 // 立っていない場合は1（白）
+        };
+        let shape = if self.shape_board & position != 0 {
+            0 // shape_boardにビットが立っている場合は0（丸）
+        } else {
+            1 // 立っていない場合は1（四角）
+        };
+        let height = if self.height_board & position != 0 {
+            0 // height_boardにビットが立っている場合は0（高い）
+        } else {
+            1 // 立っていない場合は1（低い）
+        };
+        let surface = if self.surface_board & position != 0 {
+            0 // surface_boardにビットが立っている場合は0（穴あり）
+        } else {
+            1 // 立っていない場合は1（穴なし）
+        };
+        Some(Piece::new(color, shape, height, surface))
+    }
+
     pub fn grid(&self) -> [[Option<Piece>; 4]; 4] {
         let mut grid = [[None; 4]; 4];
 
-        for row in 0..4 {
-            for col in 0..4 {
-                let position = 1 << (row * 4 + col);
-
-                if self.empty_cells & position == 0 {
-                    // ピースが配置されている場合
-                    let color = if self.color_board & position != 0 {
-                        0 // color_boardにビットが立っている場合は0（黒）
-                    } else {
-                        1 // 立っていない場合は1（白）
-                    };
-                    let shape = if self.shape_board & position != 0 {
-                        0 // shape_boardにビットが立っている場合は0（丸）
-                    } else {
-                        1 // 立っていない場合は1（四角）
-                    };
-                    let height = if self.height_board & position != 0 {
-                        0 // height_boardにビットが立っている場合は0（高い）
-                    } else {
-                        1 // 立っていない場合は1（低い）
-                    };
-                    let surface = if self.surface_board & position != 0 {
-                        0 // surface_boardにビットが立っている場合は0（穴あり）
-                    } else {
-                        1 // 立っていない場合は1（穴なし）
-                    };
-                    grid[row][col] = Some(Piece::new(color, shape, height, surface));
-                }
+        for (row, grid_row) in grid.iter_mut().enumerate() {
+            for (col, cell) in grid_row.iter_mut().enumerate() {
+                *cell = self.piece_at(row, col);
             }
         }
 
@@ -187,7 +238,7 @@ mod tests {
         let board = Board::new();
         assert!(!board.check_win(), "新しいボードでは勝者がいないはず");
         assert_eq!(
-            board.available_positions().len(),
+            board.empty_positions().len(),
             16,
             "初期状態では全てのポジションが利用可能であるべき"
         );
@@ -387,4 +438,56 @@ mod tests {
             "ピースが正しく復元されるべき"
         );
     }
+
+    #[test]
+    fn test_piece_at() {
+        let piece = Piece::new(1, 0, 1, 0);
+        let mut board = Board::new();
+
+        board.place_piece(2, 1, piece).unwrap();
+
+        assert_eq!(board.piece_at(2, 1), Some(piece), "置いたピースが取り出せるべき");
+        assert_eq!(board.piece_at(1, 2), None, "空のセルはNoneになるべき");
+    }
+
+    #[test]
+    fn test_count_three_piece_lines() {
+        let mut board = Board::new();
+        board.place_piece(0, 0, Piece::new(0, 1, 0, 0)).unwrap();
+        board.place_piece(0, 1, Piece::new(0, 1, 0, 1)).unwrap();
+        assert_eq!(board.count_three_piece_lines(), 0, "2つだけのラインは数えない");
+
+        board.place_piece(0, 2, Piece::new(0, 1, 1, 0)).unwrap();
+        assert_eq!(board.count_three_piece_lines(), 1, "共通の属性を持つ3つのラインを数える");
+
+        // 共通の属性が無い3つのラインは数えない
+        board.place_piece(1, 0, Piece::new(1, 0, 1, 1)).unwrap();
+        board.place_piece(2, 0, Piece::new(0, 1, 0, 0)).unwrap();
+        assert_eq!(board.count_three_piece_lines(), 1, "属性が揃わないラインは数えない");
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut board = Board::new();
+        board.place_piece(0, 3, Piece::new(1, 0, 1, 1)).unwrap();
+        board.place_piece(2, 1, Piece::new(0, 1, 0, 0)).unwrap();
+
+        let json = serde_json::to_string(&board).unwrap();
+        let loaded: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, board, "書き出したJSONから同じボードが読み込めるべき");
+    }
+
+    #[test]
+    fn test_json_rejects_invalid_piece() {
+        let json = r#"{"color": 2, "shape": 0, "height": 0, "surface": 0}"#;
+        assert!(
+            serde_json::from_str::<Piece>(json).is_err(),
+            "属性は0か1でなければならない"
+        );
+        let json = r#"{"color": 1, "shape": 0, "height": 1, "surface": 0}"#;
+        assert_eq!(
+            serde_json::from_str::<Piece>(json).unwrap(),
+            Piece::new(1, 0, 1, 0)
+        );
+    }
 }
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 不正な手を指そうとしたときのエラー
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameError {
+    /// すでに終局している
+    GameOver,
+    /// 盤面の外に置こうとした
+    OutOfBounds { row: usize, col: usize },
+    /// すでに駒が置かれているセルに置こうとした
+    CellOccupied { row: usize, col: usize },
+    /// available_piecesに無い駒を渡そうとした
+    InvalidPieceIndex {
+        piece_index: usize,
+        available: usize,
+    },
+    /// 渡せる駒があるのに駒を渡さなかった
+    MissingPiece,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::GameOver => write!(f, "Game is already over"),
+            GameError::OutOfBounds { row, col } => {
+                write!(f, "Cell ({}, {}) is out of bounds", row, col)
+            }
+            GameError::CellOccupied { row, col } => {
+                write!(f, "Cell ({}, {}) is already occupied", row, col)
+            }
+            GameError::InvalidPieceIndex {
+                piece_index,
+                available,
+            } => write!(
+                f,
+                "Piece index {} is out of range ({} pieces available)",
+                piece_index, available
+            ),
+            GameError::MissingPiece => write!(f, "A piece must be given to the opponent"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<GameError> for String {
+    fn from(error: GameError) -> Self {
+        error.to_string()
+    }
+}
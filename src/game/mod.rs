@@ -1,16 +1,25 @@
 pub mod action;
 pub mod board;
+pub mod error;
+pub mod movegen;
+pub mod notation;
+pub mod perft;
 pub mod piece;
 pub mod player;
+pub mod symmetry;
 pub use action::Action;
 pub use board::Board;
+pub use error::GameError;
+pub use movegen::ActionList;
 pub use piece::Piece;
 pub use player::Player;
 
-use rand;
+use crate::utils::rng;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(try_from = "GameFields")]
 pub struct Game {
     pub board: Board,
     pub available_pieces: Vec<Piece>,
@@ -18,12 +27,65 @@ pub struct Game {
     pub current_player: Player,
 }
 
+// Serializeで書き出した形式から読み込むための中間表現
+#[derive(Deserialize)]
+struct GameFields {
+    board: Board,
+    available_pieces: Vec<Piece>,
+    selected_piece: Piece,
+    current_player: Player,
+}
+
+impl TryFrom<GameFields> for Game {
+    type Error = String;
+
+    // 同じ駒が盤面，available_pieces，selected_pieceに重ねて現れてはいけない．
+    // ただし駒を渡さずに終局した後は，selected_pieceは最後に置いた駒のまま残る
+    fn try_from(fields: GameFields) -> Result<Self, Self::Error> {
+        let duplicated = |piece: Piece| format!("Piece {:x} appears more than once", piece.bits());
+        let mut on_board = 0u16;
+        for row in 0..4 {
+            for col in 0..4 {
+                if let Some(piece) = fields.board.piece_at(row, col) {
+                    if on_board & (1 << piece.bits()) != 0 {
+                        return Err(duplicated(piece));
+                    }
+                    on_board |= 1 << piece.bits();
+                }
+            }
+        }
+        let mut used = on_board;
+        for &piece in fields.available_pieces.iter() {
+            if used & (1 << piece.bits()) != 0 {
+                return Err(duplicated(piece));
+            }
+            used |= 1 << piece.bits();
+        }
+        let selected = 1 << fields.selected_piece.bits();
+        let is_game_over = fields.board.check_win() || fields.board.is_full();
+        if used & selected != 0 && !(is_game_over && on_board & selected != 0) {
+            return Err(duplicated(fields.selected_piece));
+        }
+        Ok(Game {
+            board: fields.board,
+            available_pieces: fields.available_pieces,
+            selected_piece: fields.selected_piece,
+            current_player: fields.current_player,
+        })
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Game {
     pub fn new() -> Self {
         let mut available_pieces = Game::create_pieces();
         // 最初のターンはどのpieceを選んでも同じなので、ランダムに最初のpieceを選ぶ
-        let selected_piece =
-            available_pieces.remove(rand::random::<usize>() % available_pieces.len());
+        let selected_piece = available_pieces.remove(rng().gen_range(0..available_pieces.len()));
         Game {
             board: Board::new(),
             available_pieces,
@@ -60,20 +122,77 @@ impl Game {
         row: usize,
         col: usize,
         piece_index: Option<usize>,
-    ) -> Result<(), String> {
+    ) -> Result<(), GameError> {
+        self.validate_action(&Action {
+            row,
+            col,
+            piece_index,
+        })?;
+        self.play_turn_unchecked(row, col, piece_index);
+        Ok(())
+    }
+
+    /// 手を検証せずに指す．探索やプレイアウトのように合法手から選んだ手を指すときに使う．
+    /// 外から受け取った手は`play_turn`で指すこと．不正な手を渡すとdebugビルドではpanicし，
+    /// releaseビルドでは局面が壊れることがある
+    pub fn play_turn_unchecked(&mut self, row: usize, col: usize, piece_index: Option<usize>) {
+        debug_assert!(
+            self.validate_action(&Action {
+                row,
+                col,
+                piece_index,
+            })
+            .is_ok(),
+            "illegal action ({}, {}, {:?})",
+            row,
+            col,
+            piece_index
+        );
+
         // selected_pieceを置く
-        self.board.place_piece(row, col, self.selected_piece)?;
+        self.board
+            .place_piece(row, col, self.selected_piece)
+            .unwrap();
 
         // 選ばれたpieceをavailable_piecesから取り除く
-        if piece_index.is_some() {
-            let piece_index = piece_index.unwrap();
+        if let Some(piece_index) = piece_index {
             self.selected_piece = self.available_pieces.remove(piece_index);
         }
 
         // ターンが終了したら、current_playerを切り替える
         self.switch_player();
+    }
 
-        Ok(())
+    /// 手が合法かどうかを調べる．置いて勝つ手なら駒を渡さなくてもよい
+    pub fn validate_action(&self, action: &Action) -> Result<(), GameError> {
+        if self.is_game_over() {
+            return Err(GameError::GameOver);
+        }
+        let (row, col) = (action.row, action.col);
+        if row >= 4 || col >= 4 {
+            return Err(GameError::OutOfBounds { row, col });
+        }
+        if self.board.piece_at(row, col).is_some() {
+            return Err(GameError::CellOccupied { row, col });
+        }
+        match action.piece_index {
+            Some(piece_index) if piece_index >= self.available_pieces.len() => {
+                Err(GameError::InvalidPieceIndex {
+                    piece_index,
+                    available: self.available_pieces.len(),
+                })
+            }
+            None if !self.available_pieces.is_empty() => {
+                let mut board = self.board;
+                board.place_piece(row, col, self.selected_piece).unwrap();
+                if board.check_win() {
+                    Ok(())
+                } else {
+                    Err(GameError::MissingPiece)
+                }
+            }
+            _ => Ok(()),
+        }
     }
 
     pub fn switch_player(&mut self) {
@@ -101,23 +220,140 @@ impl Game {
         }
     }
 
+    /// 置いて駒を渡す手をすべて返す．渡す駒が残っていなければ空．
+    /// movegenを使わずに1マスずつ調べる素朴な実装で，legal_actionsの検算にも使う
     pub fn available_actions(&self) -> Vec<Action> {
         let mut actions = vec![];
-        let available_positions = self.board.available_positions();
-        for (row, col) in available_positions {
-            for piece_index in 0..self.available_pieces.len() {
-                actions.push(Action {
-                    row,
-                    col,
-                    piece_index: Some(piece_index),
-                });
+        for row in 0..4 {
+            for col in 0..4 {
+                if self.board.piece_at(row, col).is_some() {
+                    continue;
+                }
+                for piece_index in 0..self.available_pieces.len() {
+                    actions.push(Action {
+                        row,
+                        col,
+                        piece_index: Some(piece_index),
+                    });
+                }
             }
         }
-
         actions
     }
 
+    // 盤面と渡されている駒から一意に決まるキー（置換表などに使う）
+    // 各セルを5ビット（0: 空, 1..=16: ピース+1）で並べ，最後に渡されている駒の4ビットを付ける
+    pub fn position_key(&self) -> u128 {
+        let mut key = 0u128;
+        for row in 0..4 {
+            for col in 0..4 {
+                let code = match self.board.piece_at(row, col) {
+                    Some(piece) => piece.bits() as u128 + 1,
+                    None => 0,
+                };
+                key = (key << 5) | code;
+            }
+        }
+        (key << 4) | self.selected_piece.bits() as u128
+    }
+
+    // position_keyから局面を復元する．available_piecesは駒の値の順に並べ，
+    // 手番は盤面の駒の数が偶数ならPlayer1とする
+    pub fn from_position_key(key: u128) -> Result<Game, String> {
+        let selected_piece = Piece::from_bits((key & 0xF) as u8);
+        let mut board = Board::new();
+        let mut used = 1u16 << selected_piece.bits();
+        for cell in 0..16 {
+            let code = ((key >> (4 + 5 * (15 - cell))) & 0x1F) as u8;
+            if code == 0 {
+                continue;
+            }
+            if code > 16 || used & (1 << (code - 1)) != 0 {
+                return Err(format!("Invalid position key: {:x}", key));
+            }
+            used |= 1 << (code - 1);
+            board.place_piece(cell / 4, cell % 4, Piece::from_bits(code - 1))?;
+        }
+        let available_pieces: Vec<Piece> = (0..16)
+            .filter(|bits| used & (1 << bits) == 0)
+            .map(Piece::from_bits)
+            .collect();
+        let current_player = if (16 - available_pieces.len()) % 2 == 1 {
+            Player::Player1
+        } else {
+            Player::Player2
+        };
+        Ok(Game {
+            board,
+            available_pieces,
+            selected_piece,
+            current_player,
+        })
+    }
+
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_action() {
+        let mut game = Game::new();
+        game.play_turn(0, 0, Some(0)).unwrap();
+
+        let action = |row, col, piece_index| Action {
+            row,
+            col,
+            piece_index,
+        };
+        assert_eq!(game.validate_action(&action(1, 1, Some(3))), Ok(()));
+        assert_eq!(
+            game.validate_action(&action(0, 0, Some(0))),
+            Err(GameError::CellOccupied { row: 0, col: 0 })
+        );
+        assert_eq!(
+            game.validate_action(&action(4, 0, Some(0))),
+            Err(GameError::OutOfBounds { row: 4, col: 0 })
+        );
+        assert_eq!(
+            game.validate_action(&action(1, 1, Some(14))),
+            Err(GameError::InvalidPieceIndex {
+                piece_index: 14,
+                available: 14
+            })
+        );
+        assert_eq!(
+            game.validate_action(&action(1, 1, None)),
+            Err(GameError::MissingPiece)
+        );
+        assert!(
+            game.play_turn(0, 0, Some(0)).is_err(),
+            "不正な手は指せないはず"
+        );
+        assert_eq!(
+            game.available_pieces.len(),
+            14,
+            "不正な手では局面は変わらないはず"
+        );
+    }
+
+    #[test]
+    fn test_play_turn_unchecked() {
+        let mut checked = Game::new();
+        let mut unchecked = checked.clone();
+        for action in [(0, 0, Some(3)), (1, 2, Some(0)), (3, 3, Some(5))] {
+            checked.play_turn(action.0, action.1, action.2).unwrap();
+            unchecked.play_turn_unchecked(action.0, action.1, action.2);
+            assert_eq!(
+                checked.to_position_string(),
+                unchecked.to_position_string(),
+                "合法手なら検証の有無で結果は変わらない"
+            );
+            assert_eq!(checked.current_player, unchecked.current_player);
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use super::{Action, Board, Game};
+use std::fmt;
+use std::ops::{Deref, DerefMut, Range};
+
+/// 1つの局面の合法手の最大数（16マス × 15個の駒）
+pub const MAX_ACTIONS: usize = 16 * 15;
+
+/// 空いているセルの(row, col)を順に返すイテレータ．ビットマスクを消費するだけでヒープを使わない
+#[derive(Debug, Clone)]
+pub struct Positions(u16);
+
+impl Iterator for Positions {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.0 == 0 {
+            return None;
+        }
+        let pos = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some((pos / 4, pos % 4))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Positions {}
+
+/// 合法手を順に返すイテレータ．セルごとに渡す駒のインデックスの小さい順で，
+/// 渡す駒が残っていなければ置くだけの手（piece_indexがNone）を返す
+#[derive(Debug, Clone)]
+pub struct Actions {
+    positions: Positions,
+    current: Option<(usize, usize)>,
+    gives: Range<usize>,
+    num_pieces: usize,
+}
+
+impl Iterator for Actions {
+    type Item = Action;
+
+    fn next(&mut self) -> Option<Action> {
+        if self.num_pieces == 0 {
+            let (row, col) = self.positions.next()?;
+            return Some(Action {
+                row,
+                col,
+                piece_index: None,
+            });
+        }
+        loop {
+            if let Some((row, col)) = self.current {
+                if let Some(piece_index) = self.gives.next() {
+                    return Some(Action {
+                        row,
+                        col,
+                        piece_index: Some(piece_index),
+                    });
+                }
+            }
+            self.current = Some(self.positions.next()?);
+            self.gives = 0..self.num_pieces;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let mut len = self.positions.len() * self.num_pieces.max(1);
+        if self.current.is_some() {
+            len += self.gives.len();
+        }
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Actions {}
+
+/// 固定長の配列に手を並べるリスト．探索のノードごとに作ってもヒープを使わない
+#[derive(Clone)]
+pub struct ActionList {
+    actions: [Action; MAX_ACTIONS],
+    len: usize,
+}
+
+impl ActionList {
+    pub fn new() -> Self {
+        ActionList {
+            actions: [Action {
+                row: 0,
+                col: 0,
+                piece_index: None,
+            }; MAX_ACTIONS],
+            len: 0,
+        }
+    }
+
+    /// 手を追加する．MAX_ACTIONSを超えるとpanicする
+    pub fn push(&mut self, action: Action) {
+        self.actions[self.len] = action;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl Default for ActionList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for ActionList {
+    type Target = [Action];
+
+    fn deref(&self) -> &[Action] {
+        &self.actions[..self.len]
+    }
+}
+
+impl DerefMut for ActionList {
+    fn deref_mut(&mut self) -> &mut [Action] {
+        &mut self.actions[..self.len]
+    }
+}
+
+impl fmt::Debug for ActionList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for ActionList {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Extend<Action> for ActionList {
+    fn extend<I: IntoIterator<Item = Action>>(&mut self, iter: I) {
+        for action in iter {
+            self.push(action);
+        }
+    }
+}
+
+impl FromIterator<Action> for ActionList {
+    fn from_iter<I: IntoIterator<Item = Action>>(iter: I) -> Self {
+        let mut list = ActionList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<'a> IntoIterator for &'a ActionList {
+    type Item = &'a Action;
+    type IntoIter = std::slice::Iter<'a, Action>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Board {
+    /// 空いているセルを上の行から順に返す
+    pub fn empty_positions(&self) -> Positions {
+        Positions(self.empty_cells())
+    }
+}
+
+impl Game {
+    /// 合法手をすべて返す．終局しているかどうかは呼び出し側で確認すること
+    pub fn legal_actions(&self) -> Actions {
+        Actions {
+            positions: self.placements(),
+            current: None,
+            gives: 0..0,
+            num_pieces: self.available_pieces.len(),
+        }
+    }
+
+    /// 合法手をすべてActionListに入れて返す
+    pub fn action_list(&self) -> ActionList {
+        self.legal_actions().collect()
+    }
+
+    /// 置く場所だけの手（selected_pieceを置けるセル）
+    pub fn placements(&self) -> Positions {
+        self.board.empty_positions()
+    }
+
+    /// 渡す駒だけの手（available_piecesのインデックス）
+    pub fn gives(&self) -> Range<usize> {
+        0..self.available_pieces.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::START_POSITION;
+    use crate::utils::{rng, seed_rng};
+    use rand::Rng;
+
+    #[test]
+    fn test_legal_actions() {
+        let game = Game::from_position_string(START_POSITION).unwrap();
+        let actions = game.legal_actions();
+        assert_eq!(actions.len(), 240);
+        assert_eq!(actions.collect::<Vec<_>>(), game.available_actions());
+
+        let game = Game::from_position_string("0a3./..../..../.... 5").unwrap();
+        let list = game.action_list();
+        assert_eq!(list.len(), 13 * 12);
+        assert_eq!(game.format_move(&list[0]), "d1:1");
+        assert_eq!(game.format_move(&list[12]), "a2:1");
+        assert!(list
+            .iter()
+            .all(|action| game.validate_action(action).is_ok()));
+    }
+
+    #[test]
+    fn test_legal_actions_match_available_actions() {
+        // 素朴に作ったavailable_actionsと同じ手を同じ順に返す
+        seed_rng(11);
+        for _ in 0..50 {
+            let mut game = Game::new();
+            while !game.is_game_over() && !game.available_pieces.is_empty() {
+                let expected = game.available_actions();
+                assert_eq!(
+                    game.legal_actions().collect::<Vec<_>>(),
+                    expected,
+                    "{}",
+                    game.to_position_string()
+                );
+                assert_eq!(game.action_list().to_vec(), expected);
+                let action = expected[rng().gen_range(0..expected.len())];
+                game.play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_legal_actions_without_pieces() {
+        let game = Game::from_position_string("c827/50a4/be93/1d6. f").unwrap();
+        let actions: Vec<Action> = game.legal_actions().collect();
+        assert_eq!(
+            actions,
+            vec![Action {
+                row: 3,
+                col: 3,
+                piece_index: None
+            }]
+        );
+        assert_eq!(game.gives().len(), 0);
+    }
+
+    #[test]
+    fn test_sub_moves() {
+        let game = Game::from_position_string("0a3./..../..../.... 5").unwrap();
+        let mut placements = game.placements();
+        assert_eq!(placements.len(), 13);
+        assert_eq!(placements.next(), Some((0, 3)));
+        assert_eq!(placements.len(), 12);
+        assert_eq!(game.gives(), 0..12);
+
+        let mut actions = game.legal_actions();
+        actions.next();
+        assert_eq!(actions.len(), 13 * 12 - 1);
+    }
+
+    #[test]
+    fn test_action_list() {
+        let mut list = ActionList::new();
+        assert!(list.is_empty());
+        let action = Action {
+            row: 1,
+            col: 2,
+            piece_index: Some(3),
+        };
+        list.push(action);
+        list.push(Action {
+            piece_index: None,
+            ..action
+        });
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0], action);
+        list.swap(0, 1);
+        assert_eq!(list[1], action);
+        assert_eq!(format!("{:?}", list).matches("Action").count(), 2);
+        list.clear();
+        assert!(list.is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use super::action::Action;
+use super::board::Board;
+use super::piece::Piece;
+use super::player::Player;
+use super::Game;
+
+// 局面を1行の文字列で表す記法．
+// 盤面を上の行から'/'区切りで並べ（各セルは駒の4ビットの値の16進数1文字，空なら'.'），
+// 空白を挟んで渡されている駒，手番(1 or 2)を続ける．例: "0a3./..../..../.... 5 2"
+impl Game {
+    pub fn to_position_string(&self) -> String {
+        let rows: Vec<String> = (0..4)
+            .map(|row| {
+                (0..4)
+                    .map(|col| match self.board.piece_at(row, col) {
+                        Some(piece) => format!("{:x}", piece.bits()),
+                        None => ".".to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        let player = match self.current_player {
+            Player::Player1 => 1,
+            Player::Player2 => 2,
+        };
+        format!(
+            "{} {:x} {}",
+            rows.join("/"),
+            self.selected_piece.bits(),
+            player
+        )
+    }
+
+    /// 局面の文字列を読み込む．available_piecesは駒の値の順に並べる．
+    /// 手番を省略した場合は盤面の駒の数が偶数ならPlayer1とする
+    pub fn from_position_string(position: &str) -> Result<Game, String> {
+        let fields: Vec<&str> = position.split_whitespace().collect();
+        if fields.len() < 2 || fields.len() > 3 {
+            return Err(format!("Invalid position string: {}", position));
+        }
+
+        let rows: Vec<&str> = fields[0].split('/').collect();
+        if rows.len() != 4 || rows.iter().any(|row| row.chars().count() != 4) {
+            return Err(format!("Board must have 4 rows of 4 cells: {}", fields[0]));
+        }
+        let mut board = Board::new();
+        let mut used = 0u16;
+        for (row, cells) in rows.iter().enumerate() {
+            for (col, cell) in cells.chars().enumerate() {
+                if cell == '.' {
+                    continue;
+                }
+                let bits = parse_piece(cell)?;
+                if used & (1 << bits) != 0 {
+                    return Err(format!("Piece {} appears twice", cell));
+                }
+                used |= 1 << bits;
+                board.place_piece(row, col, Piece::from_bits(bits))?;
+            }
+        }
+
+        let mut selected = fields[1].chars();
+        let selected_bits = match (selected.next(), selected.next()) {
+            (Some(c), None) => parse_piece(c)?,
+            _ => return Err(format!("Invalid selected piece: {}", fields[1])),
+        };
+        if used & (1 << selected_bits) != 0 {
+            return Err(format!(
+                "Selected piece {} is already on the board",
+                fields[1]
+            ));
+        }
+        used |= 1 << selected_bits;
+
+        let available_pieces: Vec<Piece> = (0..16)
+            .filter(|bits| used & (1 << bits) == 0)
+            .map(Piece::from_bits)
+            .collect();
+        let current_player = match fields.get(2) {
+            Some(&"1") => Player::Player1,
+            Some(&"2") => Player::Player2,
+            Some(other) => return Err(format!("Invalid player: {}", other)),
+            None if available_pieces.len() % 2 == 1 => Player::Player1,
+            None => Player::Player2,
+        };
+        Ok(Game {
+            board,
+            available_pieces,
+            selected_piece: Piece::from_bits(selected_bits),
+            current_player,
+        })
+    }
+}
+
+// 手の記法．置くセルを列(a-d)と行(1-4，上の行が1)で書き，':'の後に渡す駒の16進数1文字を続ける．
+// 渡す駒が無い最後の手は駒を省略する．例: "c3:a", "d4"
+impl Game {
+    pub fn format_move(&self, action: &Action) -> String {
+        let cell = format!("{}{}", (b'a' + action.col as u8) as char, action.row + 1);
+        match action.piece_index {
+            Some(index) => format!("{}:{:x}", cell, self.available_pieces[index].bits()),
+            None => cell,
+        }
+    }
+
+    /// 手の文字列をこの局面でのActionにする．合法かどうかは調べない
+    pub fn parse_move(&self, text: &str) -> Result<Action, String> {
+        let text = text.trim();
+        let (cell, piece) = match text.split_once(':') {
+            Some((cell, piece)) => (cell, Some(piece)),
+            None => (text, None),
+        };
+        let mut chars = cell.chars();
+        let (col, row) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(col @ 'a'..='d'), Some(row @ '1'..='4'), None) => {
+                (col as usize - 'a' as usize, row as usize - '1' as usize)
+            }
+            _ => return Err(format!("Invalid cell: {}", cell)),
+        };
+        let piece_index = match piece {
+            Some(piece) => {
+                let mut chars = piece.chars();
+                let bits = match (chars.next(), chars.next()) {
+                    (Some(c), None) => parse_piece(c)?,
+                    _ => return Err(format!("Invalid piece: {}", piece)),
+                };
+                let index = self
+                    .available_pieces
+                    .iter()
+                    .position(|p| p.bits() == bits)
+                    .ok_or_else(|| format!("Piece {} is not available", piece))?;
+                Some(index)
+            }
+            None => None,
+        };
+        Ok(Action {
+            row,
+            col,
+            piece_index,
+        })
+    }
+}
+
+fn parse_piece(c: char) -> Result<u8, String> {
+    c.to_digit(16)
+        .map(|bits| bits as u8)
+        .ok_or_else(|| format!("Invalid piece: {}", c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_string_round_trip() {
+        let mut game = Game::new();
+        game.play_turn(0, 1, Some(3)).unwrap();
+        game.play_turn(2, 2, Some(7)).unwrap();
+
+        let position = game.to_position_string();
+        let loaded = Game::from_position_string(&position).unwrap();
+        assert_eq!(loaded.board, game.board);
+        assert_eq!(loaded.selected_piece, game.selected_piece);
+        assert_eq!(loaded.current_player, game.current_player);
+        assert_eq!(loaded.available_pieces.len(), game.available_pieces.len());
+        assert_eq!(loaded.to_position_string(), position);
+    }
+
+    #[test]
+    fn test_position_string_parse() {
+        let game = Game::from_position_string("0a3./..../..../.... 5").unwrap();
+        assert_eq!(game.board.piece_at(0, 1), Some(Piece::from_bits(10)));
+        assert_eq!(game.selected_piece, Piece::from_bits(5));
+        assert_eq!(game.available_pieces.len(), 12);
+        assert_eq!(
+            game.current_player,
+            Player::Player2,
+            "3手指した後は後手番のはず"
+        );
+
+        for invalid in [
+            "0a3./..../....",
+            "0a3./..../..../.... 0",
+            "00../..../..../.... 5",
+            "0a3./..../..../.... x",
+            "0a3./..../..../.... 5 3",
+        ] {
+            assert!(
+                Game::from_position_string(invalid).is_err(),
+                "不正な文字列: {}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_move_notation() {
+        let game = Game::from_position_string("0a3./..../..../.... 5").unwrap();
+        let action = game.parse_move("c3:b").unwrap();
+        assert_eq!((action.row, action.col), (2, 2));
+        assert_eq!(
+            game.available_pieces[action.piece_index.unwrap()],
+            Piece::from_bits(11)
+        );
+        assert_eq!(game.format_move(&action), "c3:b");
+        assert_eq!(game.parse_move("d4").unwrap().piece_index, None);
+
+        for invalid in ["e1:b", "c0:b", "c3:a", "c3:bb", "c"] {
+            assert!(game.parse_move(invalid).is_err(), "不正な手: {}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_game_json_round_trip() {
+        let mut game = Game::new();
+        game.play_turn(3, 0, Some(4)).unwrap();
+
+        let loaded: Game = serde_json::from_str(&game.to_json()).unwrap();
+        assert_eq!(loaded.to_position_string(), game.to_position_string());
+        assert_eq!(
+            loaded.available_pieces, game.available_pieces,
+            "渡せる駒の順番も保たれるはず"
+        );
+    }
+
+    #[test]
+    fn test_game_json_rejects_duplicated_pieces() {
+        let game = Game::from_position_string("0a3./..../..../.... 5").unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&game.to_json()).unwrap();
+        let board_piece = serde_json::to_value(Piece::from_bits(0xa)).unwrap();
+
+        // 盤面にある駒を渡せる駒にも入れる
+        let mut duplicated = value.clone();
+        duplicated["available_pieces"][0] = board_piece.clone();
+        assert!(serde_json::from_value::<Game>(duplicated).is_err());
+
+        // 渡された駒がまだ渡せる駒に残っている
+        let mut duplicated = value.clone();
+        duplicated["selected_piece"] = value["available_pieces"][0].clone();
+        assert!(serde_json::from_value::<Game>(duplicated).is_err());
+
+        // 終局していない盤面に渡された駒が置いてある
+        value["selected_piece"] = board_piece;
+        assert!(serde_json::from_value::<Game>(value).is_err());
+
+        // 駒を渡さずに勝った後は，最後に置いた駒がselected_pieceに残る
+        let mut won = Game::from_position_string("012./..../..../.... 3").unwrap();
+        won.play_turn(0, 3, None).unwrap();
+        let loaded: Game = serde_json::from_str(&won.to_json()).unwrap();
+        assert!(loaded.is_game_over());
+    }
+}
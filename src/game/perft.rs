@@ -0,0 +1,235 @@
+use super::{Action, Board, Game, Piece};
+
+/// depth手先までの手順の数（perft）．途中で終局した手順はそこで止まり，数えない
+pub fn perft(game: &Game, depth: usize) -> u64 {
+    let remaining = game
+        .available_pieces
+        .iter()
+        .fold(0u16, |mask, piece| mask | 1 << piece.bits());
+    perft_bitboard(&game.board, game.selected_piece, remaining, depth)
+}
+
+/// 1手目ごとのperft（divide）．合計はperft(game, depth)と等しい
+pub fn perft_divide(game: &Game, depth: usize) -> Vec<(Action, u64)> {
+    if depth == 0 || game.is_game_over() {
+        return vec![];
+    }
+    game.legal_actions()
+        .map(|action| {
+            let mut next_state = game.clone();
+            next_state.play_turn_unchecked(action.row, action.col, action.piece_index);
+            let nodes = perft(&next_state, depth - 1);
+            (action, nodes)
+        })
+        .collect()
+}
+
+/// Gameを複製して1手ずつ指していく素朴なperft．perftの検算に使う
+pub fn perft_naive(game: &Game, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if game.is_game_over() {
+        return 0;
+    }
+    naive_actions(game)
+        .into_iter()
+        .map(|action| {
+            let mut next_state = game.clone();
+            next_state
+                .play_turn(action.row, action.col, action.piece_index)
+                .unwrap();
+            perft_naive(&next_state, depth - 1)
+        })
+        .sum()
+}
+
+// movegenを使わない素朴な手の生成．渡す駒が残っていなければ置くだけの手になる
+fn naive_actions(game: &Game) -> Vec<Action> {
+    if !game.available_pieces.is_empty() {
+        return game.available_actions();
+    }
+    let mut actions = vec![];
+    for row in 0..4 {
+        for col in 0..4 {
+            if game.board.piece_at(row, col).is_none() {
+                actions.push(Action {
+                    row,
+                    col,
+                    piece_index: None,
+                });
+            }
+        }
+    }
+    actions
+}
+
+// remainingは渡せる駒のビットマスク（駒の4ビットの値のビットが立つ）
+fn perft_bitboard(board: &Board, selected: Piece, remaining: u16, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if board.check_win() || board.is_full() {
+        return 0;
+    }
+    let empty_cells = board.empty_cells();
+    if depth == 1 {
+        // 最後の1手は数えるだけでよい．渡す駒がなければ置くだけの手になる
+        return empty_cells.count_ones() as u64 * remaining.count_ones().max(1) as u64;
+    }
+
+    let mut nodes = 0;
+    let mut cells = empty_cells;
+    while cells != 0 {
+        let pos = cells.trailing_zeros() as usize;
+        cells &= cells - 1;
+        let mut next_board = *board;
+        next_board.place_piece(pos / 4, pos % 4, selected).unwrap();
+        // 置いた時点で終局していれば，どの駒を渡してもその先はない
+        if next_board.check_win() || next_board.is_full() {
+            continue;
+        }
+        let mut pieces = remaining;
+        while pieces != 0 {
+            let bits = pieces.trailing_zeros();
+            pieces &= pieces - 1;
+            nodes += perft_bitboard(
+                &next_board,
+                Piece::from_bits(bits as u8),
+                remaining & !(1 << bits),
+                depth - 1,
+            );
+        }
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::START_POSITION;
+    use crate::utils::{rng, seed_rng};
+    use rand::Rng;
+
+    #[test]
+    fn test_perft_start_position() {
+        let game = Game::from_position_string(START_POSITION).unwrap();
+        assert_eq!(perft(&game, 0), 1);
+        assert_eq!(perft(&game, 1), 240);
+        assert_eq!(perft(&game, 2), 50_400);
+        assert_eq!(perft(&game, 3), 9_172_800);
+        assert_eq!(perft_naive(&game, 2), 50_400);
+    }
+
+    #[test]
+    fn test_perft_stops_at_wins() {
+        // d1に置けば勝ちなので，その先は数えない
+        let game = Game::from_position_string("012./..../..../.... 3").unwrap();
+        assert_eq!(perft(&game, 1), 13 * 12);
+        assert_eq!(perft(&game, 2), 12 * 12 * 12 * 11);
+        assert_eq!(perft_naive(&game, 2), 12 * 12 * 12 * 11);
+
+        let mut won = game.clone();
+        won.play_turn(0, 3, Some(0)).unwrap();
+        assert_eq!(perft(&won, 0), 1);
+        assert_eq!(perft(&won, 1), 0);
+        assert!(perft_divide(&won, 1).is_empty());
+    }
+
+    #[test]
+    fn test_perft_last_move() {
+        // 渡す駒が残っていないので置くだけの手が1つだけある（置くと引き分け）
+        let game = Game::from_position_string("c827/50a4/be93/1d6. f").unwrap();
+        assert_eq!(perft(&game, 1), 1);
+        assert_eq!(perft_naive(&game, 1), 1);
+        assert_eq!(perft(&game, 2), 0);
+    }
+
+    // 各局面のdepth 0からのperft．別に書いた総当たりで数えた値
+    const PERFT_POSITIONS: [(&str, &[u64]); 6] = [
+        // 空き8マス．置いて勝てるセルが2つある
+        (".1.4/..d./96../.372 0", &[1, 56, 1_764, 42_480]),
+        // 空き6マス．置いて勝てるセルが2つある
+        ("c7.0/1e../.a89/d.5. 4", &[1, 30, 400, 2_544, 6_840]),
+        // 空き3マスのうち2つで勝てるので，続くのは残りの1マスに置く手だけ
+        ("a13c/9d.5/08e./.764 b", &[1, 6, 4, 0]),
+        // 空き3マスで勝てるセルはない
+        ("708a/c3d1/.5.b/ef.6 9", &[1, 6, 12, 2, 0]),
+        // 渡す駒は1つだけで，2手目は置くだけの手になる
+        ("b81f/472c/36d./059. a", &[1, 2, 2, 0]),
+        // 渡す駒が残っておらず，最後のマスに置くと勝つ
+        ("7293/0d.c/5186/fae4 b", &[1, 1, 0]),
+    ];
+
+    #[test]
+    fn test_perft_positions() {
+        for (position, expected) in PERFT_POSITIONS {
+            let game = Game::from_position_string(position).unwrap();
+            for (depth, &nodes) in expected.iter().enumerate() {
+                assert_eq!(perft(&game, depth), nodes, "{} depth {}", position, depth);
+                assert_eq!(
+                    perft_naive(&game, depth),
+                    nodes,
+                    "{} depth {}",
+                    position,
+                    depth
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_perft_full_board_draw() {
+        // 最後のマスに置いても揃わず，盤面が埋まって引き分けになる
+        let mut game = Game::from_position_string("c827/50a4/be93/1d6. f").unwrap();
+        game.play_turn(3, 3, None).unwrap();
+        assert!(game.board.is_full());
+        assert_eq!(game.judge_winner(), None);
+        assert_eq!(perft(&game, 0), 1);
+        assert_eq!(perft_naive(&game, 0), 1);
+        assert_eq!(perft(&game, 1), 0);
+        assert_eq!(perft_naive(&game, 1), 0);
+        assert!(perft_divide(&game, 1).is_empty());
+    }
+
+    #[test]
+    fn test_perft_divide() {
+        let game = Game::from_position_string("0a3./..../..../.... 5").unwrap();
+        let divide = perft_divide(&game, 2);
+        assert_eq!(divide.len(), 13 * 12);
+        let total: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+        assert_eq!(total, perft(&game, 2));
+        for (action, nodes) in &divide {
+            let mut next_state = game.clone();
+            next_state
+                .play_turn(action.row, action.col, action.piece_index)
+                .unwrap();
+            assert_eq!(*nodes, perft_naive(&next_state, 1));
+        }
+    }
+
+    #[test]
+    fn test_perft_matches_naive() {
+        seed_rng(7);
+        for _ in 0..20 {
+            // 空きが6〜9マスになるまでランダムに指す
+            let empty_cells = rng().gen_range(6..10);
+            let mut game = Game::new();
+            while !game.is_game_over() && game.board.empty_positions().len() > empty_cells {
+                let actions = game.available_actions();
+                let action = &actions[rng().gen_range(0..actions.len())];
+                game.play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+            }
+            for depth in 0..=3 {
+                assert_eq!(
+                    perft(&game, depth),
+                    perft_naive(&game, depth),
+                    "{} depth {}",
+                    game.to_position_string(),
+                    depth
+                );
+            }
+        }
+    }
+}
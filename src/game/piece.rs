@@ -4,18 +4,61 @@ use serde::{Deserialize, Serialize};
 
 // 16ビットで表現される Piece
 #[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "PieceFields")]
 pub struct Piece(u16);
 
+// Serializeで書き出した形式から読み込むための中間表現
+#[derive(Deserialize)]
+struct PieceFields {
+    color: u8,
+    shape: u8,
+    height: u8,
+    surface: u8,
+}
+
+impl TryFrom<PieceFields> for Piece {
+    type Error = String;
+
+    fn try_from(fields: PieceFields) -> Result<Self, Self::Error> {
+        for (name, value) in [
+            ("color", fields.color),
+            ("shape", fields.shape),
+            ("height", fields.height),
+            ("surface", fields.surface),
+        ] {
+            if value > 1 {
+                return Err(format!("Invalid {}: {} (must be 0 or 1)", name, value));
+            }
+        }
+        Ok(Piece::new(
+            fields.color,
+            fields.shape,
+            fields.height,
+            fields.surface,
+        ))
+    }
+}
+
 impl Piece {
     pub fn new(color: u8, shape: u8, height: u8, surface: u8) -> Self {
         let mut piece = 0u16;
-        piece |= (color as u16) << 0; // 色は下位ビットから1ビット目
+        piece |= color as u16; // 色は下位ビットから1ビット目
         piece |= (shape as u16) << 1; // 形は下位ビットから2ビット目
         piece |= (height as u16) << 2; // 高さは下位ビットから3ビット目
         piece |= (surface as u16) << 3; // 表面は下位ビットから4ビット目
         Piece(piece)
     }
 
+    // 4ビットの値から復元する（下位ビットから色・形・高さ・表面）
+    pub fn from_bits(bits: u8) -> Self {
+        Piece((bits & 0b1111) as u16)
+    }
+
+    // 4ビットの値として取り出す
+    pub fn bits(&self) -> u8 {
+        self.0 as u8
+    }
+
     // 各属性のゲッター
     pub fn color(&self) -> u8 {
         (self.0 & 0b0001) as u8
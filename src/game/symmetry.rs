@@ -0,0 +1,303 @@
+use super::board::Board;
+use super::piece::Piece;
+use super::Game;
+use std::sync::OnceLock;
+
+/// 盤面の対称変換（ラインの集合を保つセルの置換）と，駒の属性の入れ替え・反転の組．
+/// 変換後の局面は変換前の局面と勝敗が同じになる
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Symmetry {
+    // 変換前のセル(row * 4 + col) → 変換後のセル
+    cells: [u8; 16],
+    // 変換前の属性ビット → 変換後の属性ビット
+    attributes: [u8; 4],
+    // 属性を入れ替えた後に反転するビット
+    flip: u8,
+}
+
+// ラインの集合を保つ盤面の対称変換（32通り）
+pub(crate) fn board_symmetries() -> &'static Vec<[u8; 16]> {
+    static BOARD_SYMMETRIES: OnceLock<Vec<[u8; 16]>> = OnceLock::new();
+    BOARD_SYMMETRIES.get_or_init(|| {
+        // σ(3 - i) = 3 - σ(i) を満たす行（列）の並べ替え（8通り）
+        let line_permutations: Vec<[usize; 4]> = permutations4()
+            .iter()
+            .copied()
+            .filter(|p| (0..4).all(|i| p[3 - i] == 3 - p[i]))
+            .collect();
+        let mut symmetries = vec![];
+        for col_permutation in line_permutations.iter() {
+            // 対角線が対角線に移るのは，行と列に同じ並べ替えをするか，行だけ上下反転したとき
+            let reversed = col_permutation.map(|i| 3 - i);
+            for row_permutation in [*col_permutation, reversed] {
+                for transpose in [false, true] {
+                    let mut cells = [0u8; 16];
+                    for row in 0..4 {
+                        for col in 0..4 {
+                            let (new_row, new_col) = if transpose {
+                                (col_permutation[col], row_permutation[row])
+                            } else {
+                                (row_permutation[row], col_permutation[col])
+                            };
+                            cells[row * 4 + col] = (new_row * 4 + new_col) as u8;
+                        }
+                    }
+                    symmetries.push(cells);
+                }
+            }
+        }
+        symmetries
+    })
+}
+
+// 4要素の並べ替え（24通り）
+fn permutations4() -> &'static Vec<[usize; 4]> {
+    static PERMUTATIONS: OnceLock<Vec<[usize; 4]>> = OnceLock::new();
+    PERMUTATIONS.get_or_init(|| {
+        let mut permutations = vec![];
+        for a in 0..4 {
+            for b in 0..4 {
+                for c in 0..4 {
+                    for d in 0..4 {
+                        let p = [a, b, c, d];
+                        if (0..4).all(|i| (0..i).all(|j| p[i] != p[j])) {
+                            permutations.push(p);
+                        }
+                    }
+                }
+            }
+        }
+        permutations
+    })
+}
+
+impl Symmetry {
+    pub fn identity() -> Self {
+        Symmetry {
+            cells: std::array::from_fn(|i| i as u8),
+            attributes: [0, 1, 2, 3],
+            flip: 0,
+        }
+    }
+
+    /// 盤面の対称変換と属性の入れ替え・反転をすべて列挙する（32 × 24 × 16通り）
+    pub fn all() -> Vec<Symmetry> {
+        let mut symmetries = vec![];
+        for cells in board_symmetries().iter() {
+            for attributes in permutations4().iter() {
+                for flip in 0..16 {
+                    symmetries.push(Symmetry {
+                        cells: *cells,
+                        attributes: attributes.map(|a| a as u8),
+                        flip,
+                    });
+                }
+            }
+        }
+        symmetries
+    }
+
+    pub fn map_cell(&self, row: usize, col: usize) -> (usize, usize) {
+        let cell = self.cells[row * 4 + col] as usize;
+        (cell / 4, cell % 4)
+    }
+
+    pub fn map_piece(&self, piece: Piece) -> Piece {
+        Piece::from_bits(self.map_bits(piece.bits()))
+    }
+
+    fn map_bits(&self, bits: u8) -> u8 {
+        let mut mapped = 0;
+        for (from, &to) in self.attributes.iter().enumerate() {
+            mapped |= ((bits >> from) & 1) << to;
+        }
+        mapped ^ self.flip
+    }
+
+    pub fn inverse(&self) -> Symmetry {
+        let mut cells = [0u8; 16];
+        for (from, &to) in self.cells.iter().enumerate() {
+            cells[to as usize] = from as u8;
+        }
+        let mut attributes = [0u8; 4];
+        for (from, &to) in self.attributes.iter().enumerate() {
+            attributes[to as usize] = from as u8;
+        }
+        // 反転してから属性を戻すので，反転するビットも戻した位置に移す
+        let mut flip = 0;
+        for (to, &from) in attributes.iter().enumerate() {
+            flip |= ((self.flip >> to) & 1) << from;
+        }
+        Symmetry {
+            cells,
+            attributes,
+            flip,
+        }
+    }
+
+    /// 局面を変換する．available_piecesの順番は変えないので，piece_indexはそのまま使える
+    pub fn apply(&self, game: &Game) -> Game {
+        let mut board = Board::new();
+        for row in 0..4 {
+            for col in 0..4 {
+                if let Some(piece) = game.board.piece_at(row, col) {
+                    let (new_row, new_col) = self.map_cell(row, col);
+                    board
+                        .place_piece(new_row, new_col, self.map_piece(piece))
+                        .unwrap();
+                }
+            }
+        }
+        Game {
+            board,
+            available_pieces: game
+                .available_pieces
+                .iter()
+                .map(|&piece| self.map_piece(piece))
+                .collect(),
+            selected_piece: self.map_piece(game.selected_piece),
+            current_player: game.current_player,
+        }
+    }
+}
+
+/// 対称な局面の中でposition_keyが最小になるものを代表とし，そのキーと代表に移す変換を返す
+pub fn canonical_key(game: &Game) -> (u128, Symmetry) {
+    let mut codes: [Option<u8>; 16] = [None; 16];
+    for (cell, code) in codes.iter_mut().enumerate() {
+        *code = game
+            .board
+            .piece_at(cell / 4, cell % 4)
+            .map(|piece| piece.bits());
+    }
+
+    let mut best: Option<(u128, Symmetry)> = None;
+    for cells in board_symmetries().iter() {
+        let mut transformed: [Option<u8>; 16] = [None; 16];
+        for (from, &to) in cells.iter().enumerate() {
+            transformed[to as usize] = codes[from];
+        }
+        // 変換後に最初に駒がある（なければ渡されている）駒が0になる反転を選べばキーが最小になる
+        let leading = transformed
+            .iter()
+            .flatten()
+            .next()
+            .copied()
+            .unwrap_or(game.selected_piece.bits());
+        for attributes in permutations4().iter() {
+            let mut symmetry = Symmetry {
+                cells: *cells,
+                attributes: attributes.map(|a| a as u8),
+                flip: 0,
+            };
+            symmetry.flip = symmetry.map_bits(leading);
+
+            let mut key = 0u128;
+            for code in transformed.iter() {
+                let field = match code {
+                    Some(bits) => symmetry.map_bits(*bits) as u128 + 1,
+                    None => 0,
+                };
+                key = (key << 5) | field;
+            }
+            key = (key << 4) | symmetry.map_bits(game.selected_piece.bits()) as u128;
+
+            if best.is_none_or(|(best_key, _)| key < best_key) {
+                best = Some((key, symmetry));
+            }
+        }
+    }
+    best.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::{Policy, RandomPolicy};
+
+    const LINES: [u16; 10] = [
+        0x000F, 0x00F0, 0x0F00, 0xF000, 0x1111, 0x2222, 0x4444, 0x8888, 0x1248, 0x8421,
+    ];
+
+    fn random_game(n_turns: usize) -> Game {
+        let policy = RandomPolicy::new();
+        let mut game = Game::new();
+        for _ in 0..n_turns {
+            if game.is_game_over() {
+                break;
+            }
+            let action = policy.action(&game);
+            game.play_turn(action.row, action.col, action.piece_index)
+                .unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn test_board_symmetries_preserve_lines() {
+        let symmetries = board_symmetries();
+        assert_eq!(symmetries.len(), 32, "盤面の対称変換は32通りのはず");
+        for (i, cells) in symmetries.iter().enumerate() {
+            assert!(
+                symmetries[..i].iter().all(|other| other != cells),
+                "対称変換は重複しないはず"
+            );
+            for line in LINES {
+                let mut mapped = 0u16;
+                for (cell, &to) in cells.iter().enumerate() {
+                    if line & (1 << cell) != 0 {
+                        mapped |= 1 << to;
+                    }
+                }
+                assert!(LINES.contains(&mapped), "ラインはラインに移るはず");
+            }
+        }
+    }
+
+    #[test]
+    fn test_symmetry_inverse() {
+        let piece = Piece::new(1, 0, 0, 1);
+        for symmetry in Symmetry::all().iter().step_by(97) {
+            let inverse = symmetry.inverse();
+            assert_eq!(
+                inverse.map_piece(symmetry.map_piece(piece)),
+                piece,
+                "駒が元に戻るはず"
+            );
+            let (row, col) = symmetry.map_cell(1, 2);
+            assert_eq!(inverse.map_cell(row, col), (1, 2), "セルが元に戻るはず");
+        }
+    }
+
+    #[test]
+    fn test_symmetry_preserves_win() {
+        for _ in 0..20 {
+            let game = random_game(8);
+            for symmetry in Symmetry::all().iter().step_by(131) {
+                let transformed = symmetry.apply(&game);
+                assert_eq!(
+                    transformed.board.check_win(),
+                    game.board.check_win(),
+                    "対称な局面は勝敗が同じはず"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonical_key_is_invariant() {
+        for n_turns in [0, 3, 6, 9] {
+            let game = random_game(n_turns);
+            let (key, symmetry) = canonical_key(&game);
+            assert_eq!(
+                symmetry.apply(&game).position_key(),
+                key,
+                "代表に移した局面のキーになるはず"
+            );
+            for other in Symmetry::all().iter().step_by(61) {
+                let (other_key, _) = canonical_key(&other.apply(&game));
+                assert_eq!(other_key, key, "対称な局面は同じキーになるはず");
+            }
+        }
+    }
+}
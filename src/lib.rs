@@ -1,4 +1,17 @@
+pub mod book;
+pub mod cli;
+pub mod clock;
+pub mod engine;
+pub mod evaluators;
 pub mod game;
 pub mod policies;
 pub mod runner;
+pub mod search;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod solver;
+pub mod tablebase;
+pub mod tournament;
+#[cfg(feature = "tui")]
+pub mod tui;
 pub mod utils;
\ No newline at end of file
@@ -0,0 +1,507 @@
+use crate::clock::{ClockInfo, TimeManager};
+use crate::evaluators::{Evaluator, HandcraftedEvaluator};
+use crate::game::action::Action;
+use crate::game::movegen::MAX_ACTIONS;
+use crate::game::Game;
+use crate::game::Piece;
+use crate::policies::policy::Policy;
+use crate::search::{SearchInfo, SearchLimits};
+use crate::utils::{now, TimeKeeper};
+use std::collections::HashMap;
+
+/// 勝ち・負けが確定した局面のscore（決着までの手数だけ割り引く）
+pub const WIN_SCORE: i32 = 1_000_000;
+
+// 時間切れの確認をするノード数の間隔
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// 探索の結果
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub action: Action,
+    /// 手番側から見たscore
+    pub score: i32,
+    /// 最後まで探索を終えた深さ（1手 = 置いて渡すまで）
+    pub depth: usize,
+    pub nodes: u64,
+}
+
+/// 反復深化付きのαβ法（negamax）で手を選ぶpolicy
+#[derive(Clone)]
+pub struct AlphaBetaPolicy<E: Evaluator = HandcraftedEvaluator> {
+    pub max_time: f64,
+    pub max_depth: usize,
+    /// 探索の末端で使う評価関数
+    pub evaluator: E,
+    /// 持ち時間のある対局で1手に使う時間を決める
+    pub time_manager: TimeManager,
+}
+
+impl<E: Evaluator> AlphaBetaPolicy<E> {
+    /// 末端で使う評価関数を指定して作成する
+    pub fn with_evaluator(evaluator: E) -> Self {
+        AlphaBetaPolicy {
+            max_time: 0.01,
+            max_depth: 16,
+            evaluator,
+            time_manager: TimeManager::default(),
+        }
+    }
+
+    /// 制限時間いっぱい反復深化で探索し，最後に探索を終えた深さの最善手を返す
+    pub fn search(&self, game: &Game) -> SearchResult {
+        self.search_with_time(game, self.max_time)
+    }
+
+    /// max_time秒まで探索する
+    pub fn search_with_time(&self, game: &Game, max_time: f64) -> SearchResult {
+        self.search_with_limits(game, max_time, &SearchLimits::default(), &mut |_| {})
+    }
+
+    /// max_time秒まで，limitsのノード数・深さの制限とstopに従って探索し，
+    /// 深さごとに探索を終えるたびに途中経過を報告する
+    pub fn search_with_limits(
+        &self,
+        game: &Game,
+        max_time: f64,
+        limits: &SearchLimits,
+        info: &mut dyn FnMut(&SearchInfo),
+    ) -> SearchResult {
+        // 置いて勝てる手があるなら探索するまでもない
+        if let Some((row, col)) = game.board.find_winning_cell(game.selected_piece) {
+            let piece_index = if game.available_pieces.is_empty() {
+                None
+            } else {
+                Some(0)
+            };
+            return SearchResult {
+                action: Action {
+                    row,
+                    col,
+                    piece_index,
+                },
+                score: WIN_SCORE,
+                depth: 1,
+                nodes: 1,
+            };
+        }
+
+        let mut placements = game.placements();
+        let num_placements = placements.len();
+        if num_placements == 0 {
+            panic!("No available moves left.");
+        }
+        if game.available_pieces.is_empty() {
+            // 渡す駒が無く勝てる手も無いなら，どこに置いても引き分け
+            let (row, col) = placements.next().unwrap();
+            return SearchResult {
+                action: Action {
+                    row,
+                    col,
+                    piece_index: None,
+                },
+                score: 0,
+                depth: 1,
+                nodes: 1,
+            };
+        }
+
+        let start = now();
+        let mut searcher = Searcher::new(&self.evaluator, max_time);
+        searcher.limits = Some(limits);
+        let max_depth = limits
+            .depth
+            .unwrap_or(self.max_depth)
+            .min(num_placements)
+            .max(1);
+        let mut best: Option<(SearchMove, i32, usize)> = None;
+        for depth in 1..=max_depth {
+            // 深さ1の探索は時間切れでも打ち切らず，必ず1手は返せるようにする
+            searcher.can_abort = depth > 1;
+            let (search_move, score) = searcher.search_root(game, depth);
+            if searcher.aborted {
+                break;
+            }
+            best = Some((search_move, score, depth));
+            info(&SearchInfo {
+                depth,
+                score: Some(score),
+                mate: mate_plies(score),
+                nodes: searcher.nodes,
+                elapsed: now() - start,
+                best_action: Some(search_move.to_action(game)),
+            });
+            // 勝ち負けが確定したらそれ以上深く読んでも結果は変わらない
+            if is_decisive(score) {
+                break;
+            }
+        }
+
+        let (search_move, score, depth) = best.unwrap();
+        SearchResult {
+            action: search_move.to_action(game),
+            score,
+            depth,
+            nodes: searcher.nodes,
+        }
+    }
+}
+
+impl<E: Evaluator + Default> Policy for AlphaBetaPolicy<E> {
+    fn new() -> Self {
+        AlphaBetaPolicy::with_evaluator(E::default())
+    }
+
+    fn action(&self, game: &Game) -> Action {
+        self.search(game).action
+    }
+
+    fn action_with_clock(&self, game: &Game, clock: &ClockInfo) -> Action {
+        self.search_with_time(game, self.time_manager.allocate(game, clock))
+            .action
+    }
+
+    fn action_with_limits(
+        &self,
+        game: &Game,
+        limits: &SearchLimits,
+        info: &mut dyn FnMut(&SearchInfo),
+    ) -> Action {
+        let max_time = if limits.infinite {
+            f64::INFINITY
+        } else if let Some(move_time) = limits.move_time {
+            move_time
+        } else if let Some(clock) = limits.clock {
+            self.time_manager.allocate(game, &clock)
+        } else {
+            self.max_time
+        };
+        self.search_with_limits(game, max_time, limits, info).action
+    }
+}
+
+// 並べ替えた合法手．(優先度, セル, 渡す駒のインデックス)を1つの整数にまとめて持ち，
+// ヒープを使わずに整数のまま並べ替える
+struct OrderedMoves {
+    keys: [u64; MAX_ACTIONS],
+    len: usize,
+}
+
+impl OrderedMoves {
+    fn iter(&self) -> impl Iterator<Item = Action> + '_ {
+        self.keys[..self.len].iter().map(|&key| {
+            let cell = (key >> 4) as usize & 0xF;
+            Action {
+                row: cell / 4,
+                col: cell % 4,
+                piece_index: Some(key as usize & 0xF),
+            }
+        })
+    }
+}
+
+// 探索中に扱う手．渡す駒はインデックスではなく駒そのもので持つ
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SearchMove {
+    cell: u8,
+    piece: Piece,
+}
+
+impl SearchMove {
+    fn from_action(game: &Game, action: &Action) -> Self {
+        SearchMove {
+            cell: (action.row * 4 + action.col) as u8,
+            piece: game.available_pieces[action.piece_index.unwrap()],
+        }
+    }
+
+    fn to_action(self, game: &Game) -> Action {
+        Action {
+            row: self.cell as usize / 4,
+            col: self.cell as usize % 4,
+            piece_index: game.available_pieces.iter().position(|&p| p == self.piece),
+        }
+    }
+}
+
+struct Searcher<'a, E: Evaluator> {
+    evaluator: &'a E,
+    time_keeper: TimeKeeper,
+    can_abort: bool,
+    aborted: bool,
+    nodes: u64,
+    // ノード数の上限とstopを確認するための制限
+    limits: Option<&'a SearchLimits>,
+    // 局面ごとに前回の探索で最善だった手（手の並べ替えに使う）
+    transposition_table: HashMap<u128, SearchMove>,
+    // 深さごとにβカットを起こした手
+    killers: Vec<[Option<SearchMove>; 2]>,
+    // (置くセル, 渡す駒)ごとのβカットの実績
+    history: [[u32; 16]; 16],
+}
+
+impl<'a, E: Evaluator> Searcher<'a, E> {
+    fn new(evaluator: &'a E, max_time: f64) -> Self {
+        Searcher {
+            evaluator,
+            time_keeper: TimeKeeper::new(max_time),
+            can_abort: false,
+            aborted: false,
+            nodes: 0,
+            limits: None,
+            transposition_table: HashMap::new(),
+            killers: vec![[None; 2]; 17],
+            history: [[0; 16]; 16],
+        }
+    }
+
+    fn search_root(&mut self, game: &Game, depth: usize) -> (SearchMove, i32) {
+        let moves = self.ordered_moves(game, 0);
+        let mut best_move = SearchMove::from_action(game, &moves.iter().next().unwrap());
+        let mut best_score = -WIN_SCORE - 1;
+        let mut alpha = -WIN_SCORE - 1;
+        for action in moves.iter() {
+            let search_move = SearchMove::from_action(game, &action);
+            let score = -self.negamax(&play(game, &action), depth - 1, 1, -WIN_SCORE - 1, -alpha);
+            if self.aborted {
+                break;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = search_move;
+            }
+            alpha = alpha.max(score);
+        }
+        if !self.aborted {
+            self.transposition_table
+                .insert(game.position_key(), best_move);
+        }
+        (best_move, best_score)
+    }
+
+    fn negamax(&mut self, game: &Game, depth: usize, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.can_abort && self.is_limit_reached() {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return 0;
+        }
+
+        // 直前の手番のプレイヤーが揃えていれば負け
+        if game.board.check_win() {
+            return -(WIN_SCORE - (ply - 1) as i32);
+        }
+        // 置いて勝てるなら勝ち
+        if game.board.find_winning_cell(game.selected_piece).is_some() {
+            return WIN_SCORE - ply as i32;
+        }
+        // 勝てずに最後の駒を置くなら引き分け
+        if game.available_pieces.is_empty() {
+            return 0;
+        }
+        if depth == 0 {
+            return self.evaluator.evaluate(game);
+        }
+
+        let moves = self.ordered_moves(game, ply);
+        let mut best_move = SearchMove::from_action(game, &moves.iter().next().unwrap());
+        let mut best_score = -WIN_SCORE - 1;
+        for action in moves.iter() {
+            let search_move = SearchMove::from_action(game, &action);
+            let score = -self.negamax(&play(game, &action), depth - 1, ply + 1, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = search_move;
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                self.update_cutoff_heuristics(search_move, depth, ply);
+                break;
+            }
+        }
+
+        self.transposition_table
+            .insert(game.position_key(), best_move);
+        best_score
+    }
+
+    fn is_limit_reached(&mut self) -> bool {
+        let Some(limits) = self.limits else {
+            return self.nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+                && self.time_keeper.is_time_over();
+        };
+        if limits
+            .nodes
+            .is_some_and(|max_nodes| self.nodes >= max_nodes)
+        {
+            return true;
+        }
+        self.nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+            && (self.time_keeper.is_time_over() || limits.is_stopped())
+    }
+
+    // 置換表の手 → killer手 → history順に並べた合法手
+    fn ordered_moves(&self, game: &Game, ply: usize) -> OrderedMoves {
+        let tt_move = self.transposition_table.get(&game.position_key()).copied();
+        let killers = self.killers[ply];
+        let mut moves = OrderedMoves {
+            keys: [0; MAX_ACTIONS],
+            len: 0,
+        };
+        for action in game.legal_actions() {
+            let search_move = SearchMove::from_action(game, &action);
+            let priority = if Some(search_move) == tt_move {
+                u32::MAX
+            } else if killers.contains(&Some(search_move)) {
+                u32::MAX - 1
+            } else {
+                self.history[search_move.cell as usize][search_move.piece.bits() as usize]
+            };
+            // 優先度の高い順，同じなら生成した順（セル，渡す駒のインデックスの順）に並ぶキー
+            moves.keys[moves.len] = (u64::from(!priority) << 8)
+                | u64::from(search_move.cell) << 4
+                | action.piece_index.unwrap() as u64;
+            moves.len += 1;
+        }
+        moves.keys[..moves.len].sort_unstable();
+        moves
+    }
+
+    fn update_cutoff_heuristics(&mut self, search_move: SearchMove, depth: usize, ply: usize) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(search_move) {
+            killers[1] = killers[0];
+            killers[0] = Some(search_move);
+        }
+        let history =
+            &mut self.history[search_move.cell as usize][search_move.piece.bits() as usize];
+        *history = history.saturating_add((depth * depth) as u32);
+    }
+}
+
+/// 勝ち負けが確定した局面のscoreかどうか
+pub fn is_decisive(score: i32) -> bool {
+    score.abs() >= WIN_SCORE - 64
+}
+
+/// 勝ち負けが確定したscoreなら決着までの手数を返す．負けなら負の値
+pub fn mate_plies(score: i32) -> Option<i32> {
+    if !is_decisive(score) {
+        return None;
+    }
+    let plies = WIN_SCORE - score.abs() + 1;
+    Some(if score > 0 { plies } else { -plies })
+}
+
+fn play(game: &Game, action: &Action) -> Game {
+    let mut next_state = game.clone();
+    next_state.play_turn_unchecked(action.row, action.col, action.piece_index);
+    next_state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluators::NeutralEvaluator;
+    use crate::policies::alpha_beta_policy::AlphaBetaPolicy;
+    use crate::policies::random_policy::RandomPolicy;
+    use crate::policies::test_utils::*;
+
+    #[test]
+    fn test_alpha_beta_policy_wins_when_possible() {
+        let mut game = Game::new();
+        game.board
+            .place_piece(0, 0, Piece::new(0, 1, 0, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 1, Piece::new(0, 1, 0, 1))
+            .unwrap();
+        game.board
+            .place_piece(0, 2, Piece::new(0, 1, 1, 0))
+            .unwrap();
+        game.selected_piece = Piece::new(1, 1, 1, 1);
+
+        let result = AlphaBetaPolicy::<HandcraftedEvaluator>::new().search(&game);
+        assert_eq!(
+            (result.action.row, result.action.col),
+            (0, 3),
+            "勝てるセルを選ぶはず"
+        );
+        assert_eq!(result.score, WIN_SCORE, "勝ちのscoreになるはず");
+    }
+
+    #[test]
+    fn test_alpha_beta_policy_avoids_deadly_handoff() {
+        let mut game = Game::new();
+        game.board
+            .place_piece(0, 0, Piece::new(0, 0, 0, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 1, Piece::new(1, 0, 1, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 2, Piece::new(0, 0, 1, 1))
+            .unwrap();
+        game.selected_piece = Piece::new(1, 1, 0, 1);
+        game.available_pieces = vec![Piece::new(1, 0, 0, 1), Piece::new(1, 1, 1, 0)];
+
+        let action = AlphaBetaPolicy::<HandcraftedEvaluator>::new().action(&game);
+        game.play_turn(action.row, action.col, action.piece_index)
+            .unwrap();
+        assert!(
+            game.board.find_winning_cell(game.selected_piece).is_none(),
+            "相手が置いて勝てる駒は渡さないはず"
+        );
+    }
+
+    #[test]
+    fn test_alpha_beta_policy_solves_endgame() {
+        // 残り3マスまでランダムに進めた局面は最後まで読み切れる
+        let policy = RandomPolicy::new();
+        let game = loop {
+            let mut game = Game::new();
+            while !game.is_game_over() && game.board.empty_positions().len() > 3 {
+                let action = policy.action(&game);
+                game.play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+            }
+            if !game.is_game_over() {
+                break game;
+            }
+        };
+
+        let result = AlphaBetaPolicy {
+            max_time: 1.0,
+            ..AlphaBetaPolicy::with_evaluator(NeutralEvaluator::default())
+        }
+        .search(&game);
+        assert!(
+            result.depth == 3 || is_decisive(result.score),
+            "残りの手をすべて読み切るはず"
+        );
+    }
+
+    #[test]
+    fn test_alpha_beta_policy_action() {
+        test_policy_action(AlphaBetaPolicy::<HandcraftedEvaluator>::new());
+    }
+
+    #[test]
+    fn test_alpha_beta_policy_game_progression() {
+        test_policy_game_progression(AlphaBetaPolicy::<HandcraftedEvaluator>::new());
+    }
+
+    #[test]
+    fn test_alpha_beta_policy_no_available_positions() {
+        test_policy_no_available_positions(AlphaBetaPolicy::<HandcraftedEvaluator>::new());
+    }
+
+    #[test]
+    fn test_alpha_beta_policy_no_available_pieces() {
+        test_policy_no_available_pieces(AlphaBetaPolicy::<HandcraftedEvaluator>::new());
+    }
+}
@@ -0,0 +1,145 @@
+use crate::book::OpeningBook;
+use crate::clock::ClockInfo;
+use crate::game::action::Action;
+use crate::game::Game;
+use crate::policies::mcs_policy::MCSPolicy;
+use crate::policies::policy::Policy;
+use crate::search::{SearchInfo, SearchLimits};
+use crate::utils::rng;
+use std::sync::Arc;
+
+/// 定跡に載っている局面では定跡の手を重み付きで選び，それ以外の局面はpolicyに任せるpolicy
+#[derive(Clone)]
+pub struct BookPolicy<P: Policy = MCSPolicy> {
+    pub book: Arc<OpeningBook>,
+    pub policy: P,
+    /// 重みを何乗根して使うか．大きいほど軽い手も選ばれ，0なら常に最も重い手を選ぶ
+    pub temperature: f64,
+}
+
+impl<P: Policy> BookPolicy<P> {
+    pub fn with_book(book: Arc<OpeningBook>, policy: P) -> Self {
+        BookPolicy {
+            book,
+            policy,
+            temperature: 1.0,
+        }
+    }
+
+    // 定跡から手を選ぶ．定跡が壊れていて合法でない手が出たら定跡を使わない
+    fn book_action(&self, game: &Game) -> Option<Action> {
+        self.book
+            .sample(game, self.temperature, &mut rng())
+            .filter(|action| game.validate_action(action).is_ok())
+    }
+}
+
+impl<P: Policy> Policy for BookPolicy<P> {
+    fn new() -> Self {
+        BookPolicy::with_book(Arc::new(OpeningBook::new()), P::new())
+    }
+
+    fn action(&self, game: &Game) -> Action {
+        match self.book_action(game) {
+            Some(action) => action,
+            None => self.policy.action(game),
+        }
+    }
+
+    fn action_with_clock(&self, game: &Game, clock: &ClockInfo) -> Action {
+        match self.book_action(game) {
+            Some(action) => action,
+            None => self.policy.action_with_clock(game, clock),
+        }
+    }
+
+    fn action_with_limits(
+        &self,
+        game: &Game,
+        limits: &SearchLimits,
+        info: &mut dyn FnMut(&SearchInfo),
+    ) -> Action {
+        match self.book_action(game) {
+            Some(action) => action,
+            None => self.policy.action_with_limits(game, limits, info),
+        }
+    }
+
+    fn num_threads(&self) -> usize {
+        self.policy.num_threads()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::generate_from_self_play;
+    use crate::policies::one_step_look_ahead_policy::OneStepLookAheadPolicy;
+    use crate::policies::test_utils::*;
+
+    fn book_policy() -> BookPolicy<OneStepLookAheadPolicy> {
+        let book = generate_from_self_play(&OneStepLookAheadPolicy::new(), 5, 3);
+        BookPolicy::with_book(Arc::new(book), OneStepLookAheadPolicy::new())
+    }
+
+    #[test]
+    fn test_book_policy_uses_book() {
+        let game = Game::new();
+        let action = Action {
+            row: 3,
+            col: 1,
+            piece_index: Some(2),
+        };
+        let mut book = OpeningBook::new();
+        book.add(&game, &action, 1.0);
+        let policy = BookPolicy {
+            temperature: 0.0,
+            ..BookPolicy::with_book(Arc::new(book), OneStepLookAheadPolicy::new())
+        };
+
+        assert_eq!(policy.action(&game), action, "定跡の手を選ぶはず");
+    }
+
+    #[test]
+    fn test_book_policy_falls_back_on_illegal_move() {
+        let mut game = Game::new();
+        game.play_turn(3, 1, Some(0)).unwrap();
+        // 埋まっているセルに置く手が載った定跡
+        let mut book = OpeningBook::new();
+        let illegal = Action {
+            row: 3,
+            col: 1,
+            piece_index: Some(2),
+        };
+        book.add(&game, &illegal, 1.0);
+        let policy = BookPolicy {
+            temperature: 0.0,
+            ..BookPolicy::with_book(Arc::new(book), OneStepLookAheadPolicy::new())
+        };
+
+        let action = policy.action(&game);
+        assert!(game.validate_action(&action).is_ok(), "{:?}", action);
+        let action = policy.action_with_limits(&game, &SearchLimits::default(), &mut |_| {});
+        assert!(game.validate_action(&action).is_ok(), "{:?}", action);
+    }
+
+    #[test]
+    fn test_book_policy_action() {
+        test_policy_action(book_policy());
+    }
+
+    #[test]
+    fn test_book_policy_game_progression() {
+        test_policy_game_progression(book_policy());
+    }
+
+    #[test]
+    fn test_book_policy_no_available_positions() {
+        test_policy_no_available_positions(book_policy());
+    }
+
+    #[test]
+    fn test_book_policy_no_available_pieces() {
+        test_policy_no_available_pieces(book_policy());
+    }
+}
@@ -0,0 +1,384 @@
+use crate::clock::{ClockInfo, TimeControl};
+use crate::game::action::Action;
+use crate::game::{Game, Player};
+use crate::policies::policy::Policy;
+use crate::search::{SearchInfo, SearchLimits};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 思考を止めるまでの間にstopが来ていないかを確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// QEIプロトコルを話す別プロセスのエンジンに手を考えさせるpolicy．
+/// エンジンは最初に手を聞かれたときに起動し，落ちたり応答しなくなったりした場合はpanicする
+/// （Runnerでは反則負けになる）．次に手を聞かれたときには起動し直す
+pub struct ExternalEnginePolicy {
+    pub command: String,
+    pub args: Vec<String>,
+    /// ハンドシェイクの後にsetoptionで設定する(名前, 値)
+    pub options: Vec<(String, String)>,
+    /// 時間の指定が無いときに1手に使わせる時間（秒）．nodesやdepthだけを指定した思考もこの時間で打ち切る
+    pub move_time: f64,
+    /// ハンドシェイクを待つ時間（秒）
+    pub handshake_timeout: f64,
+    /// 制限時間を過ぎてからbestmoveを待つ時間（秒）
+    pub timeout_margin: f64,
+    process: Mutex<Option<EngineProcess>>,
+}
+
+impl ExternalEnginePolicy {
+    pub fn with_command(command: &str, args: &[&str]) -> Self {
+        ExternalEnginePolicy {
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            options: vec![],
+            move_time: 0.1,
+            handshake_timeout: 5.0,
+            timeout_margin: 1.0,
+            process: Mutex::new(None),
+        }
+    }
+
+    /// エンジンのsetoptionで設定する値を追加する
+    pub fn with_option(mut self, name: &str, value: &str) -> Self {
+        self.options.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    // goの後，bestmoveが来るまで待って手を返す．deadlineを過ぎたらstopを送り，
+    // それでもtimeout_marginの間に応答が無ければエンジンを止めてpanicする
+    fn think(
+        &self,
+        game: &Game,
+        go: &str,
+        deadline: Option<f64>,
+        limits: Option<&SearchLimits>,
+        info: &mut dyn FnMut(&SearchInfo),
+    ) -> Action {
+        let mut guard = self.process.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none() {
+            *guard = Some(self.start().unwrap_or_else(|error| panic!("{}", error)));
+        }
+        let process = guard.as_mut().unwrap();
+
+        let result = process.think(game, go, deadline, limits, self.timeout_margin, info);
+        match result {
+            Ok(action) => action,
+            Err(error) => {
+                // 状態のわからないエンジンは使い続けない
+                *guard = None;
+                panic!("{}: {}", self.command, error);
+            }
+        }
+    }
+
+    fn start(&self) -> Result<EngineProcess, String> {
+        let mut process = EngineProcess::spawn(&self.command, &self.args)?;
+        let timeout = Duration::from_secs_f64(self.handshake_timeout);
+        process.send("qei")?;
+        process.wait_for("qeiok", timeout)?;
+        for (name, value) in self.options.iter() {
+            process.send(&format!("setoption name {} value {}", name, value))?;
+        }
+        process.send("isready")?;
+        process.wait_for("readyok", timeout)?;
+        Ok(process)
+    }
+}
+
+impl Policy for ExternalEnginePolicy {
+    /// PATHにあるquart-engineを使う
+    fn new() -> Self {
+        ExternalEnginePolicy::with_command("quart-engine", &[])
+    }
+
+    fn action(&self, game: &Game) -> Action {
+        let millis = (self.move_time * 1000.0) as u64;
+        self.think(
+            game,
+            &format!("go movetime {}", millis),
+            Some(self.move_time),
+            None,
+            &mut |_| {},
+        )
+    }
+
+    fn action_with_clock(&self, game: &Game, clock: &ClockInfo) -> Action {
+        self.think(
+            game,
+            &go_with_clock(game, clock),
+            Some(clock.time_limit()),
+            None,
+            &mut |_| {},
+        )
+    }
+
+    fn action_with_limits(
+        &self,
+        game: &Game,
+        limits: &SearchLimits,
+        info: &mut dyn FnMut(&SearchInfo),
+    ) -> Action {
+        let millis = |seconds: f64| (seconds * 1000.0) as u64;
+        let mut go = match (limits.move_time, limits.clock) {
+            (Some(move_time), _) => format!("go movetime {}", millis(move_time)),
+            (None, Some(clock)) => go_with_clock(game, &clock),
+            (None, None) if limits.infinite => "go".to_string(),
+            (None, None) => format!("go movetime {}", millis(self.move_time)),
+        };
+        if let Some(nodes) = limits.nodes {
+            go += &format!(" nodes {}", nodes);
+        }
+        if let Some(depth) = limits.depth {
+            go += &format!(" depth {}", depth);
+        }
+        if limits.infinite {
+            go += " infinite";
+        }
+        // 時間の指定が無くてもmove_timeで打ち切り，応答しないエンジンを待ち続けないようにする．
+        // infiniteはstopで止める
+        let deadline = if limits.infinite {
+            None
+        } else {
+            Some(
+                limits
+                    .clock_info()
+                    .map_or(self.move_time, |clock| clock.time_limit()),
+            )
+        };
+        self.think(game, &go, deadline, Some(limits), info)
+    }
+}
+
+// 持ち時間をgoのwtime/btime/winc/bincにする（wが先手のPlayer1）
+fn go_with_clock(game: &Game, clock: &ClockInfo) -> String {
+    let millis = |seconds: f64| (seconds.max(0.0) * 1000.0) as u64;
+    if let TimeControl::FixedPerMove { per_move } = clock.time_control {
+        return format!("go movetime {}", millis(per_move));
+    }
+    let (white, black) = match game.current_player {
+        Player::Player1 => (clock.remaining, clock.opponent_remaining),
+        Player::Player2 => (clock.opponent_remaining, clock.remaining),
+    };
+    let increment = millis(clock.increment());
+    format!(
+        "go wtime {} btime {} winc {} binc {}",
+        millis(white),
+        millis(black),
+        increment,
+        increment
+    )
+}
+
+/// info行を読む．読めない項目は無視する
+pub fn parse_info(game: &Game, line: &str) -> Option<SearchInfo> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.first() != Some(&"info") || tokens.get(1) == Some(&"string") {
+        return None;
+    }
+    let mut info = SearchInfo {
+        depth: 0,
+        score: None,
+        mate: None,
+        nodes: 0,
+        elapsed: 0.0,
+        best_action: None,
+    };
+    let mut i = 1;
+    while i + 1 < tokens.len() {
+        let value = tokens[i + 1];
+        match tokens[i] {
+            "depth" => info.depth = value.parse().ok()?,
+            "nodes" => info.nodes = value.parse().ok()?,
+            "time" => info.elapsed = value.parse::<u64>().ok()? as f64 / 1000.0,
+            "pv" => info.best_action = game.parse_move(value).ok(),
+            "score" if i + 2 < tokens.len() => {
+                let score = tokens[i + 2].parse().ok()?;
+                match value {
+                    "cp" => info.score = Some(score),
+                    "mate" => info.mate = Some(score),
+                    _ => {}
+                }
+                i += 1;
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+    }
+    Some(info)
+}
+
+// 起動したエンジン．標準出力は別のスレッドで読んで，待つときにタイムアウトできるようにする
+struct EngineProcess {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl EngineProcess {
+    fn spawn(command: &str, args: &[String]) -> Result<Self, String> {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", command, e))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(EngineProcess {
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("Engine closed its input: {}", e))
+    }
+
+    fn receive(&self, timeout: Duration) -> Result<Option<String>, String> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Ok(Some(line)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err("Engine exited".to_string()),
+        }
+    }
+
+    fn wait_for(&self, expected: &str, timeout: Duration) -> Result<(), String> {
+        let start = Instant::now();
+        while let Some(remaining) = timeout.checked_sub(start.elapsed()) {
+            if let Some(line) = self.receive(remaining)? {
+                if line.trim() == expected {
+                    return Ok(());
+                }
+            }
+        }
+        Err(format!("Engine did not reply {}", expected))
+    }
+
+    fn think(
+        &mut self,
+        game: &Game,
+        go: &str,
+        deadline: Option<f64>,
+        limits: Option<&SearchLimits>,
+        timeout_margin: f64,
+        info: &mut dyn FnMut(&SearchInfo),
+    ) -> Result<Action, String> {
+        self.send(&format!("position string {}", game.to_position_string()))?;
+        self.send(go)?;
+
+        let start = Instant::now();
+        let mut stop_sent_at: Option<Instant> = None;
+        loop {
+            if let Some(line) = self.receive(POLL_INTERVAL)? {
+                if let Some(text) = line.strip_prefix("bestmove") {
+                    let text = text.trim();
+                    return game
+                        .parse_move(text)
+                        .map_err(|e| format!("Invalid bestmove {}: {}", text, e));
+                }
+                if let Some(search_info) = parse_info(game, &line) {
+                    info(&search_info);
+                }
+                continue;
+            }
+
+            match stop_sent_at {
+                Some(sent_at) => {
+                    if sent_at.elapsed().as_secs_f64() > timeout_margin {
+                        return Err("Engine did not reply bestmove".to_string());
+                    }
+                }
+                None => {
+                    let time_over =
+                        deadline.is_some_and(|deadline| start.elapsed().as_secs_f64() > deadline);
+                    if time_over || limits.is_some_and(|limits| limits.is_stopped()) {
+                        self.send("stop")?;
+                        stop_sent_at = Some(Instant::now());
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        // quitで終わらなければ強制的に終了させる
+        let _ = self.send("quit");
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(200) {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info() {
+        let game = Game::from_position_string("0a3./..../..../.... 5").unwrap();
+        let info = parse_info(
+            &game,
+            "info depth 3 score mate -2 nodes 1234 time 56 nps 22035 pv b2:6",
+        )
+        .unwrap();
+        assert_eq!(info.depth, 3);
+        assert_eq!(info.mate, Some(-2));
+        assert_eq!(info.nodes, 1234);
+        assert_eq!(info.elapsed, 0.056);
+        assert_eq!(info.best_action, game.parse_move("b2:6").ok());
+        assert!(parse_info(&game, "info string hello").is_none());
+        assert!(parse_info(&game, "bestmove b2:6").is_none());
+    }
+
+    #[test]
+    fn test_go_with_clock() {
+        let mut game = Game::from_position_string("..../..../..../.... 0").unwrap();
+        let clock = ClockInfo {
+            time_control: TimeControl::Fischer {
+                initial: 10.0,
+                increment: 0.5,
+            },
+            remaining: 3.0,
+            opponent_remaining: 7.0,
+        };
+        assert_eq!(
+            go_with_clock(&game, &clock),
+            "go wtime 3000 btime 7000 winc 500 binc 500"
+        );
+        game.switch_player();
+        assert_eq!(
+            go_with_clock(&game, &clock),
+            "go wtime 7000 btime 3000 winc 500 binc 500",
+            "後手なら自分の時間はbtimeになるはず"
+        );
+    }
+}
@@ -1,91 +1,414 @@
+use crate::clock::{ClockInfo, TimeManager};
+use crate::evaluators::{Evaluator, HandcraftedEvaluator};
 use crate::game::action::Action;
-use crate::game::Game;
 use crate::game::Player;
+use crate::game::{ActionList, Game};
 use crate::policies::one_step_look_ahead_policy::OneStepLookAheadPolicy;
 use crate::policies::policy::Policy;
-use crate::utils::TimeKeeper;
+use crate::search::{SearchInfo, SearchLimits};
+use crate::utils::{now, TimeKeeper};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// ルートの各手にプレイアウトをどう割り当てるか
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Allocation {
+    /// すべての手に同じ回数ずつプレイアウトを割り当てる
+    Uniform,
+    /// UCB1に従って有望な手ほど多くのプレイアウトを割り当てる
+    Ucb1 { c: f64 },
+    /// 制限時間をラウンドに分け，ラウンドごとに平均scoreが下位半分の手を切り捨てる
+    SuccessiveHalving,
+}
+
+impl Default for Allocation {
+    fn default() -> Self {
+        Allocation::Ucb1 { c: 1.0 }
+    }
+}
 
 #[derive(Clone)]
-pub struct MCSPolicy {
-    pub policy: OneStepLookAheadPolicy,
+pub struct MCSPolicy<P: Policy = OneStepLookAheadPolicy, E: Evaluator = HandcraftedEvaluator> {
+    pub policy: P,
     pub max_time: f64,
+    pub allocation: Allocation,
+    /// プレイアウトを打ち切る手数．Noneなら終局までプレイアウトする
+    pub play_out_depth: Option<usize>,
+    /// 1手で行うプレイアウトの回数の上限．Noneなら時間だけで打ち切る
+    pub max_play_outs: Option<u64>,
+    /// プレイアウトを打ち切った局面の勝敗を決める評価関数
+    pub evaluator: E,
+    /// 持ち時間のある対局で1手に使う時間を決める
+    pub time_manager: TimeManager,
 }
 
-impl Policy for MCSPolicy {
-    fn new() -> Self {
+impl<P: Policy> MCSPolicy<P> {
+    /// プレイアウトに使うpolicyを指定して作成する
+    pub fn with_policy(policy: P) -> Self {
         MCSPolicy {
-            policy: OneStepLookAheadPolicy::new(),
+            policy,
             max_time: 0.01,
+            allocation: Allocation::default(),
+            play_out_depth: None,
+            max_play_outs: None,
+            evaluator: HandcraftedEvaluator::default(),
+            time_manager: TimeManager::default(),
         }
     }
+}
 
-    fn action(&self, game: &Game) -> Action {
+impl<P: Policy, E: Evaluator> MCSPolicy<P, E> {
+    /// プレイアウトを打ち切ったときに使う評価関数を差し替える
+    pub fn with_evaluator<F: Evaluator>(self, evaluator: F) -> MCSPolicy<P, F> {
+        MCSPolicy {
+            policy: self.policy,
+            max_time: self.max_time,
+            allocation: self.allocation,
+            play_out_depth: self.play_out_depth,
+            max_play_outs: self.max_play_outs,
+            evaluator,
+            time_manager: self.time_manager,
+        }
+    }
+
+    /// max_time秒（またはmax_play_outs回）プレイアウトして手を選ぶ．stopが立てばそこでやめる
+    fn choose_action(
+        &self,
+        game: &Game,
+        max_time: f64,
+        max_play_outs: Option<u64>,
+        stop: &AtomicBool,
+    ) -> Action {
+        // 置いて勝てる手があるなら，プレイアウトせずにその手を選択すれば良い
+        if let Some((row, col)) = game.board.find_winning_cell(game.selected_piece) {
+            let piece_index = if game.available_pieces.is_empty() {
+                None
+            } else {
+                Some(0)
+            };
+            return Action {
+                row,
+                col,
+                piece_index,
+            };
+        }
         if game.available_pieces.is_empty() {
-            // 渡せる駒が無い場合は，置いて勝てる手があるならそれを選択すれば良い
-            // これはOneStepLookAheadPolicyのactionメソッドと同じ
+            // 渡せる駒が無く，勝てる手も無い場合は置き場所によらず引き分けなので
+            // プレイアウト用のpolicyに任せる
             return self.policy.action(game);
-        } else {
-            // すべてのactionに対して、num_playouts_per_action回プレイアウトを行い、最も勝率が高いactionを選択
-            // 実行時間内でできるだけ多くのプレイアウトを行い，最も勝率が高い手を選択する
-
-            // アクションを列挙して次の状態を計算
-            let available_actions = game.available_actions();
-            let n_available_actions = available_actions.len();
-            if n_available_actions == 0 {
-                panic!("利用可能なアクションがありません");
+        }
+
+        // アクションを列挙して次の状態を計算
+        let available_actions = game.action_list();
+        if available_actions.is_empty() {
+            panic!("利用可能なアクションがありません");
+        }
+
+        // 相手が置いてすぐに勝てる駒を渡す手は候補から除外する
+        let mut candidates = ActionList::new();
+        let mut next_states: Vec<Game> = vec![];
+        for action in available_actions.iter() {
+            let mut next_state = game.clone();
+            next_state.play_turn_unchecked(action.row, action.col, action.piece_index);
+            if next_state
+                .board
+                .find_winning_cell(next_state.selected_piece)
+                .is_none()
+            {
+                candidates.push(*action);
+                next_states.push(next_state);
             }
-            let next_states: Vec<Game> = available_actions
-                .iter()
-                .map(|action| {
-                    let mut next_state = game.clone();
-                    next_state
-                        .play_turn(action.row, action.col, action.piece_index)
-                        .unwrap();
-                    next_state
-                })
-                .collect();
-
-            // 時間いっぱいプレイアウトを行う
-            let mut time_keeper = TimeKeeper::new(self.max_time);
-            let mut scores = vec![0; n_available_actions];
-            let mut counts = vec![0; n_available_actions];
-            while !time_keeper.is_time_over() {
-                for (i, next_state) in next_states.iter().enumerate() {
-                    let winner = play_out(&next_state, &self.policy);
-                    if winner.is_some() {
-                        if winner.unwrap() == game.current_player {
-                            scores[i] += 1;
-                        } else {
-                            scores[i] -= 1;
-                        }
-                    }
-                    counts[i] += 1;
+        }
+        // どの手を選んでも負ける，または候補が1つしかない場合はプレイアウトしても意味がない
+        if candidates.is_empty() {
+            return available_actions[0];
+        }
+        if candidates.len() == 1 {
+            return candidates[0];
+        }
+
+        // 時間（または回数）いっぱいプレイアウトを行う
+        let mut stats = vec![ArmStats::default(); candidates.len()];
+        let player = game.current_player;
+        let best_index = match self.allocation {
+            Allocation::Uniform => {
+                let budget = PlayOutBudget::new(max_time, max_play_outs, stop);
+                self.allocate_uniform(&next_states, player, &mut stats, budget);
+                best_mean_index(&stats)
+            }
+            Allocation::Ucb1 { c } => {
+                let budget = PlayOutBudget::new(max_time, max_play_outs, stop);
+                self.allocate_ucb1(&next_states, player, &mut stats, c, budget);
+                best_mean_index(&stats)
+            }
+            Allocation::SuccessiveHalving => self.allocate_successive_halving(
+                &next_states,
+                player,
+                &mut stats,
+                max_time,
+                max_play_outs,
+                stop,
+            ),
+        };
+
+        candidates[best_index]
+    }
+
+    fn record_play_out(&self, next_state: &Game, player: Player, stats: &mut ArmStats) {
+        let winner = play_out(
+            next_state,
+            &self.policy,
+            &self.evaluator,
+            self.play_out_depth,
+        );
+        if let Some(winner) = winner {
+            if winner == player {
+                stats.score += 1;
+            } else {
+                stats.score -= 1;
+            }
+        }
+        stats.count += 1;
+    }
+
+    fn allocate_uniform(
+        &self,
+        next_states: &[Game],
+        player: Player,
+        stats: &mut [ArmStats],
+        mut budget: PlayOutBudget<'_>,
+    ) {
+        // 1回ごとに予算を確かめるので，ラウンドの途中でも時間が来れば止める
+        loop {
+            for (i, next_state) in next_states.iter().enumerate() {
+                if budget.is_over() {
+                    return;
                 }
+                self.record_play_out(next_state, player, &mut stats[i]);
+                budget.consume(1);
             }
+        }
+    }
+
+    fn allocate_ucb1(
+        &self,
+        next_states: &[Game],
+        player: Player,
+        stats: &mut [ArmStats],
+        c: f64,
+        mut budget: PlayOutBudget<'_>,
+    ) {
+        // まだプレイアウトしていない手のUCB値は無限大とみなすので，すべての手を1回ずつ試してから絞り込む．
+        // 1回ごとに予算を確かめるので，手が多くて短い時間でも時間を超えない
+        let mut total_count = 0.0f64;
+        while !budget.is_over() {
+            let log_total = total_count.ln();
+            let mut best_index = 0;
+            let mut best_ucb = f64::MIN;
+            for (i, arm) in stats.iter().enumerate() {
+                let ucb = if arm.count == 0 {
+                    f64::INFINITY
+                } else {
+                    arm.mean() + c * (log_total / arm.count as f64).sqrt()
+                };
+                if ucb > best_ucb {
+                    best_ucb = ucb;
+                    best_index = i;
+                }
+            }
+            self.record_play_out(&next_states[best_index], player, &mut stats[best_index]);
+            budget.consume(1);
+            total_count += 1.0;
+        }
+    }
 
-            // // 平均scoreが最も高いactionを選択
-            let mut best_action: Option<Action> = None;
-            let mut best_score = std::f64::MIN;
-            for (i, action) in available_actions.iter().enumerate() {
-                let score = scores[i] as f64 / counts[i] as f64;
-                if score > best_score {
-                    best_score = score;
-                    best_action = Some(action.clone());
+    // 生き残った1手のインデックスを返す
+    fn allocate_successive_halving(
+        &self,
+        next_states: &[Game],
+        player: Player,
+        stats: &mut [ArmStats],
+        max_time: f64,
+        max_play_outs: Option<u64>,
+        stop: &AtomicBool,
+    ) -> usize {
+        let start = now();
+        let mut alive: Vec<usize> = (0..next_states.len()).collect();
+        // 1手に絞り込むまでに必要なラウンド数で制限時間（と回数）を等分する
+        let n_rounds = (next_states.len() as f64).log2().ceil().max(1.0);
+        let round_time = max_time / n_rounds;
+        let per_round = max_play_outs.map(|max_play_outs| max_play_outs / n_rounds as u64);
+        let mut remaining = max_play_outs;
+        while alive.len() > 1 {
+            // 残った手には1ラウンドで少なくとも1回ずつプレイアウトを配る．
+            // 全体の残りがそれに足りなければ絞り込みをやめる
+            let round_play_outs = per_round
+                .zip(remaining)
+                .map(|(per_round, remaining)| per_round.max(alive.len() as u64).min(remaining));
+            if round_play_outs.is_some_and(|play_outs| play_outs < alive.len() as u64) {
+                break;
+            }
+            let mut budget = PlayOutBudget::new(round_time, round_play_outs, stop);
+            // 1回ごとに予算を確かめ，ラウンドの途中でも時間が来ればそこでやめる
+            let mut completed = false;
+            'round: loop {
+                for &i in alive.iter() {
+                    if budget.is_over() {
+                        break 'round;
+                    }
+                    self.record_play_out(&next_states[i], player, &mut stats[i]);
+                    budget.consume(1);
+                    if let Some(remaining) = remaining.as_mut() {
+                        *remaining -= 1;
+                    }
+                }
+                completed = true;
+            }
+            // 一周し終えていなければプレイアウトしていない手があるので，切り捨てずにやめる
+            if !completed {
+                break;
+            }
+            alive.sort_by(|&a, &b| stats[b].mean().total_cmp(&stats[a].mean()));
+            alive.truncate(alive.len().div_ceil(2));
+        }
+        if alive.len() > 1 {
+            // 絞り込みを途中でやめたときは，残りの予算を残った手に順に配る
+            let mut budget = PlayOutBudget::new(max_time - (now() - start), remaining, stop);
+            'rest: loop {
+                for &i in alive.iter() {
+                    if budget.is_over() {
+                        break 'rest;
+                    }
+                    self.record_play_out(&next_states[i], player, &mut stats[i]);
+                    budget.consume(1);
                 }
             }
+        }
+        alive
+            .into_iter()
+            .max_by(|&a, &b| stats[a].mean().total_cmp(&stats[b].mean()))
+            .unwrap()
+    }
+}
+
+impl<P: Policy, E: Evaluator + Default> Policy for MCSPolicy<P, E> {
+    fn new() -> Self {
+        MCSPolicy::with_policy(P::new()).with_evaluator(E::default())
+    }
+
+    fn action(&self, game: &Game) -> Action {
+        let stop = AtomicBool::new(false);
+        self.choose_action(game, self.max_time, self.max_play_outs, &stop)
+    }
+
+    fn action_with_clock(&self, game: &Game, clock: &ClockInfo) -> Action {
+        let stop = AtomicBool::new(false);
+        let max_time = self.time_manager.allocate(game, clock);
+        self.choose_action(game, max_time, self.max_play_outs, &stop)
+    }
+
+    /// nodesはプレイアウトの回数の上限として扱う．infiniteならstopかnodesまでプレイアウトを続ける
+    fn action_with_limits(
+        &self,
+        game: &Game,
+        limits: &SearchLimits,
+        _info: &mut dyn FnMut(&SearchInfo),
+    ) -> Action {
+        let max_time = if limits.infinite {
+            f64::INFINITY
+        } else if let Some(move_time) = limits.move_time {
+            move_time
+        } else if let Some(clock) = limits.clock {
+            self.time_manager.allocate(game, &clock)
+        } else {
+            self.max_time
+        };
+        let max_play_outs = limits.nodes.or(self.max_play_outs);
+        self.choose_action(game, max_time, max_play_outs, &limits.stop)
+    }
+}
+
+/// プレイアウトを続けてよいかを，時間と回数とstopのフラグで判断する
+struct PlayOutBudget<'a> {
+    time_keeper: TimeKeeper,
+    remaining: Option<u64>,
+    stop: &'a AtomicBool,
+}
+
+impl<'a> PlayOutBudget<'a> {
+    fn new(max_time: f64, max_play_outs: Option<u64>, stop: &'a AtomicBool) -> Self {
+        PlayOutBudget {
+            time_keeper: TimeKeeper::new(max_time),
+            remaining: max_play_outs,
+            stop,
+        }
+    }
+
+    fn consume(&mut self, play_outs: u64) {
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining = remaining.saturating_sub(play_outs);
+        }
+    }
+
+    fn is_over(&mut self) -> bool {
+        self.remaining == Some(0)
+            || self.stop.load(Ordering::Relaxed)
+            || self.time_keeper.is_time_over()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ArmStats {
+    score: i64,
+    count: u64,
+}
+
+impl ArmStats {
+    fn mean(&self) -> f64 {
+        if self.count == 0 {
+            f64::MIN
+        } else {
+            self.score as f64 / self.count as f64
+        }
+    }
+}
 
-            best_action.unwrap()
+// 平均scoreが最も高い手のインデックス
+fn best_mean_index(stats: &[ArmStats]) -> usize {
+    let mut best_index = 0;
+    let mut best_score = f64::MIN;
+    for (i, arm) in stats.iter().enumerate() {
+        let score = arm.mean();
+        if score > best_score {
+            best_score = score;
+            best_index = i;
         }
     }
+    best_index
 }
 
-fn play_out(game: &Game, policy: &OneStepLookAheadPolicy) -> Option<Player> {
+fn play_out<P: Policy, E: Evaluator>(
+    game: &Game,
+    policy: &P,
+    evaluator: &E,
+    max_turns: Option<usize>,
+) -> Option<Player> {
     let mut game_copy = game.clone();
+    let mut turns = 0;
     while !game_copy.is_game_over() {
+        if max_turns.is_some_and(|max_turns| turns >= max_turns) {
+            // 打ち切った局面は評価値の符号で勝敗を決める
+            let score = evaluator.evaluate(&game_copy);
+            return match score.cmp(&0) {
+                std::cmp::Ordering::Greater => Some(game_copy.current_player),
+                std::cmp::Ordering::Less => Some(game_copy.current_player.opponent()),
+                std::cmp::Ordering::Equal => None,
+            };
+        }
         let action = policy.action(&game_copy);
-        game_copy
-            .play_turn(action.row, action.col, action.piece_index)
-            .unwrap();
+        game_copy.play_turn_unchecked(action.row, action.col, action.piece_index);
+        turns += 1;
     }
 
     game_copy.judge_winner()
@@ -94,26 +417,239 @@ fn play_out(game: &Game, policy: &OneStepLookAheadPolicy) -> Option<Player> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::Piece;
     use crate::policies::mcs_policy::MCSPolicy;
+    use crate::policies::random_policy::RandomPolicy;
     use crate::policies::test_utils::*;
+    use crate::utils::seed_rng;
+
+    fn mcs_policy(allocation: Allocation) -> MCSPolicy {
+        MCSPolicy {
+            allocation,
+            ..MCSPolicy::new()
+        }
+    }
 
     #[test]
     fn test_mcs_policy_action() {
-        test_policy_action(MCSPolicy::new());
+        test_policy_action(MCSPolicy::<OneStepLookAheadPolicy>::new());
     }
 
     #[test]
     fn test_mcs_policy_game_progression() {
-        test_policy_game_progression(MCSPolicy::new());
+        test_policy_game_progression(mcs_policy(Allocation::Uniform));
+        test_policy_game_progression(mcs_policy(Allocation::Ucb1 { c: 1.0 }));
+        test_policy_game_progression(mcs_policy(Allocation::SuccessiveHalving));
+    }
+
+    #[test]
+    fn test_mcs_policy_with_random_play_out() {
+        test_policy_game_progression(MCSPolicy::with_policy(RandomPolicy::new()));
+    }
+
+    #[test]
+    fn test_mcs_policy_with_play_out_cutoff() {
+        test_policy_game_progression(MCSPolicy {
+            play_out_depth: Some(2),
+            ..MCSPolicy::with_policy(RandomPolicy::new())
+        });
+    }
+
+    #[test]
+    fn test_mcs_policy_with_play_out_budget() {
+        // 時間ではなく回数で打ち切るので，乱数のseedが同じなら同じ手を選ぶ
+        for allocation in [
+            Allocation::Uniform,
+            Allocation::Ucb1 { c: 1.0 },
+            Allocation::SuccessiveHalving,
+        ] {
+            let policy = MCSPolicy {
+                max_time: 3600.0,
+                max_play_outs: Some(500),
+                ..mcs_policy(allocation)
+            };
+            let mut game = Game::new();
+            game.play_turn(0, 0, Some(0)).unwrap();
+            seed_rng(7);
+            let first = policy.action(&game);
+            seed_rng(7);
+            let second = policy.action(&game);
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn test_mcs_policy_stops_within_round() {
+        // 候補の数より予算が少なくても，1ラウンドを終えるまで続けたりしない
+        let mut game = Game::new();
+        game.play_turn(0, 0, Some(0)).unwrap();
+        let next_states: Vec<Game> = game
+            .legal_actions()
+            .map(|action| {
+                let mut next_state = game.clone();
+                next_state
+                    .play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+                next_state
+            })
+            .collect();
+        let policy = MCSPolicy {
+            max_play_outs: Some(10),
+            ..mcs_policy(Allocation::Uniform)
+        };
+        let player = game.current_player;
+        let total = |stats: &[ArmStats]| stats.iter().map(|arm| arm.count).sum::<u64>();
+
+        let mut stats = vec![ArmStats::default(); next_states.len()];
+        policy.allocate_uniform(
+            &next_states,
+            player,
+            &mut stats,
+            PlayOutBudget::new(3600.0, Some(10), &AtomicBool::new(false)),
+        );
+        assert_eq!(total(&stats), 10);
+
+        let mut stats = vec![ArmStats::default(); next_states.len()];
+        policy.allocate_ucb1(
+            &next_states,
+            player,
+            &mut stats,
+            1.0,
+            PlayOutBudget::new(3600.0, Some(10), &AtomicBool::new(false)),
+        );
+        assert_eq!(total(&stats), 10);
+
+        let mut stats = vec![ArmStats::default(); next_states.len()];
+        policy.allocate_successive_halving(
+            &next_states,
+            player,
+            &mut stats,
+            3600.0,
+            Some(10),
+            &AtomicBool::new(false),
+        );
+        assert_eq!(total(&stats), 10);
+    }
+
+    #[test]
+    fn test_successive_halving_plays_out_every_survivor() {
+        // 1ラウンドあたりの回数が候補の数より少なくても，プレイアウトしていない手は切り捨てない
+        let mut game = Game::new();
+        game.play_turn(0, 0, Some(0)).unwrap();
+        let next_states: Vec<Game> = game
+            .legal_actions()
+            .map(|action| {
+                let mut next_state = game.clone();
+                next_state
+                    .play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+                next_state
+            })
+            .collect();
+        let max_play_outs = next_states.len() as u64 + 5;
+        let policy = mcs_policy(Allocation::SuccessiveHalving);
+        let mut stats = vec![ArmStats::default(); next_states.len()];
+        let best = policy.allocate_successive_halving(
+            &next_states,
+            game.current_player,
+            &mut stats,
+            3600.0,
+            Some(max_play_outs),
+            &AtomicBool::new(false),
+        );
+        assert!(stats.iter().all(|arm| arm.count >= 1));
+        assert_eq!(
+            stats.iter().map(|arm| arm.count).sum::<u64>(),
+            max_play_outs
+        );
+        assert!(stats.iter().all(|arm| arm.mean() <= stats[best].mean()));
+    }
+
+    #[test]
+    fn test_mcs_policy_honors_search_limits() {
+        let mut game = Game::new();
+        game.play_turn(0, 0, Some(0)).unwrap();
+        for allocation in [
+            Allocation::Uniform,
+            Allocation::Ucb1 { c: 1.0 },
+            Allocation::SuccessiveHalving,
+        ] {
+            let policy = mcs_policy(allocation);
+            // infiniteでもnodes回プレイアウトすれば終わる
+            let limits = SearchLimits {
+                infinite: true,
+                nodes: Some(50),
+                ..SearchLimits::default()
+            };
+            let action = policy.action_with_limits(&game, &limits, &mut |_| {});
+            assert!(game.validate_action(&action).is_ok());
+
+            // infiniteでもstopが立てば終わる
+            let limits = SearchLimits {
+                infinite: true,
+                ..SearchLimits::default()
+            };
+            let stop = limits.stop.clone();
+            let stopper = std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                stop.store(true, Ordering::Relaxed);
+            });
+            let action = policy.action_with_limits(&game, &limits, &mut |_| {});
+            stopper.join().unwrap();
+            assert!(game.validate_action(&action).is_ok());
+        }
     }
 
     #[test]
     fn test_mcs_policy_no_available_positions() {
-        test_policy_no_available_positions(MCSPolicy::new());
+        test_policy_no_available_positions(MCSPolicy::<OneStepLookAheadPolicy>::new());
     }
 
     #[test]
     fn test_mcs_policy_no_available_pieces() {
-        test_policy_no_available_pieces(MCSPolicy::new());
+        test_policy_no_available_pieces(MCSPolicy::<OneStepLookAheadPolicy>::new());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_mcs_policy_wins_when_possible() {
+        let mut game = Game::new();
+        game.board
+            .place_piece(0, 0, Piece::new(0, 1, 0, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 1, Piece::new(0, 1, 0, 1))
+            .unwrap();
+        game.board
+            .place_piece(0, 2, Piece::new(0, 1, 1, 0))
+            .unwrap();
+        game.selected_piece = Piece::new(1, 1, 1, 1);
+
+        let action = mcs_policy(Allocation::SuccessiveHalving).action(&game);
+        assert_eq!((action.row, action.col), (0, 3), "勝てるセルを選ぶはず");
+    }
+
+    #[test]
+    fn test_mcs_policy_avoids_deadly_handoff() {
+        // (0, 3)が空いたままだと形が揃うので，形が丸(0)の駒を渡すと負ける
+        let mut game = Game::new();
+        game.board
+            .place_piece(0, 0, Piece::new(0, 0, 0, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 1, Piece::new(1, 0, 1, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 2, Piece::new(0, 0, 1, 1))
+            .unwrap();
+        game.selected_piece = Piece::new(1, 1, 0, 1);
+        game.available_pieces = vec![Piece::new(1, 0, 0, 1), Piece::new(1, 1, 1, 0)];
+
+        let action = mcs_policy(Allocation::Ucb1 { c: 1.0 }).action(&game);
+        game.play_turn(action.row, action.col, action.piece_index)
+            .unwrap();
+        assert!(
+            game.board.find_winning_cell(game.selected_piece).is_none(),
+            "相手が置いて勝てる駒は渡さないはず"
+        );
+    }
+}
@@ -1,10 +1,22 @@
 pub mod policy;
 pub mod random_policy;
 pub mod one_step_look_ahead_policy;
+pub mod two_step_look_ahead_policy;
 pub mod mcs_policy;
+pub mod alpha_beta_policy;
+pub mod tablebase_policy;
+pub mod book_policy;
+pub mod external_engine_policy;
+pub mod policy_config;
 pub mod test_utils;
 
 pub use policy::Policy;
 pub use random_policy::RandomPolicy;
 pub use one_step_look_ahead_policy::OneStepLookAheadPolicy;
-pub use mcs_policy::MCSPolicy;
\ No newline at end of file
+pub use two_step_look_ahead_policy::TwoStepLookAheadPolicy;
+pub use mcs_policy::{Allocation, MCSPolicy};
+pub use alpha_beta_policy::AlphaBetaPolicy;
+pub use tablebase_policy::TablebasePolicy;
+pub use book_policy::BookPolicy;
+pub use external_engine_policy::ExternalEnginePolicy;
+pub use policy_config::PolicyConfig;
\ No newline at end of file
@@ -1,8 +1,8 @@
 use crate::game::Game;
 use crate::game::action::Action;
 use crate::policies::policy::Policy;
+use crate::utils::rng;
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
 use rand::Rng;
 
 #[derive(Clone)]
@@ -14,83 +14,75 @@ impl Policy for OneStepLookAheadPolicy {
     }
 
     fn action(&self, game: &Game) -> Action {
-        let mut rng = thread_rng();
-        let winning_cell = game.board.find_winning_cell(game.selected_piece);
-        let mut available_positions: Vec<(usize, usize)> = game.board.available_positions();
+        let mut rng = rng();
+        if let Some((row, col)) = game.board.find_winning_cell(game.selected_piece) {
+            // 勝利する手がある場合は、その手を返す
+            // 渡すpieceはランダム．渡すpieceがない場合は、Noneを返す
+            let piece_index = if game.available_pieces.is_empty() {
+                None
+            } else {
+                Some(rng.gen_range(game.gives()))
+            };
+            return Action {
+                row,
+                col,
+                piece_index,
+            };
+        }
+
+        // 置く場所と渡すpieceをそれぞれシャッフルして順に調べる．すべての手を並べてシャッフルするより速い
+        let mut cells = [(0, 0); 16];
+        let mut num_cells = 0;
+        for position in game.placements() {
+            cells[num_cells] = position;
+            num_cells += 1;
+        }
         // 利用可能な位置がない場合のエラーチェック
-        if available_positions.is_empty() {
+        if num_cells == 0 {
             panic!("No available moves left.");
         }
-        available_positions.shuffle(&mut rng);
+        let cells = &mut cells[..num_cells];
+        cells.shuffle(&mut rng);
+        let mut pieces = [0; 16];
+        let pieces = &mut pieces[..game.available_pieces.len()];
+        for (i, piece_index) in pieces.iter_mut().enumerate() {
+            *piece_index = i;
+        }
+        pieces.shuffle(&mut rng);
 
-        if winning_cell.is_some() {
-            // 勝利する手がある場合は、その手を返す
-            let position = winning_cell.unwrap();
-            
-            // 渡すpieceがない場合は、Noneを返す
-            if game.available_pieces.is_empty() {
-                let piece_index = None;
-                return Action {
-                    row: position.0,
-                    col: position.1,
-                    piece_index: piece_index,
-                };
-            }
-            // 渡すpieceはランダム
-            let piece_index = Some(rng.gen_range(0..game.available_pieces.len()));
+        // 渡すpieceがない場合は，どこに置いても同じ
+        let Some(&first_piece) = pieces.first() else {
             return Action {
-                row: position.0,
-                col: position.1,
-                piece_index: piece_index,
-            };
-        } else if game.available_pieces.is_empty() {
-            // 勝利できる手がなくて，渡すpieceがない場合は，ランダムな場所に置く
-            let position = available_positions[0];
-            return Action {
-                row: position.0,
-                col: position.1,
+                row: cells[0].0,
+                col: cells[0].1,
                 piece_index: None,
             };
-        } else {
-            // 勝利する手がない場合は、置いて、渡したときに負けない手を返す
-            // available_piecesをシャッフル
-            let mut available_pieces = game.available_pieces.clone();
-            available_pieces.shuffle(&mut rng);
+        };
 
-            // positionとpieceの組合せで、負けない手を全探索
-            for cand_position in available_positions.iter() {
-                for piece in available_pieces.iter() {
-                    // 一手進めたゲームを作成
-                    let mut game_copy = game.clone();
-                    let piece_index = game_copy.available_pieces.iter().position(|&x| x == *piece);
-                    game_copy
-                        .play_turn(cand_position.0, cand_position.1, piece_index)
-                        .unwrap();
-                    let winning_cell = game_copy.board.find_winning_cell(*piece);
-                    // 負けない手がある場合は、その手を返す
-                    if winning_cell.is_none() {
-                        return Action {
-                            row: cand_position.0,
-                            col: cand_position.1,
-                            piece_index: piece_index,
-                        };
-                    }
+        // 勝利する手がない場合は、置いて、渡したときに負けない手を返す
+        for &(row, col) in cells.iter() {
+            let mut board = game.board;
+            board.place_piece(row, col, game.selected_piece).unwrap();
+            for &piece_index in pieces.iter() {
+                // 負けない手がある場合は、その手を返す
+                if board
+                    .find_winning_cell(game.available_pieces[piece_index])
+                    .is_none()
+                {
+                    return Action {
+                        row,
+                        col,
+                        piece_index: Some(piece_index),
+                    };
                 }
             }
         }
 
         // どの手も負ける場合は、ランダムな手を返す
-        let piece_index: Option<usize>;
-        if game.available_pieces.is_empty() {
-            piece_index = None;
-        } else {
-            piece_index = Some(rng.gen_range(0..game.available_pieces.len()));
-        }
-        let position = available_positions[0];
         Action {
-            row: position.0,
-            col: position.1,
-            piece_index: piece_index,
+            row: cells[0].0,
+            col: cells[0].1,
+            piece_index: Some(first_piece),
         }
     }
 }
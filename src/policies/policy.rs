@@ -1,11 +1,38 @@
 use crate::game::Game;
 use crate::game::action::Action;
+use crate::clock::ClockInfo;
+use crate::search::{SearchInfo, SearchLimits};
 
-pub trait Policy {
+/// 対局中に別のスレッドで手を考えられるように，policyはSendでなければならない
+pub trait Policy: Send {
     fn new() -> Self
     where
         Self: Sized;
 
     /// CPUが次の手を決定するためのメソッド
     fn action(&self, game: &Game) -> Action;
+
+    /// 持ち時間のある対局で次の手を決定するメソッド．時間を気にしないpolicyはactionと同じ
+    fn action_with_clock(&self, game: &Game, _clock: &ClockInfo) -> Action {
+        self.action(game)
+    }
+
+    /// 時間やノード数の制限を指定して次の手を決めるメソッド．途中経過はinfoで報告する．
+    /// 途中経過を出さないpolicyは，時間の制限だけを見てaction_with_clockかactionで手を決める
+    fn action_with_limits(
+        &self,
+        game: &Game,
+        limits: &SearchLimits,
+        _info: &mut dyn FnMut(&SearchInfo),
+    ) -> Action {
+        match limits.clock_info() {
+            Some(clock) => self.action_with_clock(game, &clock),
+            None => self.action(game),
+        }
+    }
+
+    /// 1手を決めるのに使うスレッド数．並列に対局するときのスレッド数の配分に使う
+    fn num_threads(&self) -> usize {
+        1
+    }
 }
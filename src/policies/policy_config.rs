@@ -0,0 +1,181 @@
+use crate::evaluators::{HandcraftedEvaluator, HandcraftedWeights};
+use crate::policies::{
+    Allocation, AlphaBetaPolicy, ExternalEnginePolicy, MCSPolicy, OneStepLookAheadPolicy, Policy,
+    RandomPolicy, TwoStepLookAheadPolicy,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+fn default_max_time() -> f64 {
+    0.01
+}
+
+fn default_move_time() -> f64 {
+    0.1
+}
+
+fn default_max_depth() -> usize {
+    16
+}
+
+/// JSONなどの設定から作れるpolicyの種類とパラメータ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PolicyConfig {
+    Random,
+    OneStepLookAhead,
+    TwoStepLookAhead,
+    Mcs {
+        #[serde(default = "default_max_time")]
+        max_time: f64,
+        #[serde(default)]
+        play_out_depth: Option<usize>,
+        /// 1手で行うプレイアウトの回数の上限
+        #[serde(default)]
+        max_play_outs: Option<u64>,
+        /// ルートの各手へのプレイアウトの割り当て方
+        #[serde(default)]
+        allocation: Allocation,
+        #[serde(default)]
+        weights: HandcraftedWeights,
+    },
+    AlphaBeta {
+        #[serde(default = "default_max_time")]
+        max_time: f64,
+        #[serde(default = "default_max_depth")]
+        max_depth: usize,
+        #[serde(default)]
+        weights: HandcraftedWeights,
+    },
+    /// QEIプロトコルを話す別プロセスのエンジン
+    External {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        /// setoptionで設定する値
+        #[serde(default)]
+        options: BTreeMap<String, String>,
+        /// 時間の指定が無いときに1手に使わせる時間
+        #[serde(default = "default_move_time")]
+        move_time: f64,
+    },
+}
+
+impl PolicyConfig {
+    pub fn build(&self) -> Box<dyn Policy> {
+        match self {
+            PolicyConfig::Random => Box::new(RandomPolicy::new()),
+            PolicyConfig::OneStepLookAhead => Box::new(OneStepLookAheadPolicy::new()),
+            PolicyConfig::TwoStepLookAhead => Box::new(TwoStepLookAheadPolicy::new()),
+            PolicyConfig::Mcs {
+                max_time,
+                play_out_depth,
+                max_play_outs,
+                allocation,
+                weights,
+            } => Box::new(MCSPolicy {
+                max_time: *max_time,
+                play_out_depth: *play_out_depth,
+                max_play_outs: *max_play_outs,
+                allocation: *allocation,
+                evaluator: HandcraftedEvaluator {
+                    weights: weights.clone(),
+                },
+                ..MCSPolicy::with_policy(OneStepLookAheadPolicy::new())
+            }),
+            PolicyConfig::AlphaBeta {
+                max_time,
+                max_depth,
+                weights,
+            } => Box::new(AlphaBetaPolicy {
+                max_time: *max_time,
+                max_depth: *max_depth,
+                ..AlphaBetaPolicy::with_evaluator(HandcraftedEvaluator {
+                    weights: weights.clone(),
+                })
+            }),
+            PolicyConfig::External {
+                command,
+                args,
+                options,
+                move_time,
+            } => {
+                let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+                let mut policy = ExternalEnginePolicy::with_command(command, &args);
+                policy.move_time = *move_time;
+                for (name, value) in options.iter() {
+                    policy = policy.with_option(name, value);
+                }
+                Box::new(policy)
+            }
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn test_policy_config_from_json() {
+        let config =
+            PolicyConfig::from_json(r#"{"type": "alpha_beta", "max_time": 0.002}"#).unwrap();
+        assert_eq!(
+            config,
+            PolicyConfig::AlphaBeta {
+                max_time: 0.002,
+                max_depth: 16,
+                weights: HandcraftedWeights::default(),
+            },
+            "書かれていないパラメータはデフォルト値になるはず"
+        );
+        assert!(PolicyConfig::from_json(r#"{"type": "unknown"}"#).is_err());
+
+        let config = PolicyConfig::from_json(
+            r#"{"type": "mcs", "allocation": {"type": "successive_halving"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config,
+            PolicyConfig::Mcs {
+                allocation: Allocation::SuccessiveHalving,
+                ..
+            }
+        ));
+        let config = PolicyConfig::from_json(r#"{"type": "mcs"}"#).unwrap();
+        assert!(matches!(
+            config,
+            PolicyConfig::Mcs {
+                allocation: Allocation::Ucb1 { c: 1.0 },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_policy_config_build() {
+        let game = Game::new();
+        for config in [
+            PolicyConfig::Random,
+            PolicyConfig::TwoStepLookAhead,
+            PolicyConfig::Mcs {
+                max_time: 0.001,
+                play_out_depth: Some(4),
+                max_play_outs: None,
+                allocation: Allocation::SuccessiveHalving,
+                weights: HandcraftedWeights::default(),
+            },
+        ] {
+            let action = config.build().action(&game);
+            assert!(game
+                .clone()
+                .play_turn(action.row, action.col, action.piece_index)
+                .is_ok());
+        }
+    }
+}
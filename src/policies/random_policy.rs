@@ -1,8 +1,7 @@
 use crate::game::action::Action;
 use crate::game::Game;
 use crate::policies::policy::Policy;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use crate::utils::rng;
 use rand::Rng;
 
 #[derive(Clone)]
@@ -14,33 +13,32 @@ impl Policy for RandomPolicy {
     }
 
     fn action(&self, game: &Game) -> Action {
-        let mut rng = thread_rng();
+        let mut rng = rng();
 
         // 利用可能な位置を取得する
-        let available_positions: Vec<(usize, usize)> = game.board.available_positions();
+        let mut placements = game.placements();
 
         // 利用可能な位置がない場合のエラーチェック
-        if available_positions.is_empty() {
+        if placements.len() == 0 {
             panic!("No available moves left.");
         }
 
-        let piece_index: Option<usize>;
-        if game.available_pieces.is_empty() {
-            piece_index = None;
+        let piece_index: Option<usize> = if game.available_pieces.is_empty() {
+            None
         } else {
             // ランダムなピースを選ぶ
-            piece_index = Some(rng.gen_range(0..game.available_pieces.len()));
-        }
+            Some(rng.gen_range(game.gives()))
+        };
 
         // ランダムな位置を選ぶ
-        let position = available_positions
-            .choose(&mut rng)
+        let position = placements
+            .nth(rng.gen_range(0..placements.len()))
             .expect("No available positions found.");
 
         Action {
             row: position.0,
             col: position.1,
-            piece_index: piece_index,
+            piece_index,
         }
     }
 }
@@ -0,0 +1,89 @@
+use crate::clock::ClockInfo;
+use crate::game::action::Action;
+use crate::game::Game;
+use crate::policies::policy::Policy;
+use crate::policies::two_step_look_ahead_policy::TwoStepLookAheadPolicy;
+use crate::search::{SearchInfo, SearchLimits};
+use crate::tablebase::Tablebase;
+use std::sync::Arc;
+
+/// Tablebaseに載っている局面では最善手を指し，それ以外の局面はpolicyに任せるpolicy
+#[derive(Clone)]
+pub struct TablebasePolicy<P: Policy = TwoStepLookAheadPolicy> {
+    pub tablebase: Arc<Tablebase>,
+    pub policy: P,
+}
+
+impl<P: Policy> TablebasePolicy<P> {
+    pub fn with_tablebase(tablebase: Arc<Tablebase>, policy: P) -> Self {
+        TablebasePolicy { tablebase, policy }
+    }
+}
+
+impl<P: Policy> Policy for TablebasePolicy<P> {
+    fn new() -> Self {
+        TablebasePolicy::with_tablebase(Arc::new(Tablebase::default()), P::new())
+    }
+
+    fn action(&self, game: &Game) -> Action {
+        match self.tablebase.best_action(game) {
+            Ok((action, _)) => action,
+            Err(_) => self.policy.action(game),
+        }
+    }
+
+    fn action_with_clock(&self, game: &Game, clock: &ClockInfo) -> Action {
+        match self.tablebase.best_action(game) {
+            Ok((action, _)) => action,
+            Err(_) => self.policy.action_with_clock(game, clock),
+        }
+    }
+
+    fn action_with_limits(
+        &self,
+        game: &Game,
+        limits: &SearchLimits,
+        info: &mut dyn FnMut(&SearchInfo),
+    ) -> Action {
+        match self.tablebase.best_action(game) {
+            Ok((action, _)) => action,
+            Err(_) => self.policy.action_with_limits(game, limits, info),
+        }
+    }
+
+    fn num_threads(&self) -> usize {
+        self.policy.num_threads()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::test_utils::*;
+    use crate::tablebase::TablebaseGenerator;
+
+    fn tablebase_policy() -> TablebasePolicy {
+        let tablebase = TablebaseGenerator::new(0).generate();
+        TablebasePolicy::with_tablebase(Arc::new(tablebase), TwoStepLookAheadPolicy::new())
+    }
+
+    #[test]
+    fn test_tablebase_policy_action() {
+        test_policy_action(tablebase_policy());
+    }
+
+    #[test]
+    fn test_tablebase_policy_game_progression() {
+        test_policy_game_progression(tablebase_policy());
+    }
+
+    #[test]
+    fn test_tablebase_policy_no_available_positions() {
+        test_policy_no_available_positions(tablebase_policy());
+    }
+
+    #[test]
+    fn test_tablebase_policy_no_available_pieces() {
+        test_policy_no_available_pieces(tablebase_policy());
+    }
+}
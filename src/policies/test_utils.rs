@@ -41,7 +41,7 @@ pub fn test_policy_no_available_positions<P: Policy>(policy: P) {
     let piece = Piece::new(0, 0, 0, 0);
     for row in 0..4 {
         for col in 0..4 {
-            game.board.place_piece(row, col, piece.clone()).unwrap();
+            game.board.place_piece(row, col, piece).unwrap();
         }
     }
 
@@ -0,0 +1,192 @@
+use crate::game::action::Action;
+use crate::game::Game;
+use crate::policies::policy::Policy;
+use crate::utils::rng;
+use rand::prelude::SliceRandom;
+use rand::Rng;
+
+/// 2手先（相手が置いて渡すまで）を読んで，相手に安全な駒が残らない手を優先するpolicy
+#[derive(Clone)]
+pub struct TwoStepLookAheadPolicy {}
+
+impl TwoStepLookAheadPolicy {
+    /// 自分の手を指した後の局面で，相手がどこに置いても渡せる安全な駒の数の最大値を返す．
+    /// 0なら相手は必ず自分が勝てる駒を渡すことになる
+    pub fn count_opponent_safe_pieces(next_state: &Game) -> usize {
+        // 相手が最後の駒を置くだけなら引き分けなので，安全な駒が1つ残っているとみなす
+        if next_state.available_pieces.is_empty() {
+            return 1;
+        }
+
+        let mut max_safe_pieces = 0;
+        for (row, col) in next_state.placements() {
+            let mut board = next_state.board;
+            board
+                .place_piece(row, col, next_state.selected_piece)
+                .unwrap();
+            // 揃いかけのラインが無ければ，どの駒を渡しても安全
+            let safe_pieces = if board.count_three_piece_lines() == 0 {
+                next_state.available_pieces.len()
+            } else {
+                next_state
+                    .available_pieces
+                    .iter()
+                    .filter(|&&piece| board.find_winning_cell(piece).is_none())
+                    .count()
+            };
+            max_safe_pieces = max_safe_pieces.max(safe_pieces);
+        }
+        max_safe_pieces
+    }
+}
+
+impl Policy for TwoStepLookAheadPolicy {
+    fn new() -> Self {
+        TwoStepLookAheadPolicy {}
+    }
+
+    fn action(&self, game: &Game) -> Action {
+        let mut rng = rng();
+        let mut actions = game.action_list();
+        // 利用可能な位置がない場合のエラーチェック
+        if actions.is_empty() {
+            panic!("No available moves left.");
+        }
+        actions.shuffle(&mut rng);
+
+        // 勝利する手がある場合は、その手を返す
+        if let Some((row, col)) = game.board.find_winning_cell(game.selected_piece) {
+            let piece_index = if game.available_pieces.is_empty() {
+                None
+            } else {
+                Some(rng.gen_range(game.gives()))
+            };
+            return Action {
+                row,
+                col,
+                piece_index,
+            };
+        }
+
+        // 渡すpieceがない場合は，どこに置いても同じなのでランダムな場所に置く
+        if game.available_pieces.is_empty() {
+            return actions[0];
+        }
+
+        // 相手がすぐに勝てない手のうち，相手に残る安全な駒が最も少ない手を選ぶ
+        let mut best_action: Option<Action> = None;
+        let mut best_count = usize::MAX;
+        for action in actions.iter() {
+            let mut next_state = game.clone();
+            next_state.play_turn_unchecked(action.row, action.col, action.piece_index);
+            if next_state
+                .board
+                .find_winning_cell(next_state.selected_piece)
+                .is_some()
+            {
+                continue;
+            }
+
+            let count = Self::count_opponent_safe_pieces(&next_state);
+            if count < best_count {
+                best_count = count;
+                best_action = Some(*action);
+                // 相手に安全な駒が残らないなら，これ以上良い手は無い
+                if count == 0 {
+                    return *action;
+                }
+            }
+        }
+
+        // どの手も負ける場合は、ランダムな手を返す
+        best_action.unwrap_or(actions[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Piece;
+    use crate::policies::random_policy::RandomPolicy;
+    use crate::policies::test_utils::*;
+    use crate::policies::two_step_look_ahead_policy::TwoStepLookAheadPolicy;
+
+    #[test]
+    fn test_two_step_look_ahead_policy_wins_when_possible() {
+        let mut game = Game::new();
+        game.board
+            .place_piece(0, 0, Piece::new(0, 1, 0, 0))
+            .unwrap();
+        game.board
+            .place_piece(0, 1, Piece::new(0, 1, 0, 1))
+            .unwrap();
+        game.board
+            .place_piece(0, 2, Piece::new(0, 1, 1, 0))
+            .unwrap();
+        game.selected_piece = Piece::new(1, 1, 1, 1);
+
+        let action = TwoStepLookAheadPolicy::new().action(&game);
+        assert_eq!((action.row, action.col), (0, 3), "勝てるセルを選ぶはず");
+    }
+
+    #[test]
+    fn test_two_step_look_ahead_policy_leaves_no_safe_pieces() {
+        // 相手に安全な駒を残さない手があるなら，その手を選ぶはず
+        let random_policy = RandomPolicy::new();
+        let policy = TwoStepLookAheadPolicy::new();
+        let mut n_checked = 0;
+        while n_checked < 20 {
+            let mut game = Game::new();
+            while !game.is_game_over() && game.board.empty_positions().len() > 6 {
+                let action = random_policy.action(&game);
+                game.play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+            }
+            if game.is_game_over() || game.board.find_winning_cell(game.selected_piece).is_some() {
+                continue;
+            }
+
+            let count_after = |action: &Action| {
+                let mut next_state = game.clone();
+                next_state
+                    .play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+                if next_state
+                    .board
+                    .find_winning_cell(next_state.selected_piece)
+                    .is_some()
+                {
+                    usize::MAX
+                } else {
+                    TwoStepLookAheadPolicy::count_opponent_safe_pieces(&next_state)
+                }
+            };
+            let best_count = game.available_actions().iter().map(count_after).min();
+            let action = policy.action(&game);
+            n_checked += 1;
+            if best_count != Some(usize::MAX) {
+                assert_eq!(Some(count_after(&action)), best_count, "最善の手を選ぶはず");
+            }
+        }
+    }
+
+    #[test]
+    fn test_two_step_look_ahead_policy_action() {
+        test_policy_action(TwoStepLookAheadPolicy::new());
+    }
+
+    #[test]
+    fn test_two_step_look_ahead_policy_game_progression() {
+        test_policy_game_progression(TwoStepLookAheadPolicy::new());
+    }
+
+    #[test]
+    fn test_two_step_look_ahead_policy_no_available_positions() {
+        test_policy_no_available_positions(TwoStepLookAheadPolicy::new());
+    }
+
+    #[test]
+    fn test_two_step_look_ahead_policy_no_available_pieces() {
+        test_policy_no_available_pieces(TwoStepLookAheadPolicy::new());
+    }
+}
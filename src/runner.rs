@@ -1,11 +1,60 @@
+use crate::clock::{ClockInfo, GameClock};
+use crate::game::action::Action;
 use crate::game::Game;
+use crate::game::GameError;
 use crate::game::Player;
 use crate::policies::policy::Policy;
+use crate::search::SearchLimits;
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 反則負けの理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForfeitReason {
+    /// 不正な手を指した
+    IllegalAction(Action, GameError),
+    /// 手を考えている途中でpanicした
+    Panic(String),
+    /// 1手の制限時間を超えた，または持ち時間を使い切った
+    Timeout,
+}
+
+/// 対局が終わった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum EndReason {
+    /// 駒が4つ揃った
+    Quarto,
+    /// 盤面が埋まって引き分け
+    Draw,
+    /// playerが反則負け
+    Forfeit {
+        player: Player,
+        reason: ForfeitReason,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameResult {
+    pub winner: Option<Player>,
+    pub reason: EndReason,
+}
 
 pub struct Runner {
     pub game: Game,
     pub player1: Box<dyn Policy>,
     pub player2: Box<dyn Policy>,
+    /// 1手の制限時間（秒）．Noneなら制限しない
+    ///
+    /// 時間を制限するとpolicyは別のスレッドで考える．時間切れになったスレッドは止められないので，
+    /// 考え終わるまで（終わらなければプロセスが終わるまで）CPUとメモリを使い続ける．
+    /// 止まらないおそれのあるbotは`ExternalEnginePolicy`で別のプロセスとして動かすこと
+    pub move_time_limit: Option<f64>,
+    /// 持ち時間．設定すると各手番のpolicyに残り時間を渡し，時間切れを反則負けにする．
+    /// 時間切れのスレッドの扱いはmove_time_limitと同じ
+    pub clock: Option<GameClock>,
 }
 
 impl Runner {
@@ -14,22 +63,433 @@ impl Runner {
             game: Game::new(),
             player1,
             player2,
+            move_time_limit: None,
+            clock: None,
+        }
+    }
+
+    /// gameの局面から対局を始める
+    pub fn with_game(game: Game, player1: Box<dyn Policy>, player2: Box<dyn Policy>) -> Self {
+        Runner {
+            game,
+            ..Runner::new(player1, player2)
         }
     }
 
     pub fn run(&mut self) -> Option<Player> {
+        self.run_game().winner
+    }
+
+    /// 対局して勝者と終局の理由を返す．
+    /// policyがpanicする，制限時間を超える，不正な手を指す場合はその手番のプレイヤーの反則負けとする
+    pub fn run_game(&mut self) -> GameResult {
         while !self.game.is_game_over() {
-            let action;
-            if matches!(self.game.current_player, Player::Player1) {
-                action = self.player1.action(&self.game);
-            } else {
-                action = self.player2.action(&self.game);
+            let player = self.game.current_player;
+            let action = match self.think(player) {
+                Ok(action) => action,
+                Err(reason) => return forfeit(player, reason),
+            };
+            if let Err(error) = self.game.validate_action(&action) {
+                return forfeit(player, ForfeitReason::IllegalAction(action, error));
             }
             self.game
                 .play_turn(action.row, action.col, action.piece_index)
                 .unwrap();
         }
 
-        self.game.judge_winner()
+        let winner = self.game.judge_winner();
+        GameResult {
+            winner,
+            reason: if winner.is_some() {
+                EndReason::Quarto
+            } else {
+                EndReason::Draw
+            },
+        }
+    }
+
+    fn think(&mut self, player: Player) -> Result<Action, ForfeitReason> {
+        let clock_info = self.clock.as_ref().map(|clock| clock.info(player));
+        let time_limit = match (self.move_time_limit, clock_info) {
+            (Some(limit), Some(info)) => Some(limit.min(info.time_limit())),
+            (limit, info) => limit.or(info.map(|info| info.time_limit())),
+        };
+
+        let start_time = Instant::now();
+        let result = self.think_within(player, clock_info, time_limit);
+        let elapsed = start_time.elapsed().as_secs_f64();
+        if let Some(clock) = self.clock.as_mut() {
+            if !clock.charge(player, elapsed) {
+                return Err(ForfeitReason::Timeout);
+            }
+        }
+        result
+    }
+
+    fn think_within(
+        &mut self,
+        player: Player,
+        clock_info: Option<ClockInfo>,
+        time_limit: Option<f64>,
+    ) -> Result<Action, ForfeitReason> {
+        let policy = match player {
+            Player::Player1 => &mut self.player1,
+            Player::Player2 => &mut self.player2,
+        };
+        let limits = SearchLimits {
+            clock: clock_info,
+            ..SearchLimits::default()
+        };
+        let Some(time_limit) = time_limit else {
+            return catch_unwind(AssertUnwindSafe(|| {
+                policy.action_with_limits(&self.game, &limits, &mut |_| {})
+            }))
+            .map_err(|payload| ForfeitReason::Panic(panic_message(payload)));
+        };
+
+        // 制限時間を超えたら待たずに打ち切れるように，別のスレッドで考えさせる．
+        // 時間切れになったらstopで思考を止め，スレッドが終わるのを待ってpolicyを使い続ける
+        let start_time = Instant::now();
+        let stop = limits.stop.clone();
+        let game = &self.game;
+        std::thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            let thinking = scope.spawn(move || {
+                let result = catch_unwind(AssertUnwindSafe(|| {
+                    policy.action_with_limits(game, &limits, &mut |_| {})
+                }));
+                let _ = sender.send(());
+                result
+            });
+            let timed_out = receiver
+                .recv_timeout(Duration::from_secs_f64(time_limit.max(0.0)))
+                .is_err();
+            if timed_out {
+                stop.store(true, Ordering::Relaxed);
+            }
+            // panicはスレッドの中で捕まえているのでjoinは失敗しない
+            let result = thinking.join().unwrap();
+            if timed_out || start_time.elapsed().as_secs_f64() > time_limit {
+                return Err(ForfeitReason::Timeout);
+            }
+            result.map_err(|payload| ForfeitReason::Panic(panic_message(payload)))
+        })
+    }
+}
+
+fn forfeit(player: Player, reason: ForfeitReason) -> GameResult {
+    GameResult {
+        winner: Some(player.opponent()),
+        reason: EndReason::Forfeit { player, reason },
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// 対局ごとにpolicyを作る関数．policyはスレッドをまたがないので，作る関数だけを共有する
+pub type PolicyFactory = Box<dyn Fn() -> Box<dyn Policy> + Send + Sync>;
+
+/// player1とplayer2の対局を複数のスレッドで並列に行う
+pub struct ParallelRunner {
+    pub player1: PolicyFactory,
+    pub player2: PolicyFactory,
+    /// 全体で使うスレッド数の上限
+    pub num_threads: usize,
+}
+
+impl ParallelRunner {
+    pub fn new(player1: PolicyFactory, player2: PolicyFactory) -> Self {
+        ParallelRunner {
+            player1,
+            player2,
+            num_threads: available_threads(),
+        }
+    }
+
+    /// n_games局対局し，各局の勝者を対局の番号順に返す
+    pub fn run(&self, n_games: usize) -> Vec<Option<Player>> {
+        let threads_per_game = (self.player1)()
+            .num_threads()
+            .max((self.player2)().num_threads());
+        run_jobs(
+            n_games,
+            num_workers(self.num_threads, threads_per_game),
+            |_| Runner::new((self.player1)(), (self.player2)()).run(),
+        )
+    }
+}
+
+/// 使えるスレッド数
+pub fn available_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// 1局にthreads_per_gameスレッド使うとき，num_threadsを超えないように同時に行える対局数
+pub fn num_workers(num_threads: usize, threads_per_game: usize) -> usize {
+    (num_threads / threads_per_game.max(1)).max(1)
+}
+
+/// job(0..n_jobs)をn_workers個のスレッドで実行し，結果を番号順に返す
+pub fn run_jobs<T, F>(n_jobs: usize, n_workers: usize, job: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    let next_job = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(n_jobs));
+    std::thread::scope(|scope| {
+        for _ in 0..n_workers.clamp(1, n_jobs.max(1)) {
+            scope.spawn(|| loop {
+                let index = next_job.fetch_add(1, Ordering::Relaxed);
+                if index >= n_jobs {
+                    break;
+                }
+                let result = job(index);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(index, _)| index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{TimeControl, TimeManager};
+    use crate::evaluators::HandcraftedEvaluator;
+    use crate::policies::{AlphaBetaPolicy, MCSPolicy, OneStepLookAheadPolicy, RandomPolicy};
+    use crate::search::SearchInfo;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    // 常に左上に置こうとするpolicy
+    struct IllegalPolicy {}
+
+    impl Policy for IllegalPolicy {
+        fn new() -> Self {
+            IllegalPolicy {}
+        }
+
+        fn action(&self, _game: &Game) -> Action {
+            Action {
+                row: 0,
+                col: 0,
+                piece_index: Some(0),
+            }
+        }
+    }
+
+    struct PanicPolicy {}
+
+    impl Policy for PanicPolicy {
+        fn new() -> Self {
+            PanicPolicy {}
+        }
+
+        fn action(&self, _game: &Game) -> Action {
+            panic!("broken policy");
+        }
+    }
+
+    // 200ミリ秒かけて手を選ぶpolicy．stopされたらそこでやめ，stoppedを立てる
+    #[derive(Default)]
+    struct SlowPolicy {
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Policy for SlowPolicy {
+        fn new() -> Self {
+            SlowPolicy::default()
+        }
+
+        fn action(&self, game: &Game) -> Action {
+            std::thread::sleep(Duration::from_millis(200));
+            RandomPolicy::new().action(game)
+        }
+
+        fn action_with_limits(
+            &self,
+            game: &Game,
+            limits: &SearchLimits,
+            _info: &mut dyn FnMut(&SearchInfo),
+        ) -> Action {
+            let start_time = Instant::now();
+            while start_time.elapsed() < Duration::from_millis(200) {
+                if limits.is_stopped() {
+                    self.stopped.store(true, Ordering::Relaxed);
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            RandomPolicy::new().action(game)
+        }
+    }
+
+    #[test]
+    fn test_runner_forfeits_illegal_action() {
+        let mut runner = Runner::new(Box::new(IllegalPolicy {}), Box::new(IllegalPolicy {}));
+        let result = runner.run_game();
+        assert_eq!(
+            result.winner,
+            Some(Player::Player1),
+            "2手目で反則負けのはず"
+        );
+        assert_eq!(
+            result.reason,
+            EndReason::Forfeit {
+                player: Player::Player2,
+                reason: ForfeitReason::IllegalAction(
+                    IllegalPolicy {}.action(&runner.game),
+                    GameError::CellOccupied { row: 0, col: 0 }
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn test_runner_forfeits_panic() {
+        for move_time_limit in [None, Some(1.0)] {
+            let mut runner = Runner {
+                move_time_limit,
+                ..Runner::new(Box::new(PanicPolicy {}), Box::new(RandomPolicy::new()))
+            };
+            let result = runner.run_game();
+            assert_eq!(result.winner, Some(Player::Player2));
+            assert_eq!(
+                result.reason,
+                EndReason::Forfeit {
+                    player: Player::Player1,
+                    reason: ForfeitReason::Panic("broken policy".to_string()),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_runner_enforces_clock() {
+        let mut runner = Runner {
+            clock: Some(GameClock::new(TimeControl::SuddenDeath { total: 0.5 })),
+            ..Runner::new(Box::new(RandomPolicy::new()), Box::new(SlowPolicy::new()))
+        };
+        let result = runner.run_game();
+        assert_eq!(
+            result.reason,
+            EndReason::Forfeit {
+                player: Player::Player2,
+                reason: ForfeitReason::Timeout,
+            },
+            "持ち時間を使い切ったら反則負けのはず"
+        );
+        assert!(runner.clock.unwrap().remaining(Player::Player1) > 0.0);
+
+        // 並列に走る他のテストでスレッドが遅れても時間切れにならないよう，余裕を大きく残させる
+        let time_manager = TimeManager {
+            safety_margin: 0.5,
+            ..Default::default()
+        };
+        let mut runner = Runner {
+            clock: Some(GameClock::new(TimeControl::Fischer {
+                initial: 1.0,
+                increment: 0.02,
+            })),
+            ..Runner::new(
+                Box::new(AlphaBetaPolicy::<HandcraftedEvaluator> {
+                    time_manager: time_manager.clone(),
+                    ..AlphaBetaPolicy::new()
+                }),
+                Box::new(MCSPolicy::<OneStepLookAheadPolicy> {
+                    time_manager,
+                    ..MCSPolicy::new()
+                }),
+            )
+        };
+        let result = runner.run_game();
+        assert!(
+            matches!(result.reason, EndReason::Quarto | EndReason::Draw),
+            "時間を管理するpolicyは時間切れにならないはず: {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_runner_forfeits_timeout() {
+        let slow = SlowPolicy::new();
+        let stopped = slow.stopped.clone();
+        let mut runner = Runner {
+            move_time_limit: Some(0.05),
+            ..Runner::new(Box::new(RandomPolicy::new()), Box::new(slow))
+        };
+        let result = runner.run_game();
+        assert!(
+            stopped.load(Ordering::Relaxed),
+            "時間切れになったら思考を止め，終わるのを待つはず"
+        );
+        assert_eq!(
+            result.reason,
+            EndReason::Forfeit {
+                player: Player::Player2,
+                reason: ForfeitReason::Timeout,
+            }
+        );
+
+        let mut runner = Runner {
+            move_time_limit: Some(1.0),
+            ..Runner::new(Box::new(RandomPolicy::new()), Box::new(RandomPolicy::new()))
+        };
+        let result = runner.run_game();
+        assert!(
+            matches!(result.reason, EndReason::Quarto | EndReason::Draw),
+            "制限時間内に指せば普通に終局するはず"
+        );
+    }
+
+    #[test]
+    fn test_run_jobs_keeps_order() {
+        let results = run_jobs(100, 4, |index| index * 2);
+        assert_eq!(results, (0..100).map(|i| i * 2).collect::<Vec<_>>());
+        assert!(run_jobs(0, 4, |index| index).is_empty());
+    }
+
+    #[test]
+    fn test_num_workers() {
+        assert_eq!(num_workers(32, 1), 32);
+        assert_eq!(num_workers(32, 4), 8);
+        assert_eq!(num_workers(2, 4), 1, "最低1局は対局するはず");
+    }
+
+    #[test]
+    fn test_parallel_runner() {
+        let runner = ParallelRunner {
+            num_threads: 4,
+            ..ParallelRunner::new(
+                Box::new(|| Box::new(OneStepLookAheadPolicy::new())),
+                Box::new(|| Box::new(RandomPolicy::new())),
+            )
+        };
+        let results = runner.run(20);
+        assert_eq!(results.len(), 20);
+        let n_wins = results
+            .iter()
+            .filter(|&&winner| winner == Some(Player::Player1))
+            .count();
+        // 乱数はワーカーのスレッドごとなのでseedを固定できない．偶然では外れない緩い下限で確かめる
+        assert!(
+            n_wins > 10,
+            "OneStepLookAheadPolicyが勝ち越すはず: {}",
+            n_wins
+        );
     }
 }
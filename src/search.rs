@@ -0,0 +1,56 @@
+use crate::clock::{ClockInfo, TimeControl};
+use crate::game::action::Action;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 1回の思考の制限．指定されなかった制限はpolicyの設定に任せる
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    /// この手に使う時間（秒）
+    pub move_time: Option<f64>,
+    /// 持ち時間の状態．move_timeがあればそちらを優先する
+    pub clock: Option<ClockInfo>,
+    /// 探索するノード数の上限
+    pub nodes: Option<u64>,
+    /// 探索する深さの上限
+    pub depth: Option<usize>,
+    /// stopされるまで（または読み切るまで）考え続ける
+    pub infinite: bool,
+    /// 外から思考を止めるためのフラグ
+    pub stop: Arc<AtomicBool>,
+}
+
+impl SearchLimits {
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// 時間の制限を時計の状態として返す．move_timeは1手ごとの持ち時間として扱う
+    pub fn clock_info(&self) -> Option<ClockInfo> {
+        match self.move_time {
+            Some(per_move) => Some(ClockInfo {
+                time_control: TimeControl::FixedPerMove { per_move },
+                remaining: per_move,
+                opponent_remaining: per_move,
+            }),
+            None => self.clock,
+        }
+    }
+}
+
+/// 思考中に報告する途中経過
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchInfo {
+    /// 探索を終えた深さ
+    pub depth: usize,
+    /// 手番側から見たscore
+    pub score: Option<i32>,
+    /// 決着までの手数．負けなら負の値
+    pub mate: Option<i32>,
+    pub nodes: u64,
+    /// 思考を始めてからの時間（秒）
+    pub elapsed: f64,
+    /// 現時点での最善手
+    pub best_action: Option<Action>,
+}
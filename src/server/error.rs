@@ -0,0 +1,71 @@
+use crate::game::GameError;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+
+/// APIのエラー．{"error": メッセージ, "game_error": 不正な手の詳細} のJSONで返す
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+    pub game_error: Option<GameError>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    game_error: Option<&'a GameError>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+            game_error: None,
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn not_found(id: u64) -> Self {
+        ApiError::new(StatusCode::NOT_FOUND, format!("Game {} not found", id))
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::CONFLICT, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+/// 終局後の手は409，それ以外の不正な手は422にする
+impl From<GameError> for ApiError {
+    fn from(error: GameError) -> Self {
+        let status = match error {
+            GameError::GameOver => StatusCode::CONFLICT,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        ApiError {
+            status,
+            message: error.to_string(),
+            game_error: Some(error),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: &self.message,
+            game_error: self.game_error.as_ref(),
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
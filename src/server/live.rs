@@ -0,0 +1,517 @@
+//! 対局の様子を流すWebSocket．`GET /games/{id}/live?role=player|spectator&from=<手数>` で接続する
+//!
+//! playerは接続した順に空いている手番（先手から）に割り当てられ，自分の手番でしか指せない．
+//! 両方の手番が埋まっていれば409を返す．接続が切れるとその手番は空く
+//!
+//! サーバー → クライアント（すべて"type"で種類を表すJSON）
+//! - `joined`: playerとして接続したときに，割り当てられた手番を送る
+//! - `state`: 対局の全体の状態．接続したときと`sync`を頼まれたときに送る
+//! - `move`: 手が指された．numberは何手目か（1から）
+//! - `status`: 手が指された後の対局の状態（進行中か，勝者，手番）
+//! - `thinking`: エンジンの思考の途中経過．numberは考えている手が何手目か
+//! - `error`: そのクライアントの要求が失敗した
+//!
+//! クライアント → サーバー（playerのみ．spectatorは`sync`だけ送れる）
+//! - `{"type": "move", "move": "c3:a"}`: 手を指す
+//! - `{"type": "engine_move", "limits": {...}}`: エンジンに手を指させる．limitsはengine-moveと同じ
+//! - `{"type": "sync"}`: 状態を送り直してもらう
+//!
+//! fromに受け取り済みの手数を指定して接続し直すと，その後の手のmoveイベントから再開する
+
+use crate::game::{GameError, Player};
+use crate::search::SearchInfo;
+use crate::server::{
+    play_engine_move, ApiError, AppState, GameStatus, GameView, LimitsRequest, StoredGame,
+};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+/// liveで流すイベント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    Joined {
+        player: Player,
+    },
+    State {
+        game: GameView,
+    },
+    Move {
+        number: usize,
+        #[serde(rename = "move")]
+        text: String,
+        player: Player,
+        /// 指した後の局面の文字列
+        position: String,
+    },
+    Status {
+        status: GameStatus,
+        winner: Option<Player>,
+        current_player: Player,
+        moves: usize,
+    },
+    Thinking {
+        number: usize,
+        info: SearchInfo,
+    },
+    Error {
+        error: String,
+        game_error: Option<GameError>,
+    },
+}
+
+impl From<ApiError> for LiveEvent {
+    fn from(error: ApiError) -> Self {
+        LiveEvent::Error {
+            error: error.message,
+            game_error: error.game_error,
+        }
+    }
+}
+
+/// クライアントから送られるメッセージ
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Move {
+        #[serde(rename = "move")]
+        text: String,
+    },
+    EngineMove {
+        #[serde(default)]
+        limits: LimitsRequest,
+    },
+    Sync,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Player,
+    #[default]
+    Spectator,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LiveQuery {
+    #[serde(default)]
+    pub role: Role,
+    /// 受け取り済みの手数
+    pub from: Option<usize>,
+}
+
+/// playerとして接続しているクライアントの手番．接続が切れると（Seatを捨てると）空く
+#[derive(Debug)]
+pub struct Seat {
+    state: Arc<AppState>,
+    id: u64,
+    pub player: Player,
+}
+
+impl Seat {
+    /// 空いている手番に先手から座る．両方とも埋まっていればエラー
+    pub fn take(state: &Arc<AppState>, id: u64) -> Result<Seat, ApiError> {
+        let mut seats = state.seats.lock().unwrap();
+        let taken = seats.entry(id).or_default();
+        let player = [Player::Player1, Player::Player2]
+            .into_iter()
+            .find(|player| !taken.contains(player))
+            .ok_or_else(|| ApiError::conflict("Both players are already connected"))?;
+        taken.push(player);
+        Ok(Seat {
+            state: state.clone(),
+            id,
+            player,
+        })
+    }
+}
+
+impl Drop for Seat {
+    fn drop(&mut self) {
+        let mut seats = self.state.seats.lock().unwrap();
+        if let Some(taken) = seats.get_mut(&self.id) {
+            taken.retain(|&player| player != self.player);
+            if taken.is_empty() {
+                seats.remove(&self.id);
+            }
+        }
+    }
+}
+
+/// 最後の手を指した後の状態からmoveイベントを作る
+pub fn move_event(view: &GameView, player: Player) -> LiveEvent {
+    LiveEvent::Move {
+        number: view.moves.len(),
+        text: view.moves.last().cloned().unwrap_or_default(),
+        player,
+        position: view.position.clone(),
+    }
+}
+
+pub fn status_event(view: &GameView) -> LiveEvent {
+    LiveEvent::Status {
+        status: view.status,
+        winner: view.winner,
+        current_player: view.game.current_player,
+        moves: view.moves.len(),
+    }
+}
+
+/// 接続したクライアントに最初に送るイベント．fromがあればその後の手のmoveイベントから始める
+pub fn resume_events(stored: &StoredGame, from: Option<usize>) -> Result<Vec<LiveEvent>, ApiError> {
+    let view = stored.view();
+    let mut events = vec![];
+    if let Some(from) = from {
+        if from > view.moves.len() {
+            return Err(ApiError::bad_request(format!(
+                "Game has only {} moves",
+                view.moves.len()
+            )));
+        }
+        let games = stored.record.replay().map_err(ApiError::internal)?;
+        for (number, text) in view.moves.iter().enumerate().skip(from) {
+            events.push(LiveEvent::Move {
+                number: number + 1,
+                text: text.clone(),
+                player: games[number].current_player,
+                position: games[number + 1].to_position_string(),
+            });
+        }
+    }
+    events.push(status_event(&view));
+    events.push(LiveEvent::State { game: view });
+    Ok(events)
+}
+
+pub async fn live(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Query(query): Query<LiveQuery>,
+) -> Result<Response, ApiError> {
+    // 無い対局や範囲外のfromや3人目のplayerは接続する前にエラーにする
+    resume_events(&state.get(id)?, query.from)?;
+    let seat = match query.role {
+        Role::Player => Some(Seat::take(&state, id)?),
+        Role::Spectator => None,
+    };
+    Ok(ws.on_upgrade(move |socket| run(socket, state, id, query, seat)))
+}
+
+async fn run(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    id: u64,
+    query: LiveQuery,
+    seat: Option<Seat>,
+) {
+    // 取りこぼさないように，状態を読む前に購読しておく
+    let mut events = state.subscribe(id);
+    // このクライアントだけに送るイベント（エンジンの手のエラーなど）
+    let (direct_sender, mut direct) = mpsc::unbounded_channel::<LiveEvent>();
+
+    let initial = state
+        .get(id)
+        .and_then(|stored| resume_events(&stored, query.from));
+    let mut last_number = match &initial {
+        Ok(events) => match events.last() {
+            Some(LiveEvent::State { game }) => game.moves.len(),
+            _ => 0,
+        },
+        Err(_) => 0,
+    };
+    let joined = seat.as_ref().map(|seat| LiveEvent::Joined {
+        player: seat.player,
+    });
+    for event in joined
+        .into_iter()
+        .chain(initial.unwrap_or_else(|error| vec![error.into()]))
+    {
+        if send(&mut socket, &event).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        let event = tokio::select! {
+            message = socket.recv() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                    Some(Ok(_)) => continue,
+                };
+                let player = seat.as_ref().map(|seat| seat.player);
+                match handle_message(&state, id, player, &text, &direct_sender).await {
+                    Some(event) => event,
+                    None => continue,
+                }
+            }
+            event = events.recv() => match event {
+                Ok(LiveEvent::Move { number, .. }) if number <= last_number => continue,
+                Ok(event) => {
+                    if let LiveEvent::Move { number, .. } = event {
+                        last_number = number;
+                    }
+                    event
+                }
+                // 取りこぼしたら状態を送り直す
+                Err(RecvError::Lagged(_)) => match state.get(id) {
+                    Ok(stored) => {
+                        last_number = stored.record.moves.len();
+                        LiveEvent::State { game: stored.view() }
+                    }
+                    Err(error) => error.into(),
+                },
+                Err(RecvError::Closed) => return,
+            },
+            Some(event) = direct.recv() => event,
+        };
+        if send(&mut socket, &event).await.is_err() {
+            return;
+        }
+    }
+}
+
+// クライアントのメッセージを処理し，そのクライアントにすぐ返すイベントがあれば返す．
+// playerはそのクライアントの手番で，spectatorならNone
+async fn handle_message(
+    state: &Arc<AppState>,
+    id: u64,
+    player: Option<Player>,
+    text: &str,
+    direct: &mpsc::UnboundedSender<LiveEvent>,
+) -> Option<LiveEvent> {
+    let message: ClientMessage = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(error) => return Some(ApiError::bad_request(error.to_string()).into()),
+    };
+    let stored = match state.get(id) {
+        Ok(stored) => stored,
+        Err(error) => return Some(error.into()),
+    };
+    if !matches!(message, ClientMessage::Sync) {
+        let Some(player) = player else {
+            return Some(ApiError::bad_request("Spectators cannot move").into());
+        };
+        if stored.game().current_player != player {
+            return Some(ApiError::conflict("Not your turn").into());
+        }
+    }
+    match message {
+        ClientMessage::Sync => Some(LiveEvent::State {
+            game: stored.view(),
+        }),
+        ClientMessage::Move { text } => {
+            // 手番を確かめた後に相手の手が入っていたら指さない
+            let n_moves = stored.record.moves.len();
+            let result = match stored.game().parse_move(&text) {
+                Ok(action) => state.play(id, &action, Some(n_moves)).await,
+                Err(error) => Err(ApiError::bad_request(error)),
+            };
+            // 成功した手はmoveイベントとして全員に届く
+            result.err().map(LiveEvent::from)
+        }
+        ClientMessage::EngineMove { limits } => {
+            // 考えている間も他のイベントを流せるように別のタスクで考える
+            let state = state.clone();
+            let direct = direct.clone();
+            tokio::spawn(async move {
+                if let Err(error) = play_engine_move(&state, id, &limits).await {
+                    let _ = direct.send(error.into());
+                }
+            });
+            None
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, event: &LiveEvent) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(event).unwrap();
+    socket.send(Message::Text(json.into())).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{create_game, play_move, CreateGameRequest, MoveRequest};
+    use axum::Json;
+
+    async fn new_game(state: &Arc<AppState>) -> u64 {
+        let request = CreateGameRequest {
+            policy: Some(crate::policies::PolicyConfig::AlphaBeta {
+                max_time: 0.05,
+                max_depth: 2,
+                weights: Default::default(),
+            }),
+            ..Default::default()
+        };
+        let (_, Json(view)) = create_game(State(state.clone()), Json(request))
+            .await
+            .unwrap();
+        view.id
+    }
+
+    async fn play(state: &Arc<AppState>, id: u64, text: &str) {
+        let request = MoveRequest {
+            text: Some(text.to_string()),
+            action: None,
+        };
+        let Json(_) = play_move(State(state.clone()), Path(id), Json(request))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_live_events_for_moves_and_thinking() {
+        let state = Arc::new(AppState::new());
+        let id = new_game(&state).await;
+        let mut events = state.subscribe(id);
+
+        play(&state, id, "a1:1").await;
+        match events.recv().await.unwrap() {
+            LiveEvent::Move {
+                number,
+                text,
+                player,
+                ..
+            } => {
+                assert_eq!((number, text.as_str()), (1, "a1:1"));
+                assert_eq!(player, Player::Player1);
+            }
+            event => panic!("{:?}", event),
+        }
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            LiveEvent::Status {
+                status: GameStatus::InProgress,
+                current_player: Player::Player2,
+                moves: 1,
+                ..
+            }
+        ));
+
+        play_engine_move(&state, id, &LimitsRequest::default())
+            .await
+            .unwrap();
+        let event = events.recv().await.unwrap();
+        assert!(
+            matches!(event, LiveEvent::Thinking { number: 2, .. }),
+            "エンジンの途中経過が流れるはず: {:?}",
+            event
+        );
+    }
+
+    #[tokio::test]
+    async fn test_live_resume_from_move_number() {
+        let state = Arc::new(AppState::new());
+        let id = new_game(&state).await;
+        for text in ["a1:1", "b2:2", "c3:3"] {
+            play(&state, id, text).await;
+        }
+        let stored = state.get(id).unwrap();
+
+        let events = resume_events(&stored, Some(1)).unwrap();
+        let moves: Vec<(usize, String)> = events
+            .iter()
+            .filter_map(|event| match event {
+                LiveEvent::Move { number, text, .. } => Some((*number, text.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            moves,
+            vec![(2, "b2:2".to_string()), (3, "c3:3".to_string())]
+        );
+        assert!(matches!(events.last(), Some(LiveEvent::State { .. })));
+
+        assert_eq!(resume_events(&stored, None).unwrap().len(), 2);
+        assert!(resume_events(&stored, Some(4)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_live_spectator_cannot_move() {
+        let state = Arc::new(AppState::new());
+        let id = new_game(&state).await;
+        let (direct, _) = mpsc::unbounded_channel();
+        let event = handle_message(
+            &state,
+            id,
+            None,
+            r#"{"type": "move", "move": "a1:1"}"#,
+            &direct,
+        )
+        .await;
+        assert!(matches!(event, Some(LiveEvent::Error { .. })));
+
+        let event = handle_message(
+            &state,
+            id,
+            Some(Player::Player1),
+            r#"{"type": "move", "move": "a1:0"}"#,
+            &direct,
+        )
+        .await;
+        assert!(
+            matches!(event, Some(LiveEvent::Error { .. })),
+            "渡されている駒は渡せない"
+        );
+        let event = handle_message(
+            &state,
+            id,
+            Some(Player::Player1),
+            r#"{"type": "move", "move": "a1:1"}"#,
+            &direct,
+        )
+        .await;
+        assert!(event.is_none(), "指した手はmoveイベントで届く");
+        assert_eq!(state.get(id).unwrap().record.moves, vec!["a1:1"]);
+    }
+
+    #[tokio::test]
+    async fn test_live_player_moves_only_on_own_turn() {
+        let state = Arc::new(AppState::new());
+        let id = new_game(&state).await;
+        let (direct, _) = mpsc::unbounded_channel();
+        for message in [
+            r#"{"type": "move", "move": "a1:1"}"#,
+            r#"{"type": "engine_move"}"#,
+        ] {
+            let event = handle_message(&state, id, Some(Player::Player2), message, &direct).await;
+            assert!(
+                matches!(event, Some(LiveEvent::Error { .. })),
+                "後手は先手の番に指せない"
+            );
+        }
+        assert!(state.get(id).unwrap().record.moves.is_empty());
+
+        let event = handle_message(
+            &state,
+            id,
+            Some(Player::Player2),
+            r#"{"type": "sync"}"#,
+            &direct,
+        )
+        .await;
+        assert!(matches!(event, Some(LiveEvent::State { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_live_seats() {
+        let state = Arc::new(AppState::new());
+        let id = new_game(&state).await;
+        let first = Seat::take(&state, id).unwrap();
+        let second = Seat::take(&state, id).unwrap();
+        assert_eq!(first.player, Player::Player1);
+        assert_eq!(second.player, Player::Player2);
+        assert!(Seat::take(&state, id).is_err(), "3人目のplayerは断る");
+
+        drop(first);
+        let again = Seat::take(&state, id).unwrap();
+        assert_eq!(again.player, Player::Player1, "切断した手番は空く");
+    }
+}
@@ -0,0 +1,743 @@
+//! 対局と解析のREST API（serverフィーチャー）
+//!
+//! - `POST /games`: 対局を作る．{"policy": PolicyConfig, "ruleset": "standard", "position": 局面の文字列}（すべて省略可）
+//! - `GET /games`: 対局の一覧
+//! - `GET /games/{id}`: 対局の状態
+//! - `DELETE /games/{id}`: 対局を消す
+//! - `POST /games/{id}/moves`: 手を指す．{"move": "c3:a"} または {"action": Action}
+//! - `POST /games/{id}/engine-move`: 対局のpolicyに手を指させる．{"move_time": 秒, "nodes": n, "depth": n, "policy": PolicyConfig}（省略可）
+//! - `POST /games/{id}/analysis`: 局面を進めずに対局のpolicyで解析する．制限は engine-move と同じ
+//! - `GET /games/{id}/live?role=player|spectator&from=<手数>`: 対局の様子を流すWebSocket（`live`を参照）
+//!
+//! クライアントが指定できるpolicyはサーバーのプロセス内で動くものだけで，externalや上限を超える設定は400を返す
+//!
+//! 不正な手は422（終局後の手は409）で，{"error": メッセージ, "game_error": GameError} を返す
+
+pub mod error;
+pub mod live;
+
+pub use error::ApiError;
+pub use live::LiveEvent;
+
+use crate::cli::GameRecord;
+use crate::engine::START_POSITION;
+use crate::evaluators::HandcraftedWeights;
+use crate::game::action::Action;
+use crate::game::{Game, GameError, Player};
+use crate::policies::PolicyConfig;
+use crate::search::{SearchInfo, SearchLimits};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+// 対局ごとのイベントのチャンネルに溜めておける数．溢れたクライアントには状態を送り直す
+const EVENT_CAPACITY: usize = 256;
+
+/// 対局のルール．今は標準ルールだけ
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ruleset {
+    #[default]
+    Standard,
+}
+
+/// サーバーが持つ1つの対局．保存するファイルもこの形
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredGame {
+    pub id: u64,
+    pub ruleset: Ruleset,
+    /// engine-moveとanalysisで使うpolicy
+    pub policy: PolicyConfig,
+    pub record: GameRecord,
+    #[serde(skip)]
+    game: Option<Game>,
+}
+
+impl StoredGame {
+    fn game(&self) -> &Game {
+        self.game.as_ref().unwrap()
+    }
+
+    fn play(&mut self, action: &Action) -> Result<(), ApiError> {
+        let mut game = self.game().clone();
+        let text = game.format_move(action);
+        game.play_turn(action.row, action.col, action.piece_index)?;
+        self.record.moves.push(text);
+        self.game = Some(game);
+        Ok(())
+    }
+
+    fn view(&self) -> GameView {
+        let game = self.game();
+        let status = if game.board.check_win() {
+            GameStatus::Quarto
+        } else if game.is_game_over() {
+            GameStatus::Draw
+        } else {
+            GameStatus::InProgress
+        };
+        let legal_moves = if game.is_game_over() {
+            vec![]
+        } else {
+            game.legal_actions()
+                .map(|action| game.format_move(&action))
+                .collect()
+        };
+        GameView {
+            id: self.id,
+            ruleset: self.ruleset,
+            policy: self.policy.clone(),
+            position: game.to_position_string(),
+            game: game.clone(),
+            moves: self.record.moves.clone(),
+            status,
+            winner: if game.is_game_over() {
+                game.judge_winner()
+            } else {
+                None
+            },
+            legal_moves,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameStatus {
+    InProgress,
+    Quarto,
+    Draw,
+}
+
+/// 対局の状態のレスポンス
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameView {
+    pub id: u64,
+    pub ruleset: Ruleset,
+    pub policy: PolicyConfig,
+    pub position: String,
+    pub game: Game,
+    pub moves: Vec<String>,
+    pub status: GameStatus,
+    pub winner: Option<Player>,
+    pub legal_moves: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CreateGameRequest {
+    pub policy: Option<PolicyConfig>,
+    #[serde(default)]
+    pub ruleset: Ruleset,
+    pub position: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MoveRequest {
+    #[serde(rename = "move")]
+    pub text: Option<String>,
+    pub action: Option<Action>,
+}
+
+/// engine-moveとanalysisの制限．指定しなければpolicyの設定で考える
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LimitsRequest {
+    pub move_time: Option<f64>,
+    pub nodes: Option<u64>,
+    pub depth: Option<usize>,
+    /// 対局のpolicyの代わりに使うpolicy（エンジン同士の対局で手番ごとに変える）
+    pub policy: Option<PolicyConfig>,
+}
+
+// クライアントが指定できる考える時間（秒）とプレイアウトの回数の上限
+const MAX_MOVE_TIME: f64 = 60.0;
+const MAX_PLAY_OUTS: u64 = 10_000_000;
+// 盤のマスの数より深く読む意味はない
+const MAX_DEPTH: usize = 16;
+
+fn check_move_time(name: &str, move_time: f64) -> Result<(), ApiError> {
+    if !(move_time > 0.0 && move_time <= MAX_MOVE_TIME) {
+        return Err(ApiError::bad_request(format!(
+            "{} must be in (0, {}]",
+            name, MAX_MOVE_TIME
+        )));
+    }
+    Ok(())
+}
+
+fn check_depth(name: &str, depth: usize) -> Result<(), ApiError> {
+    if depth > MAX_DEPTH {
+        return Err(ApiError::bad_request(format!(
+            "{} must be at most {}",
+            name, MAX_DEPTH
+        )));
+    }
+    Ok(())
+}
+
+/// クライアントから受け取ったpolicyの設定を確かめる．
+/// 外部コマンドを起動するexternalは許さず，考える時間などに上限を設けてスレッドを占有させない
+fn check_policy(config: &PolicyConfig) -> Result<(), ApiError> {
+    match config {
+        PolicyConfig::Random | PolicyConfig::OneStepLookAhead | PolicyConfig::TwoStepLookAhead => {
+            Ok(())
+        }
+        PolicyConfig::Mcs {
+            max_time,
+            play_out_depth,
+            max_play_outs,
+            ..
+        } => {
+            check_move_time("max_time", *max_time)?;
+            if let Some(play_out_depth) = play_out_depth {
+                check_depth("play_out_depth", *play_out_depth)?;
+            }
+            if max_play_outs.is_some_and(|max_play_outs| max_play_outs > MAX_PLAY_OUTS) {
+                return Err(ApiError::bad_request(format!(
+                    "max_play_outs must be at most {}",
+                    MAX_PLAY_OUTS
+                )));
+            }
+            Ok(())
+        }
+        PolicyConfig::AlphaBeta {
+            max_time,
+            max_depth,
+            ..
+        } => {
+            check_move_time("max_time", *max_time)?;
+            check_depth("max_depth", *max_depth)
+        }
+        PolicyConfig::External { .. } => Err(ApiError::bad_request(
+            "External engines are not allowed on the server",
+        )),
+    }
+}
+
+impl LimitsRequest {
+    fn to_limits(&self) -> Result<SearchLimits, ApiError> {
+        if let Some(move_time) = self.move_time {
+            check_move_time("move_time", move_time)?;
+        }
+        if let Some(depth) = self.depth {
+            check_depth("depth", depth)?;
+        }
+        if let Some(policy) = &self.policy {
+            check_policy(policy)?;
+        }
+        Ok(SearchLimits {
+            move_time: self.move_time,
+            nodes: self.nodes,
+            depth: self.depth,
+            ..Default::default()
+        })
+    }
+}
+
+/// 解析の結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Analysis {
+    #[serde(rename = "move")]
+    pub text: String,
+    pub action: Action,
+    /// 思考の途中経過（最後が最終結果）
+    pub info: Vec<SearchInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineMoveResponse {
+    pub analysis: Analysis,
+    pub game: GameView,
+}
+
+/// 対局を持っておく場所．storeを指定するとファイルにも保存する
+#[derive(Debug, Default)]
+pub struct AppState {
+    games: Mutex<BTreeMap<u64, StoredGame>>,
+    // 最後に割り当てた対局のid．消した対局のidは使い回さない
+    last_id: AtomicU64,
+    store: Option<PathBuf>,
+    // ファイルへの書き込みを1つずつ行うためのロック
+    writing: tokio::sync::Mutex<()>,
+    // 対局ごとのliveのイベント
+    channels: Mutex<HashMap<u64, broadcast::Sender<LiveEvent>>>,
+    // 対局ごとにliveのplayerが座っている手番
+    seats: Mutex<HashMap<u64, Vec<Player>>>,
+}
+
+impl AppState {
+    pub fn new() -> Self {
+        AppState::default()
+    }
+
+    /// storeのディレクトリに保存された対局を読み込み，以後はそこに保存する
+    pub fn with_store(store: PathBuf) -> Result<Self, String> {
+        std::fs::create_dir_all(&store).map_err(|e| e.to_string())?;
+        let mut games = BTreeMap::new();
+        for entry in std::fs::read_dir(&store).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let json = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            let mut stored: StoredGame =
+                serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+            stored.game = stored.record.replay()?.pop();
+            games.insert(stored.id, stored);
+        }
+        Ok(AppState {
+            last_id: AtomicU64::new(games.keys().next_back().copied().unwrap_or(0)),
+            games: Mutex::new(games),
+            store: Some(store),
+            ..Default::default()
+        })
+    }
+
+    fn next_id(&self) -> u64 {
+        self.last_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    // 対局の今の状態をファイルに書く（消された対局ならファイルを消す）．
+    // gamesのロックは状態を読む間だけ持ち，書き込みはブロッキングしてよいスレッドで行う．
+    // 書き込みは1つずつ行い，そのときの最新の状態を書くので，古い状態で上書きすることはない
+    async fn save(&self, id: u64) -> Result<(), ApiError> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let _writing = self.writing.lock().await;
+        let json = match self.games.lock().unwrap().get(&id) {
+            Some(stored) => Some(
+                serde_json::to_string_pretty(stored)
+                    .map_err(|e| ApiError::internal(e.to_string()))?,
+            ),
+            None => None,
+        };
+        let path = store.join(format!("{}.json", id));
+        tokio::task::spawn_blocking(move || match json {
+            Some(json) => std::fs::write(&path, json),
+            None => match std::fs::remove_file(&path) {
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            },
+        })
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?
+        .map_err(|e| ApiError::internal(e.to_string()))
+    }
+
+    // 対局を読んで書き換え，保存する．保存に失敗してもメモリ上の対局は書き換わっている
+    async fn update<T>(
+        &self,
+        id: u64,
+        f: impl FnOnce(&mut StoredGame) -> Result<T, ApiError>,
+    ) -> Result<T, ApiError> {
+        let value = {
+            let mut games = self.games.lock().unwrap();
+            let stored = games.get_mut(&id).ok_or(ApiError::not_found(id))?;
+            let mut updated = stored.clone();
+            let value = f(&mut updated)?;
+            *stored = updated;
+            value
+        };
+        self.save(id).await?;
+        Ok(value)
+    }
+
+    /// 手を指してliveのクライアントに知らせる．expected_movesを指定すると，
+    /// それまでの手数が変わっていた（他の手が先に指された）場合は指さない
+    pub async fn play(
+        &self,
+        id: u64,
+        action: &Action,
+        expected_moves: Option<usize>,
+    ) -> Result<GameView, ApiError> {
+        let (view, player) = self
+            .update(id, |stored| {
+                if expected_moves.is_some_and(|n_moves| n_moves != stored.record.moves.len()) {
+                    return Err(ApiError::conflict(
+                        "Game changed before the move was played",
+                    ));
+                }
+                let player = stored.game().current_player;
+                stored.play(action)?;
+                Ok((stored.view(), player))
+            })
+            .await?;
+        self.publish(id, live::move_event(&view, player));
+        self.publish(id, live::status_event(&view));
+        Ok(view)
+    }
+
+    /// 対局のliveのイベントを受け取る
+    pub fn subscribe(&self, id: u64) -> broadcast::Receiver<LiveEvent> {
+        self.sender(id).subscribe()
+    }
+
+    fn sender(&self, id: u64) -> broadcast::Sender<LiveEvent> {
+        self.channels
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_insert_with(|| broadcast::channel(EVENT_CAPACITY).0)
+            .clone()
+    }
+
+    // 受け取るクライアントがいなくても構わない
+    fn publish(&self, id: u64, event: LiveEvent) {
+        let _ = self.sender(id).send(event);
+    }
+
+    fn get(&self, id: u64) -> Result<StoredGame, ApiError> {
+        self.games
+            .lock()
+            .unwrap()
+            .get(&id)
+            .cloned()
+            .ok_or(ApiError::not_found(id))
+    }
+}
+
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/games", post(create_game).get(list_games))
+        .route("/games/{id}", get(get_game).delete(delete_game))
+        .route("/games/{id}/moves", post(play_move))
+        .route("/games/{id}/engine-move", post(engine_move))
+        .route("/games/{id}/analysis", post(analyze))
+        .route("/games/{id}/live", get(live::live))
+        .with_state(state)
+}
+
+fn default_policy() -> PolicyConfig {
+    PolicyConfig::AlphaBeta {
+        max_time: 1.0,
+        max_depth: 16,
+        weights: HandcraftedWeights::default(),
+    }
+}
+
+pub async fn create_game(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CreateGameRequest>,
+) -> Result<(StatusCode, Json<GameView>), ApiError> {
+    let start = request
+        .position
+        .unwrap_or_else(|| START_POSITION.to_string());
+    let game = Game::from_position_string(&start).map_err(ApiError::bad_request)?;
+    if let Some(policy) = &request.policy {
+        check_policy(policy)?;
+    }
+    let id = state.next_id();
+    let stored = StoredGame {
+        id,
+        ruleset: request.ruleset,
+        policy: request.policy.unwrap_or_else(default_policy),
+        record: GameRecord {
+            start,
+            moves: vec![],
+        },
+        game: Some(game),
+    };
+    let view = stored.view();
+    state.games.lock().unwrap().insert(id, stored);
+    if let Err(error) = state.save(id).await {
+        state.games.lock().unwrap().remove(&id);
+        return Err(error);
+    }
+    Ok((StatusCode::CREATED, Json(view)))
+}
+
+pub async fn list_games(State(state): State<Arc<AppState>>) -> Json<Vec<GameView>> {
+    let games = state.games.lock().unwrap();
+    Json(games.values().map(|stored| stored.view()).collect())
+}
+
+pub async fn get_game(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<GameView>, ApiError> {
+    Ok(Json(state.get(id)?.view()))
+}
+
+pub async fn delete_game(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .games
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .ok_or(ApiError::not_found(id))?;
+    state.channels.lock().unwrap().remove(&id);
+    state.save(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn play_move(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(request): Json<MoveRequest>,
+) -> Result<Json<GameView>, ApiError> {
+    let game = state.get(id)?.game().clone();
+    let action = match (&request.text, &request.action) {
+        (Some(text), _) => game.parse_move(text).map_err(ApiError::bad_request)?,
+        (None, Some(action)) => *action,
+        (None, None) => return Err(ApiError::bad_request("Either move or action is required")),
+    };
+    Ok(Json(state.play(id, &action, None).await?))
+}
+
+pub async fn engine_move(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(limits): Json<LimitsRequest>,
+) -> Result<Json<EngineMoveResponse>, ApiError> {
+    Ok(Json(play_engine_move(&state, id, &limits).await?))
+}
+
+pub async fn analyze(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(limits): Json<LimitsRequest>,
+) -> Result<Json<Analysis>, ApiError> {
+    Ok(Json(think(&state, id, &limits).await?))
+}
+
+/// エンジンに手を考えさせて指す．考えている間に局面が変わっていたら指さない
+pub async fn play_engine_move(
+    state: &Arc<AppState>,
+    id: u64,
+    limits: &LimitsRequest,
+) -> Result<EngineMoveResponse, ApiError> {
+    let n_moves = state.get(id)?.record.moves.len();
+    let analysis = think(state, id, limits).await?;
+    let game = state.play(id, &analysis.action, Some(n_moves)).await?;
+    Ok(EngineMoveResponse { analysis, game })
+}
+
+// 対局のpolicyで手を考え，途中経過をliveのクライアントに流す．探索はブロッキングなので別のスレッドで行う
+async fn think(
+    state: &Arc<AppState>,
+    id: u64,
+    limits: &LimitsRequest,
+) -> Result<Analysis, ApiError> {
+    let stored = state.get(id)?;
+    let game = stored.game().clone();
+    if game.is_game_over() {
+        return Err(GameError::GameOver.into());
+    }
+    let search_limits = limits.to_limits()?;
+    let config = limits.policy.clone().unwrap_or(stored.policy);
+    let number = stored.record.moves.len() + 1;
+    let sender = state.sender(id);
+    tokio::task::spawn_blocking(move || {
+        let mut info = vec![];
+        let action = catch_unwind(AssertUnwindSafe(|| {
+            config
+                .build()
+                .action_with_limits(&game, &search_limits, &mut |search_info| {
+                    let _ = sender.send(LiveEvent::Thinking {
+                        number,
+                        info: search_info.clone(),
+                    });
+                    info.push(search_info.clone())
+                })
+        }))
+        .map_err(|_| ApiError::internal("Engine panicked"))?;
+        Ok(Analysis {
+            text: game.format_move(&action),
+            action,
+            info,
+        })
+    })
+    .await
+    .map_err(|e| ApiError::internal(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_request(json: &str) -> Json<CreateGameRequest> {
+        Json(serde_json::from_str(json).unwrap())
+    }
+
+    async fn new_game(state: &Arc<AppState>, json: &str) -> GameView {
+        let (status, Json(view)) = create_game(State(state.clone()), create_request(json))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        view
+    }
+
+    #[tokio::test]
+    async fn test_server_play_moves() {
+        let state = Arc::new(AppState::new());
+        let view = new_game(&state, r#"{"policy": {"type": "random"}}"#).await;
+        assert_eq!(view.status, GameStatus::InProgress);
+        assert_eq!(view.legal_moves.len(), 16 * 15);
+
+        let request = MoveRequest {
+            text: Some("a1:1".to_string()),
+            action: None,
+        };
+        let Json(view) = play_move(State(state.clone()), Path(view.id), Json(request))
+            .await
+            .unwrap();
+        assert_eq!(view.moves, vec!["a1:1"]);
+        assert_eq!(view.game.current_player, Player::Player2);
+
+        // 埋まっているセルには置けない
+        let request = MoveRequest {
+            text: None,
+            action: Some(Action {
+                row: 0,
+                col: 0,
+                piece_index: Some(0),
+            }),
+        };
+        let error = play_move(State(state.clone()), Path(view.id), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            error.game_error,
+            Some(GameError::CellOccupied { row: 0, col: 0 })
+        );
+
+        let error = get_game(State(state.clone()), Path(42)).await.unwrap_err();
+        assert_eq!(error.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_server_engine_move_and_analysis() {
+        let state = Arc::new(AppState::new());
+        let view = new_game(
+            &state,
+            r#"{"policy": {"type": "alpha_beta"}, "position": "0a3./..../..../.... 5"}"#,
+        )
+        .await;
+
+        let limits = LimitsRequest {
+            depth: Some(2),
+            ..Default::default()
+        };
+        let Json(analysis) = analyze(State(state.clone()), Path(view.id), Json(limits.clone()))
+            .await
+            .unwrap();
+        assert!(!analysis.info.is_empty(), "途中経過が返るはず");
+        assert!(view.legal_moves.contains(&analysis.text) || analysis.action.piece_index.is_none());
+
+        let Json(response) = engine_move(State(state.clone()), Path(view.id), Json(limits))
+            .await
+            .unwrap();
+        assert_eq!(response.game.moves.len(), 1, "エンジンの手が指されるはず");
+
+        let error = engine_move(
+            State(state.clone()),
+            Path(view.id),
+            Json(LimitsRequest {
+                move_time: Some(-1.0),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_server_rejects_unsafe_policies() {
+        let state = Arc::new(AppState::new());
+        for json in [
+            r#"{"policy": {"type": "external", "command": "/bin/sh", "args": ["-c", "true"]}}"#,
+            r#"{"policy": {"type": "mcs", "max_time": 1e9}}"#,
+            r#"{"policy": {"type": "mcs", "max_play_outs": 18446744073709551615}}"#,
+            r#"{"policy": {"type": "alpha_beta", "max_depth": 1000}}"#,
+        ] {
+            let error = create_game(State(state.clone()), create_request(json))
+                .await
+                .unwrap_err();
+            assert_eq!(error.status, StatusCode::BAD_REQUEST, "{}", json);
+        }
+        assert!(state.games.lock().unwrap().is_empty());
+
+        // 手番ごとに指定するpolicyも同じように確かめる
+        let view = new_game(&state, r#"{"policy": {"type": "random"}}"#).await;
+        let limits: LimitsRequest =
+            serde_json::from_str(r#"{"policy": {"type": "external", "command": "/bin/sh"}}"#)
+                .unwrap();
+        let error = engine_move(State(state.clone()), Path(view.id), Json(limits))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert!(state.get(view.id).unwrap().record.moves.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_server_game_over_and_store() {
+        let store = std::env::temp_dir().join("quart_engine_server_test");
+        let _ = std::fs::remove_dir_all(&store);
+        let state = Arc::new(AppState::with_store(store.clone()).unwrap());
+        let view = new_game(&state, r#"{"position": "012./..../..../.... 3"}"#).await;
+        let request = MoveRequest {
+            text: Some("d1:4".to_string()),
+            action: None,
+        };
+        let Json(view) = play_move(State(state.clone()), Path(view.id), Json(request))
+            .await
+            .unwrap();
+        assert_eq!(view.status, GameStatus::Quarto);
+        assert_eq!(view.winner, Some(Player::Player2));
+
+        let error = analyze(
+            State(state.clone()),
+            Path(view.id),
+            Json(LimitsRequest::default()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error.status,
+            StatusCode::CONFLICT,
+            "終局した局面は解析できない"
+        );
+
+        // 保存した対局を読み込めるはず
+        let loaded = Arc::new(AppState::with_store(store.clone()).unwrap());
+        let Json(reloaded) = get_game(State(loaded.clone()), Path(view.id))
+            .await
+            .unwrap();
+        assert_eq!(reloaded.position, view.position);
+        assert_eq!(reloaded.moves, view.moves);
+        assert_eq!(new_game(&loaded, "{}").await.id, view.id + 1);
+
+        delete_game(State(state.clone()), Path(view.id))
+            .await
+            .unwrap();
+        let removed = !store.join(format!("{}.json", view.id)).exists();
+        std::fs::remove_dir_all(&store).unwrap();
+        assert!(removed, "消した対局のファイルも消えるはず");
+    }
+
+    #[tokio::test]
+    async fn test_server_does_not_reuse_deleted_ids() {
+        let state = Arc::new(AppState::new());
+        let first = new_game(&state, "{}").await;
+        let second = new_game(&state, "{}").await;
+        let status = delete_game(State(state.clone()), Path(second.id))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let third = new_game(&state, "{}").await;
+        assert_eq!((first.id, second.id, third.id), (1, 2, 3));
+    }
+}
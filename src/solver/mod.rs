@@ -0,0 +1,28 @@
+pub mod proof_number;
+pub mod proof_tree;
+
+pub use proof_number::{Proof, ProofNumberSolver, SolveResult, SolverProgress};
+pub use proof_tree::{ProofTree, ProofTreeNode};
+
+use crate::game::action::Action;
+use crate::game::Game;
+
+// 手番側がこの局面ですぐに勝てるか
+fn can_win_immediately(game: &Game) -> bool {
+    game.board.find_winning_cell(game.selected_piece).is_some()
+}
+
+// 置いた後に相手がすぐに勝てる手は指した側の負けが決まっているので，それ以外の手と指した後の局面を返す
+fn expand(game: &Game) -> Vec<(Action, Game)> {
+    game.legal_actions()
+        .filter_map(|action| {
+            let mut next_state = game.clone();
+            next_state.play_turn_unchecked(action.row, action.col, action.piece_index);
+            if can_win_immediately(&next_state) {
+                None
+            } else {
+                Some((action, next_state))
+            }
+        })
+        .collect()
+}
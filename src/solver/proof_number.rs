@@ -0,0 +1,436 @@
+use crate::game::action::Action;
+use crate::game::symmetry::canonical_key;
+use crate::game::Game;
+use crate::solver::proof_tree::{ProofTree, ProofTreeNode};
+use crate::solver::{can_win_immediately, expand};
+use crate::utils::now;
+use std::collections::{HashMap, HashSet};
+
+const INFINITY: u32 = u32::MAX;
+
+/// 「手番側が勝てる」を証明できたかどうか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Proof {
+    /// 手番側が必ず勝てる
+    Proven,
+    /// 手番側は勝てない（引き分けか負け）
+    Disproven,
+    /// ノード数の上限までに決着しなかった
+    Unknown,
+}
+
+/// 探索の途中経過
+#[derive(Debug, Clone)]
+pub struct SolverProgress {
+    pub iterations: u64,
+    pub nodes: usize,
+    pub root_proof_number: u32,
+    pub root_disproof_number: u32,
+    /// 探索を始めてからの経過時間（秒）
+    pub elapsed: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SolveResult {
+    pub proof: Proof,
+    pub iterations: u64,
+    pub nodes: usize,
+    /// 証明できたときの勝つ手
+    pub best_action: Option<Action>,
+    /// 証明（反証）できたときの証明木
+    pub proof_tree: Option<ProofTree>,
+}
+
+/// 証明数探索で「手番側が勝てるか」を解くソルバー．
+/// 局面を木として展開し，証明数・反証数が最小の葉から順に展開する
+pub struct ProofNumberSolver {
+    /// 展開するノード数の上限．これを超えたら探索を打ち切る
+    pub max_nodes: usize,
+    /// 何回展開するごとに途中経過を報告するか
+    pub report_interval: u64,
+}
+
+impl Default for ProofNumberSolver {
+    fn default() -> Self {
+        ProofNumberSolver {
+            max_nodes: 1_000_000,
+            report_interval: 10_000,
+        }
+    }
+}
+
+impl ProofNumberSolver {
+    pub fn new() -> Self {
+        ProofNumberSolver::default()
+    }
+
+    pub fn solve(&self, game: &Game) -> Result<SolveResult, String> {
+        self.solve_with_progress(game, |_| {})
+    }
+
+    /// report_interval回展開するごとにprogressを呼びながら解く
+    pub fn solve_with_progress(
+        &self,
+        game: &Game,
+        mut progress: impl FnMut(&SolverProgress),
+    ) -> Result<SolveResult, String> {
+        if game.is_game_over() {
+            return Err("Game is already over".to_string());
+        }
+
+        let start_time = now();
+        let mut tree = Tree::new(game);
+        let mut iterations = 0;
+        while !tree.is_solved(0) && tree.nodes.len() < self.max_nodes {
+            let leaf = tree.select();
+            tree.expand(leaf);
+            tree.update(leaf);
+            iterations += 1;
+            if self.report_interval > 0 && iterations % self.report_interval == 0 {
+                progress(&SolverProgress {
+                    iterations,
+                    nodes: tree.nodes.len(),
+                    root_proof_number: tree.nodes[0].proof_number,
+                    root_disproof_number: tree.nodes[0].disproof_number,
+                    elapsed: now() - start_time,
+                });
+            }
+        }
+
+        let root = &tree.nodes[0];
+        let proof = if root.proof_number == 0 {
+            Proof::Proven
+        } else if root.disproof_number == 0 {
+            Proof::Disproven
+        } else {
+            Proof::Unknown
+        };
+        let best_action = if proof == Proof::Proven {
+            root.children
+                .iter()
+                .find(|&&child| tree.nodes[child].proof_number == 0)
+                .and_then(|&child| tree.nodes[child].action)
+        } else {
+            None
+        };
+        let proof_tree = if proof == Proof::Unknown {
+            None
+        } else {
+            Some(tree.export())
+        };
+        Ok(SolveResult {
+            proof,
+            iterations,
+            nodes: tree.nodes.len(),
+            best_action,
+            proof_tree,
+        })
+    }
+}
+
+struct Node {
+    game: Game,
+    parent: Option<usize>,
+    // 親の局面からこの局面に進む手
+    action: Option<Action>,
+    children: Vec<usize>,
+    // 既に解けた同じ（対称な）局面のノード
+    link: Option<usize>,
+    // 根の手番側が指す局面か
+    is_or: bool,
+    expanded: bool,
+    key: u128,
+    proof_number: u32,
+    disproof_number: u32,
+}
+
+struct Tree {
+    nodes: Vec<Node>,
+    // 解けた局面のcanonical_key → ノード
+    solved: HashMap<u128, usize>,
+}
+
+impl Tree {
+    fn new(game: &Game) -> Self {
+        let mut tree = Tree {
+            nodes: vec![],
+            solved: HashMap::new(),
+        };
+        tree.add_node(game.clone(), canonical_key(game).0, None, None, true);
+        tree
+    }
+
+    fn is_solved(&self, index: usize) -> bool {
+        self.nodes[index].proof_number == 0 || self.nodes[index].disproof_number == 0
+    }
+
+    fn add_node(
+        &mut self,
+        game: Game,
+        key: u128,
+        parent: Option<usize>,
+        action: Option<Action>,
+        is_or: bool,
+    ) -> usize {
+        // 根の手番側が勝ちなら(0, ∞)，勝てないなら(∞, 0)
+        let mut link = None;
+        let (proof_number, disproof_number, expanded) = if can_win_immediately(&game) {
+            if is_or {
+                (0, INFINITY, true)
+            } else {
+                (INFINITY, 0, true)
+            }
+        } else if game.available_pieces.is_empty() {
+            // 勝てずに最後の駒を置くなら引き分け
+            (INFINITY, 0, true)
+        } else if let Some(&target) = self.solved.get(&key) {
+            link = Some(target);
+            let target = &self.nodes[target];
+            (target.proof_number, target.disproof_number, true)
+        } else {
+            (1, 1, false)
+        };
+
+        self.nodes.push(Node {
+            game,
+            parent,
+            action,
+            children: vec![],
+            link,
+            is_or,
+            expanded,
+            key,
+            proof_number,
+            disproof_number,
+        });
+        self.nodes.len() - 1
+    }
+
+    // 根から，ORノードでは証明数，ANDノードでは反証数が最小の子をたどって葉を選ぶ
+    fn select(&self) -> usize {
+        let mut index = 0;
+        while self.nodes[index].expanded {
+            let node = &self.nodes[index];
+            index = *node
+                .children
+                .iter()
+                .min_by_key(|&&child| {
+                    if node.is_or {
+                        self.nodes[child].proof_number
+                    } else {
+                        self.nodes[child].disproof_number
+                    }
+                })
+                .unwrap();
+        }
+        index
+    }
+
+    fn expand(&mut self, index: usize) {
+        let game = self.nodes[index].game.clone();
+        let is_or = self.nodes[index].is_or;
+        // 対称な局面は同じ結果になるので1つだけ展開する
+        let mut seen = HashSet::new();
+        let mut children = vec![];
+        for (action, next_state) in expand(&game) {
+            let (key, _) = canonical_key(&next_state);
+            if seen.insert(key) {
+                children.push(self.add_node(next_state, key, Some(index), Some(action), !is_or));
+            }
+        }
+        let node = &mut self.nodes[index];
+        node.children = children;
+        node.expanded = true;
+    }
+
+    // 展開したノードから根まで証明数・反証数を更新する
+    fn update(&mut self, index: usize) {
+        let mut current = Some(index);
+        while let Some(index) = current {
+            let node = &self.nodes[index];
+            if node.link.is_none() && node.expanded && !self.is_terminal(index) {
+                let proof_numbers = node.children.iter().map(|&c| self.nodes[c].proof_number);
+                let disproof_numbers = node.children.iter().map(|&c| self.nodes[c].disproof_number);
+                let (proof_number, disproof_number) = if node.is_or {
+                    (
+                        proof_numbers.min().unwrap_or(INFINITY),
+                        disproof_numbers.fold(0, u32::saturating_add),
+                    )
+                } else {
+                    (
+                        proof_numbers.fold(0, u32::saturating_add),
+                        disproof_numbers.min().unwrap_or(INFINITY),
+                    )
+                };
+                let node = &mut self.nodes[index];
+                node.proof_number = proof_number;
+                node.disproof_number = disproof_number;
+            }
+            if self.is_solved(index) {
+                self.solved.entry(self.nodes[index].key).or_insert(index);
+            }
+            current = self.nodes[index].parent;
+        }
+    }
+
+    fn is_terminal(&self, index: usize) -> bool {
+        let game = &self.nodes[index].game;
+        can_win_immediately(game) || game.available_pieces.is_empty()
+    }
+
+    // 解けた根から，結果を示すのに必要な子だけをたどって証明木にする
+    fn export(&self) -> ProofTree {
+        let mut nodes = vec![];
+        let mut exported = HashMap::new();
+        self.export_node(0, &mut nodes, &mut exported);
+        ProofTree { nodes }
+    }
+
+    fn export_node(
+        &self,
+        index: usize,
+        nodes: &mut Vec<ProofTreeNode>,
+        exported: &mut HashMap<usize, usize>,
+    ) -> usize {
+        let index = self.nodes[index].link.unwrap_or(index);
+        if let Some(&i) = exported.get(&index) {
+            return i;
+        }
+
+        let node = &self.nodes[index];
+        let win = node.proof_number == 0;
+        let i = nodes.len();
+        nodes.push(ProofTreeNode {
+            key: format!("{:x}", node.game.position_key()),
+            win,
+            children: vec![],
+        });
+        exported.insert(index, i);
+
+        // 指す側が望む結果なら，それを示す子が1つあればよい．そうでなければすべての子が必要
+        let children: Vec<usize> = if node.is_or == win {
+            node.children
+                .iter()
+                .copied()
+                .find(|&child| {
+                    let child = &self.nodes[child];
+                    if win {
+                        child.proof_number == 0
+                    } else {
+                        child.disproof_number == 0
+                    }
+                })
+                .into_iter()
+                .collect()
+        } else {
+            node.children.clone()
+        };
+        let children = children
+            .into_iter()
+            .map(|child| self.export_node(child, nodes, exported))
+            .collect();
+        nodes[i].children = children;
+        i
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluators::NeutralEvaluator;
+    use crate::policies::alpha_beta_policy::AlphaBetaPolicy;
+    use crate::policies::{Policy, RandomPolicy};
+
+    fn random_position(empty_cells: usize) -> Game {
+        let policy = RandomPolicy::new();
+        loop {
+            let mut game = Game::new();
+            while !game.is_game_over() && game.board.empty_positions().len() > empty_cells {
+                let action = policy.action(&game);
+                game.play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+            }
+            if !game.is_game_over() {
+                return game;
+            }
+        }
+    }
+
+    #[test]
+    fn test_solver_agrees_with_alpha_beta() {
+        let solver = ProofNumberSolver::new();
+        let alpha_beta = AlphaBetaPolicy {
+            max_time: 10.0,
+            ..AlphaBetaPolicy::with_evaluator(NeutralEvaluator::default())
+        };
+        for _ in 0..5 {
+            let game = random_position(5);
+            let result = solver.solve(&game).unwrap();
+            let expected = if alpha_beta.search(&game).score > 0 {
+                Proof::Proven
+            } else {
+                Proof::Disproven
+            };
+            assert_eq!(result.proof, expected, "全探索と同じ結果になるはず");
+
+            let proof_tree = result.proof_tree.unwrap();
+            assert_eq!(
+                proof_tree.verify(),
+                Ok(expected == Proof::Proven),
+                "証明木の検証に通るはず"
+            );
+            if let Some(action) = result.best_action {
+                let mut next_state = game.clone();
+                next_state
+                    .play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+                assert!(
+                    next_state.board.check_win()
+                        || solver.solve(&next_state).unwrap().proof == Proof::Disproven,
+                    "勝つ手を指せば相手は勝てないはず"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_proof_tree_rejects_wrong_result() {
+        let game = random_position(5);
+        let result = ProofNumberSolver::new().solve(&game).unwrap();
+        let proof_tree = result.proof_tree.unwrap();
+        let loaded = ProofTree::from_json(&proof_tree.to_json()).unwrap();
+        assert_eq!(loaded, proof_tree, "読み込んでも同じ証明木になるはず");
+
+        let mut broken = proof_tree.clone();
+        broken.nodes[0].win = !broken.nodes[0].win;
+        assert!(broken.verify().is_err(), "結果を書き換えた証明木は通らない");
+        let mut broken = proof_tree;
+        broken.nodes.truncate(1);
+        broken.nodes[0].children.clear();
+        assert!(
+            broken.verify().is_err() || can_win_immediately(&game),
+            "子を消した証明木は通らない"
+        );
+    }
+
+    #[test]
+    fn test_solver_respects_node_limit() {
+        let solver = ProofNumberSolver {
+            max_nodes: 1000,
+            report_interval: 1,
+        };
+        let mut n_reports = 0;
+        let result = solver
+            .solve_with_progress(&Game::new(), |progress| {
+                assert!(progress.nodes <= 1000 + 240);
+                n_reports += 1;
+            })
+            .unwrap();
+        assert_eq!(result.proof, Proof::Unknown, "序盤は解き切れないはず");
+        assert!(result.proof_tree.is_none());
+        assert_eq!(
+            n_reports as u64, result.iterations,
+            "毎回途中経過を報告するはず"
+        );
+    }
+}
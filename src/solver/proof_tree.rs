@@ -0,0 +1,112 @@
+use crate::game::symmetry::canonical_key;
+use crate::game::Game;
+use crate::solver::{can_win_immediately, expand};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 証明木のノード
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofTreeNode {
+    /// 局面のposition_keyの16進数表記
+    pub key: String,
+    /// 根の手番側が勝てるか
+    pub win: bool,
+    /// 結果を示すのに必要な子局面（nodesの添字）．対称な局面は1つにまとめてある
+    pub children: Vec<usize>,
+}
+
+/// ProofNumberSolverが出力する証明木．nodes[0]が根で，同じ局面は共有するので木ではなくDAGになる
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofTree {
+    pub nodes: Vec<ProofTreeNode>,
+}
+
+impl ProofTree {
+    /// 証明木を合法手の生成からたどり直して検証し，根の手番側が勝てるかを返す．
+    /// 指す側が望む結果のノードは子の1つが，そうでないノードは自分が負けない手すべての子がその結果を示していればよい
+    pub fn verify(&self) -> Result<bool, String> {
+        let root = self.nodes.first().ok_or("Proof tree is empty")?;
+        let root_empty_cells = self.game(0)?.board.empty_positions().len();
+        let mut verified = vec![false; self.nodes.len()];
+        self.verify_node(0, root_empty_cells, &mut verified)?;
+        Ok(root.win)
+    }
+
+    fn game(&self, index: usize) -> Result<Game, String> {
+        let key = u128::from_str_radix(&self.nodes[index].key, 16).map_err(|e| e.to_string())?;
+        Game::from_position_key(key)
+    }
+
+    fn verify_node(
+        &self,
+        index: usize,
+        root_empty_cells: usize,
+        verified: &mut Vec<bool>,
+    ) -> Result<(), String> {
+        if verified[index] {
+            return Ok(());
+        }
+        let node = &self.nodes[index];
+        let game = self.game(index)?;
+        let empty_cells = game.board.empty_positions().len();
+        if empty_cells > root_empty_cells {
+            return Err(format!("Node {} is not reachable from the root", index));
+        }
+        let is_or = (root_empty_cells - empty_cells).is_multiple_of(2);
+
+        let shown = if can_win_immediately(&game) {
+            node.win == is_or
+        } else if game.available_pieces.is_empty() {
+            !node.win
+        } else {
+            let mut children = HashMap::new();
+            for &child in node.children.iter() {
+                if child >= self.nodes.len() {
+                    return Err(format!("Node {} has an invalid child {}", index, child));
+                }
+                children.insert(canonical_key(&self.game(child)?).0, child);
+            }
+
+            // 子の結果．証明木に無い子はNone
+            let mut results = vec![];
+            for (_, next_state) in expand(&game) {
+                let result = if next_state.available_pieces.is_empty() {
+                    Some(false)
+                } else if let Some(&child) = children.get(&canonical_key(&next_state).0) {
+                    self.verify_node(child, root_empty_cells, verified)?;
+                    Some(self.nodes[child].win)
+                } else {
+                    None
+                };
+                results.push(result);
+            }
+            if is_or == node.win {
+                results.contains(&Some(node.win))
+            } else {
+                results.iter().all(|&result| result == Some(node.win))
+            }
+        };
+        if !shown {
+            return Err(format!("Node {} does not prove its result", index));
+        }
+        verified[index] = true;
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_json()).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&json)
+    }
+}
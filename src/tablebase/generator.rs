@@ -0,0 +1,347 @@
+use crate::game::board::Board;
+use crate::game::piece::Piece;
+use crate::game::player::Player;
+use crate::game::symmetry::{board_symmetries, canonical_key};
+use crate::game::Game;
+use crate::tablebase::{Outcome, Tablebase};
+
+/// 残りマス数がmax_empty_cells以下で終局していない局面をすべて列挙し，
+/// 残りマス数の少ない順に解いていく（後退解析）ことでTablebaseを作る．
+/// 残りマス数がeの局面の子はe - 1の局面なので，1つ前に作った層の表を引くだけで解ける．
+/// 局面の数は残りマス数1でも対称性で同一視して10億を超えるので，
+/// max_empty_cellsを大きくすると時間もメモリも現実的ではなくなる
+pub struct TablebaseGenerator {
+    pub max_empty_cells: usize,
+}
+
+impl TablebaseGenerator {
+    pub fn new(max_empty_cells: usize) -> Self {
+        TablebaseGenerator { max_empty_cells }
+    }
+
+    pub fn generate(&self) -> Tablebase {
+        let mut tablebase = Tablebase::default();
+        for empty_cells in 1..=self.max_empty_cells {
+            add_layer(&mut tablebase, enumerate_positions(empty_cells));
+        }
+        tablebase
+    }
+}
+
+// 残りマス数がempty_cellsで終局していない局面のキー(canonical_key)をすべて列挙する
+fn enumerate_positions(empty_cells: usize) -> Vec<u128> {
+    // 駒の属性の入れ替えで，渡す駒はいつも0000にできる
+    let selected_piece = Piece::from_bits(0);
+    let mut keys = vec![];
+    for empty in empty_cell_sets(empty_cells) {
+        fill(&Board::new(), !empty, 0xFFFE, &mut |board, pieces| {
+            let game = Game {
+                board: *board,
+                available_pieces: pieces_from_mask(pieces),
+                selected_piece,
+                // 手番はキーに含まれない
+                current_player: Player::Player1,
+            };
+            keys.push(canonical_key(&game).0);
+        });
+    }
+    keys.sort_unstable();
+    keys.dedup();
+    keys
+}
+
+// 数がempty_cellsの空きマスの集合を，盤面の対称変換で移り合うものから1つずつ選ぶ
+fn empty_cell_sets(empty_cells: usize) -> Vec<u16> {
+    (0..=u16::MAX)
+        .filter(|mask| mask.count_ones() as usize == empty_cells)
+        .filter(|&mask| {
+            board_symmetries()
+                .iter()
+                .all(|cells| map_cells(cells, mask) >= mask)
+        })
+        .collect()
+}
+
+fn map_cells(cells: &[u8; 16], mask: u16) -> u16 {
+    (0..16)
+        .filter(|cell| mask & (1 << cell) != 0)
+        .fold(0, |mapped, cell| mapped | 1 << cells[cell])
+}
+
+fn pieces_from_mask(pieces: u16) -> Vec<Piece> {
+    (0..16)
+        .filter(|bits| pieces & (1 << bits) != 0)
+        .map(Piece::from_bits)
+        .collect()
+}
+
+// cellsのマスにpiecesの駒を1つずつ置き，どのラインも揃っていない盤面をすべてvisitに渡す．
+// visitには置かずに残った駒も渡す
+fn fill(board: &Board, cells: u16, pieces: u16, visit: &mut impl FnMut(&Board, u16)) {
+    if cells == 0 {
+        visit(board, pieces);
+        return;
+    }
+    let cell = cells.trailing_zeros() as usize;
+    let mut rest = pieces;
+    while rest != 0 {
+        let bits = rest.trailing_zeros() as u8;
+        rest &= rest - 1;
+        let mut next = *board;
+        next.place_piece(cell / 4, cell % 4, Piece::from_bits(bits))
+            .unwrap();
+        if !next.check_win() {
+            fill(&next, cells & (cells - 1), pieces & !(1 << bits), visit);
+        }
+    }
+}
+
+// 残りマス数が1つ少ない局面まで揃った表に，keysの局面を解いて加える
+fn add_layer(tablebase: &mut Tablebase, keys: Vec<u128>) {
+    let outcomes: Vec<Outcome> = keys
+        .iter()
+        .map(|&key| {
+            let game = Game::from_position_key(key).unwrap();
+            tablebase
+                .solve_from_children(&game)
+                .expect("Previous layer has every child position")
+        })
+        .collect();
+    let mut entries: Vec<(u128, Outcome)> = tablebase
+        .keys
+        .iter()
+        .copied()
+        .zip(tablebase.outcomes.iter().copied())
+        .chain(keys.into_iter().zip(outcomes))
+        .collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+    tablebase.keys = entries.iter().map(|&(key, _)| key).collect();
+    tablebase.outcomes = entries.iter().map(|&(_, outcome)| outcome).collect();
+    tablebase.max_empty_cells += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluators::NeutralEvaluator;
+    use crate::policies::alpha_beta_policy::{AlphaBetaPolicy, WIN_SCORE};
+    use crate::policies::{Policy, RandomPolicy};
+    use crate::tablebase::ProbeMiss;
+
+    fn random_position(empty_cells: usize) -> Game {
+        let policy = RandomPolicy::new();
+        loop {
+            let mut game = Game::new();
+            while !game.is_game_over() && game.board.empty_positions().len() > empty_cells {
+                let action = policy.action(&game);
+                game.play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+            }
+            if !game.is_game_over() {
+                return game;
+            }
+        }
+    }
+
+    // 局面とそこから到達できる局面だけを後退解析で解いた表を作る
+    fn sub_tablebase(game: &Game) -> Tablebase {
+        let empty_cells = game.board.empty_positions().len();
+        let mut layers = vec![vec![canonical_key(game).0]];
+        for _ in 1..empty_cells {
+            let mut children = vec![];
+            for &key in layers.last().unwrap() {
+                let game = Game::from_position_key(key).unwrap();
+                if game.board.find_winning_cell(game.selected_piece).is_some() {
+                    continue;
+                }
+                for action in game.legal_actions() {
+                    let mut next_state = game.clone();
+                    next_state.play_turn_unchecked(action.row, action.col, action.piece_index);
+                    children.push(canonical_key(&next_state).0);
+                }
+            }
+            children.sort_unstable();
+            children.dedup();
+            layers.push(children);
+        }
+        let mut tablebase = Tablebase::default();
+        for keys in layers.into_iter().rev() {
+            add_layer(&mut tablebase, keys);
+        }
+        tablebase
+    }
+
+    #[test]
+    fn test_generate_without_empty_cells() {
+        let tablebase = TablebaseGenerator::new(0).generate();
+        assert!(tablebase.is_empty());
+        assert_eq!(
+            tablebase.probe(&random_position(1)),
+            Err(ProbeMiss::TooManyEmptyCells)
+        );
+        let mut over = Game::from_position_string("7293/0d.c/5186/fae4 b").unwrap();
+        over.play_turn(1, 2, None).unwrap();
+        assert_eq!(tablebase.probe(&over), Err(ProbeMiss::GameOver));
+    }
+
+    #[test]
+    fn test_empty_cell_sets_cover_every_set() {
+        // 代表の集合を対称変換で移した集合を合わせると，すべての集合がちょうど1回ずつ現れる
+        for empty_cells in 0..=16 {
+            let mut covered = vec![];
+            for mask in empty_cell_sets(empty_cells) {
+                let mut orbit: Vec<u16> = board_symmetries()
+                    .iter()
+                    .map(|cells| map_cells(cells, mask))
+                    .collect();
+                orbit.sort_unstable();
+                orbit.dedup();
+                covered.extend(orbit);
+            }
+            covered.sort_unstable();
+            let expected: Vec<u16> = (0..=u16::MAX)
+                .filter(|mask| mask.count_ones() as usize == empty_cells)
+                .collect();
+            assert_eq!(covered, expected);
+        }
+    }
+
+    fn cell_codes(board: &Board) -> [u8; 16] {
+        std::array::from_fn(|cell| {
+            board
+                .piece_at(cell / 4, cell % 4)
+                .map_or(0, |piece| piece.bits() + 1)
+        })
+    }
+
+    #[test]
+    fn test_fill_matches_brute_force() {
+        // 上3段だけ駒を残した盤面の下段の空いていないマスを，残りの駒で埋め直す
+        let game = random_position(5);
+        let mut base = Board::new();
+        let mut placed = 0u16;
+        for cell in 0..12 {
+            if let Some(piece) = game.board.piece_at(cell / 4, cell % 4) {
+                base.place_piece(cell / 4, cell % 4, piece).unwrap();
+                placed |= 1 << piece.bits();
+            }
+        }
+        let cells = !game.board.empty_cells() & 0xF000;
+        let pieces = !placed;
+
+        let mut filled = vec![];
+        fill(&base, cells, pieces, &mut |board, rest| {
+            filled.push((cell_codes(board), rest))
+        });
+
+        // 駒の並べ方をすべて試して，ラインが揃わない盤面を集める
+        let targets: Vec<usize> = (12..16).filter(|cell| cells & (1 << cell) != 0).collect();
+        let mut expected = vec![];
+        let mut stack = vec![(base, 0, pieces)];
+        while let Some((board, depth, rest)) = stack.pop() {
+            if depth == targets.len() {
+                if !board.check_win() {
+                    expected.push((cell_codes(&board), rest));
+                }
+                continue;
+            }
+            for bits in (0..16).filter(|bits| rest & (1 << bits) != 0) {
+                let mut next = board;
+                let cell = targets[depth];
+                next.place_piece(cell / 4, cell % 4, Piece::from_bits(bits))
+                    .unwrap();
+                stack.push((next, depth + 1, rest & !(1 << bits)));
+            }
+        }
+        filled.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(filled, expected);
+    }
+
+    #[test]
+    fn test_tablebase_agrees_with_alpha_beta() {
+        let alpha_beta = AlphaBetaPolicy {
+            max_time: 10.0,
+            ..AlphaBetaPolicy::with_evaluator(NeutralEvaluator::default())
+        };
+        for _ in 0..5 {
+            let game = random_position(4);
+            let tablebase = sub_tablebase(&game);
+            let outcome = tablebase.probe(&game).unwrap();
+            let score = alpha_beta.search(&game).score;
+            let expected = match outcome {
+                Outcome::Win(plies) => WIN_SCORE - (plies as i32 - 1),
+                Outcome::Draw => 0,
+                Outcome::Loss(plies) => -(WIN_SCORE - (plies as i32 - 1)),
+            };
+            assert_eq!(score, expected, "αβ法と同じ結果になるはず");
+
+            let (action, best) = tablebase.best_action(&game).unwrap();
+            assert_eq!(best, outcome, "最善手の結果は局面の結果と同じはず");
+            assert!(game
+                .clone()
+                .play_turn(action.row, action.col, action.piece_index)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_tablebase_file_round_trip() {
+        let game = random_position(4);
+        let tablebase = sub_tablebase(&game);
+        let outcome = tablebase.probe(&game).unwrap();
+        assert!(tablebase.index(&game).unwrap() < tablebase.len());
+
+        let path = std::env::temp_dir().join("quart_engine_tablebase_test.qtb");
+        let path = path.to_str().unwrap();
+        tablebase.save(path).unwrap();
+        let loaded = Tablebase::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.len(), tablebase.len());
+        assert_eq!(
+            loaded.probe(&game),
+            Ok(outcome),
+            "読み込んだ表でも同じ結果になるはず"
+        );
+    }
+
+    #[test]
+    fn test_tablebase_rejects_incomplete_file() {
+        // 残りマス数1の局面を1つ抜くと，その親の局面を子から解けなくなる
+        let (game, tablebase, index) = loop {
+            let game = random_position(3);
+            let tablebase = sub_tablebase(&game);
+            let index = tablebase.keys.iter().position(|&key| {
+                Game::from_position_key(key)
+                    .unwrap()
+                    .board
+                    .empty_positions()
+                    .len()
+                    == 1
+            });
+            if let Some(index) = index {
+                break (game, tablebase, index);
+            }
+        };
+        assert!(Tablebase::from_bytes(&tablebase.to_bytes()).is_ok());
+
+        let mut missing = tablebase.clone();
+        missing.keys.remove(index);
+        missing.outcomes.remove(index);
+        assert!(
+            Tablebase::from_bytes(&missing.to_bytes()).is_err(),
+            "子の局面が欠けた表は読み込まない"
+        );
+
+        let mut wrong = tablebase.clone();
+        let index = wrong.index(&game).unwrap();
+        wrong.outcomes[index] = match wrong.outcomes[index] {
+            Outcome::Draw => Outcome::Win(1),
+            _ => Outcome::Draw,
+        };
+        assert!(
+            Tablebase::from_bytes(&wrong.to_bytes()).is_err(),
+            "子の局面と合わない結果は読み込まない"
+        );
+    }
+}
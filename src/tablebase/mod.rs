@@ -0,0 +1,306 @@
+pub mod generator;
+
+pub use generator::TablebaseGenerator;
+
+use crate::game::action::Action;
+use crate::game::symmetry::canonical_key;
+use crate::game::Game;
+use std::cmp::Ordering;
+use std::fmt;
+
+// ファイルの先頭に書くマジックナンバー
+const MAGIC: &[u8; 4] = b"QTB1";
+// position_keyは16セル × 5ビット + 4ビット = 84ビットなので11バイトに詰める
+const KEY_BYTES: usize = 11;
+
+/// 手番側から見た，最善を尽くしたときの結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// 何手目（自分の手番を1とする）で勝つか
+    Win(u8),
+    Draw,
+    /// 何手目（自分の手番を1とする）で負けるか
+    Loss(u8),
+}
+
+impl Outcome {
+    /// 1手指した後の局面（相手の手番）の結果から，手番側の結果を求める
+    pub fn from_child(child: Outcome) -> Outcome {
+        match child {
+            Outcome::Win(plies) => Outcome::Loss(plies + 1),
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss(plies) => Outcome::Win(plies + 1),
+        }
+    }
+
+    // 大きいほど手番側にとって良い値．早く勝つ・遅く負ける方を良いとする
+    fn value(&self) -> i16 {
+        match self {
+            Outcome::Win(plies) => 100 - *plies as i16,
+            Outcome::Draw => 0,
+            Outcome::Loss(plies) => -100 + *plies as i16,
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Outcome::Win(plies) => plies,
+            Outcome::Draw => 0,
+            Outcome::Loss(plies) => (-(plies as i8)) as u8,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Outcome {
+        match (byte as i8).cmp(&0) {
+            Ordering::Greater => Outcome::Win(byte),
+            Ordering::Equal => Outcome::Draw,
+            Ordering::Less => Outcome::Loss((-(byte as i8)) as u8),
+        }
+    }
+}
+
+impl PartialOrd for Outcome {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Outcome {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value().cmp(&other.value())
+    }
+}
+
+/// 表を引けなかった理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeMiss {
+    /// 終局している
+    GameOver,
+    /// 残りマス数がmax_empty_cellsより多い
+    TooManyEmptyCells,
+}
+
+impl fmt::Display for ProbeMiss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProbeMiss::GameOver => write!(f, "Game is already over"),
+            ProbeMiss::TooManyEmptyCells => write!(f, "Too many empty cells"),
+        }
+    }
+}
+
+/// 残りマス数がmax_empty_cells以下で終局していない局面すべての結果を，
+/// 対称性で同一視したキー(canonical_key)で引けるようにした表．
+/// キーは昇順に並べてあるので，キーの順位がそのまま衝突の無い連番の添字(index)になる
+#[derive(Debug, Clone, Default)]
+pub struct Tablebase {
+    pub max_empty_cells: usize,
+    keys: Vec<u128>,
+    outcomes: Vec<Outcome>,
+}
+
+impl Tablebase {
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// 局面の添字を返す．表に無い局面ならNone
+    pub fn index(&self, game: &Game) -> Option<usize> {
+        let (key, _) = canonical_key(game);
+        self.keys.binary_search(&key).ok()
+    }
+
+    /// 局面の結果を返す．表は完全なので，引けないのは終局しているか残りマス数が多すぎるときだけ
+    pub fn probe(&self, game: &Game) -> Result<Outcome, ProbeMiss> {
+        if game.is_game_over() {
+            return Err(ProbeMiss::GameOver);
+        }
+        if game.board.empty_positions().len() > self.max_empty_cells {
+            return Err(ProbeMiss::TooManyEmptyCells);
+        }
+        Ok(self
+            .lookup(game)
+            .expect("Tablebase has every position up to max_empty_cells"))
+    }
+
+    fn lookup(&self, game: &Game) -> Option<Outcome> {
+        self.index(game).map(|index| self.outcomes[index])
+    }
+
+    // 表を引いて，子の局面の結果から局面の結果を求める．子の局面が表に無ければNone
+    fn solve_from_children(&self, game: &Game) -> Option<Outcome> {
+        if game.board.find_winning_cell(game.selected_piece).is_some() {
+            return Some(Outcome::Win(1));
+        }
+        if game.available_pieces.is_empty() {
+            // 勝てずに最後の駒を置くなら引き分け
+            return Some(Outcome::Draw);
+        }
+        // 置いても勝てないので，子の局面は終局しておらず残りマス数が1つ少ない
+        let mut best = Outcome::Loss(1);
+        for action in game.legal_actions() {
+            let mut next_state = game.clone();
+            next_state.play_turn_unchecked(action.row, action.col, action.piece_index);
+            best = best.max(Outcome::from_child(self.lookup(&next_state)?));
+        }
+        Some(best)
+    }
+
+    /// 表を引いて最善手とその結果を返す．表を引けなければその理由を返す
+    pub fn best_action(&self, game: &Game) -> Result<(Action, Outcome), ProbeMiss> {
+        let outcome = self.probe(game)?;
+
+        // 置いて勝てる手は表を引くまでもない
+        if let Some((row, col)) = game.board.find_winning_cell(game.selected_piece) {
+            let piece_index = if game.available_pieces.is_empty() {
+                None
+            } else {
+                Some(0)
+            };
+            return Ok((
+                Action {
+                    row,
+                    col,
+                    piece_index,
+                },
+                Outcome::Win(1),
+            ));
+        }
+
+        let mut best: Option<(Action, Outcome)> = None;
+        for action in game.legal_actions() {
+            let mut next_state = game.clone();
+            next_state.play_turn_unchecked(action.row, action.col, action.piece_index);
+            let child = if next_state.board.is_full() {
+                Outcome::Draw
+            } else {
+                self.probe(&next_state)?
+            };
+            let result = Outcome::from_child(child);
+            if best
+                .as_ref()
+                .is_none_or(|(_, best_result)| result > *best_result)
+            {
+                best = Some((action, result));
+            }
+        }
+
+        // 表の結果は子の局面の結果から求めてあるので，最善手の結果と一致する
+        let best = best.unwrap();
+        debug_assert_eq!(best.1, outcome);
+        Ok(best)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(13 + self.len() * (KEY_BYTES + 1));
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(self.max_empty_cells as u8);
+        bytes.extend_from_slice(&(self.len() as u64).to_le_bytes());
+        for (key, outcome) in self.keys.iter().zip(self.outcomes.iter()) {
+            bytes.extend_from_slice(&key.to_le_bytes()[..KEY_BYTES]);
+            bytes.push(outcome.to_byte());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 13 || &bytes[..4] != MAGIC {
+            return Err("Not a tablebase file".to_string());
+        }
+        let max_empty_cells = bytes[4] as usize;
+        let len = u64::from_le_bytes(bytes[5..13].try_into().unwrap()) as usize;
+        let body = &bytes[13..];
+        if body.len() != len * (KEY_BYTES + 1) {
+            return Err("Tablebase file is truncated".to_string());
+        }
+
+        let mut keys = Vec::with_capacity(len);
+        let mut outcomes = Vec::with_capacity(len);
+        for entry in body.chunks_exact(KEY_BYTES + 1) {
+            let mut key_bytes = [0u8; 16];
+            key_bytes[..KEY_BYTES].copy_from_slice(&entry[..KEY_BYTES]);
+            keys.push(u128::from_le_bytes(key_bytes));
+            outcomes.push(Outcome::from_byte(entry[KEY_BYTES]));
+        }
+        if keys.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err("Tablebase keys are not sorted".to_string());
+        }
+
+        let tablebase = Tablebase {
+            max_empty_cells,
+            keys,
+            outcomes,
+        };
+        tablebase.verify()?;
+        Ok(tablebase)
+    }
+
+    // 各局面がcanonical_keyで表せる範囲内の局面で，子の局面がすべて表にあり，
+    // 結果が子の局面の結果と合っているかを確かめる
+    fn verify(&self) -> Result<(), String> {
+        for (&key, &outcome) in self.keys.iter().zip(self.outcomes.iter()) {
+            let game = Game::from_position_key(key)?;
+            let empty_cells = game.board.empty_positions().len();
+            if canonical_key(&game).0 != key
+                || game.is_game_over()
+                || empty_cells > self.max_empty_cells
+            {
+                return Err(format!("Invalid tablebase position: {:x}", key));
+            }
+            match self.solve_from_children(&game) {
+                Some(solved) if solved == outcome => {}
+                Some(_) => return Err(format!("Wrong tablebase outcome: {:x}", key)),
+                None => return Err(format!("Tablebase is incomplete: {:x}", key)),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_bytes()).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_order() {
+        assert!(Outcome::Win(1) > Outcome::Win(3), "早く勝つ方が良い");
+        assert!(Outcome::Win(3) > Outcome::Draw, "勝ちは引き分けより良い");
+        assert!(Outcome::Draw > Outcome::Loss(4), "引き分けは負けより良い");
+        assert!(Outcome::Loss(4) > Outcome::Loss(2), "遅く負ける方が良い");
+        assert_eq!(Outcome::from_child(Outcome::Loss(2)), Outcome::Win(3));
+    }
+
+    #[test]
+    fn test_outcome_byte_round_trip() {
+        for outcome in [Outcome::Win(5), Outcome::Draw, Outcome::Loss(2)] {
+            assert_eq!(Outcome::from_byte(outcome.to_byte()), outcome);
+        }
+    }
+
+    #[test]
+    fn test_tablebase_rejects_broken_file() {
+        assert!(
+            Tablebase::from_bytes(b"XXXX").is_err(),
+            "マジックナンバーが違えばエラー"
+        );
+        let mut bytes = Tablebase::default().to_bytes();
+        bytes[5] = 1;
+        assert!(
+            Tablebase::from_bytes(&bytes).is_err(),
+            "件数と中身が合わなければエラー"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use crate::tournament::Record;
+use std::f64::consts::LN_10;
+
+// 自然対数のスケールのレーティング差をEloに直す係数（10^(d/400) = e^(d ln10 / 400)）
+const ELO_SCALE: f64 = 400.0 / LN_10;
+// 全勝・全敗でもレーティングが発散しないように，各プレイヤーにレーティング0の相手との1勝1敗を加える
+const PRIOR_GAMES: f64 = 2.0;
+
+/// Eloレーティングの推定値と標準誤差
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EloEstimate {
+    pub elo: f64,
+    pub standard_error: f64,
+}
+
+impl EloEstimate {
+    /// 95%信頼区間
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        (
+            self.elo - 1.96 * self.standard_error,
+            self.elo + 1.96 * self.standard_error,
+        )
+    }
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// 対戦成績(player, opponent, playerから見た成績)からBradley-Terryモデルの最尤推定でEloを求める．
+/// 引き分けは0.5勝とし，平均が0になるようにずらす
+pub fn estimate_elo(n_players: usize, results: &[(usize, usize, Record)]) -> Vec<EloEstimate> {
+    let mut ratings = vec![0.0; n_players];
+    let mut hessians = vec![0.0; n_players];
+    for _ in 0..1000 {
+        let mut max_delta: f64 = 0.0;
+        for i in 0..n_players {
+            // 対数尤度の1階微分と2階微分（の符号を反転したもの）でニュートン法を行う
+            let p = sigmoid(ratings[i]);
+            let mut gradient = PRIOR_GAMES * (0.5 - p);
+            let mut hessian = PRIOR_GAMES * p * (1.0 - p);
+            for &(player, opponent, ref record) in results.iter() {
+                let (opponent, score, games) = if player == i {
+                    (opponent, record.score(), record.games() as f64)
+                } else if opponent == i {
+                    (
+                        player,
+                        record.games() as f64 - record.score(),
+                        record.games() as f64,
+                    )
+                } else {
+                    continue;
+                };
+                let p = sigmoid(ratings[i] - ratings[opponent]);
+                gradient += score - games * p;
+                hessian += games * p * (1.0 - p);
+            }
+            let delta = gradient / hessian;
+            ratings[i] += delta;
+            hessians[i] = hessian;
+            max_delta = max_delta.max(delta.abs());
+        }
+        if max_delta < 1e-9 {
+            break;
+        }
+    }
+
+    let mean = ratings.iter().sum::<f64>() / n_players.max(1) as f64;
+    ratings
+        .iter()
+        .zip(hessians.iter())
+        .map(|(rating, hessian)| EloEstimate {
+            elo: (rating - mean) * ELO_SCALE,
+            standard_error: ELO_SCALE / hessian.sqrt(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_elo_even() {
+        let record = Record {
+            wins: 10,
+            draws: 5,
+            losses: 10,
+        };
+        let estimates = estimate_elo(2, &[(0, 1, record)]);
+        assert!(estimates[0].elo.abs() < 1e-6, "五分の成績なら差は0のはず");
+        assert!(estimates[0].standard_error > 0.0);
+    }
+
+    #[test]
+    fn test_estimate_elo_stronger_player() {
+        // 勝率75%はおよそ191 Elo差
+        let record = Record {
+            wins: 750,
+            draws: 0,
+            losses: 250,
+        };
+        let estimates = estimate_elo(2, &[(0, 1, record)]);
+        let difference = estimates[0].elo - estimates[1].elo;
+        assert!(
+            (185.0..=192.0).contains(&difference),
+            "Elo差: {}",
+            difference
+        );
+        let (lower, upper) = estimates[0].confidence_interval();
+        assert!(lower < estimates[0].elo && estimates[0].elo < upper);
+
+        let perfect = Record {
+            wins: 10,
+            draws: 0,
+            losses: 0,
+        };
+        let estimates = estimate_elo(2, &[(0, 1, perfect)]);
+        assert!(estimates[0].elo.is_finite(), "全勝でも発散しないはず");
+    }
+}
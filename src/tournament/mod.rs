@@ -0,0 +1,480 @@
+pub mod elo;
+pub mod openings;
+pub mod sprt;
+
+pub use elo::{estimate_elo, EloEstimate};
+pub use openings::{OpeningMatch, OpeningMatchResult, OpeningSuite, SuiteGenerator};
+pub use sprt::{Sprt, SprtDecision, SprtResult};
+
+use crate::game::Player;
+use crate::policies::PolicyConfig;
+use crate::runner::{available_threads, num_workers, run_jobs, Runner};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 勝ち・引き分け・負けの数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Record {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// 勝ちを1，引き分けを0.5とした得点
+    pub fn score(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 * 0.5
+    }
+
+    /// 相手から見た成績
+    pub fn reversed(&self) -> Record {
+        Record {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+}
+
+/// 名前付きのpolicyの設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entrant {
+    pub name: String,
+    pub config: PolicyConfig,
+}
+
+fn default_games_per_pairing() -> usize {
+    10
+}
+
+/// 総当たり戦．各組み合わせでgames_per_pairing局を先手・後手を交互に入れ替えて対局する
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tournament {
+    pub entrants: Vec<Entrant>,
+    #[serde(default = "default_games_per_pairing")]
+    pub games_per_pairing: usize,
+    /// 全体で使うスレッド数の上限
+    #[serde(default = "available_threads")]
+    pub num_threads: usize,
+}
+
+impl Tournament {
+    pub fn new(entrants: Vec<Entrant>) -> Self {
+        Tournament {
+            entrants,
+            games_per_pairing: default_games_per_pairing(),
+            num_threads: available_threads(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let tournament: Tournament = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        tournament.validate()?;
+        Ok(tournament)
+    }
+
+    /// 対局できない設定ならエラーを返す
+    pub fn validate(&self) -> Result<(), String> {
+        if self.games_per_pairing == 0 {
+            return Err("games_per_pairing must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn run(&self) -> Result<TournamentResult, String> {
+        self.validate()?;
+        // 全組み合わせの全対局を並べ，並列に対局してから組み合わせごとに集計する
+        let mut games = vec![];
+        for i in 0..self.entrants.len() {
+            for j in (i + 1)..self.entrants.len() {
+                for game_index in 0..self.games_per_pairing {
+                    // 偶数局目はiが先手，奇数局目はjが先手
+                    games.push((i, j, game_index % 2 == 0));
+                }
+            }
+        }
+        let threads_per_game = self
+            .entrants
+            .iter()
+            .map(|entrant| entrant.config.build().num_threads())
+            .max()
+            .unwrap_or(1);
+        let winners = run_jobs(
+            games.len(),
+            num_workers(self.num_threads, threads_per_game),
+            |index| {
+                let (i, j, i_first) = games[index];
+                let (first, second) = if i_first { (i, j) } else { (j, i) };
+                Runner::new(
+                    self.entrants[first].config.build(),
+                    self.entrants[second].config.build(),
+                )
+                .run()
+            },
+        );
+
+        let mut pairings: Vec<Pairing> = vec![];
+        for (&(i, j, i_first), winner) in games.iter().zip(winners) {
+            if pairings
+                .last()
+                .is_none_or(|pairing| (pairing.player, pairing.opponent) != (i, j))
+            {
+                pairings.push(Pairing {
+                    player: i,
+                    opponent: j,
+                    record: Record::default(),
+                });
+            }
+            let record = &mut pairings.last_mut().unwrap().record;
+            match (winner, i_first) {
+                (None, _) => record.draws += 1,
+                (Some(Player::Player1), true) | (Some(Player::Player2), false) => record.wins += 1,
+                _ => record.losses += 1,
+            }
+        }
+        Ok(TournamentResult {
+            names: self
+                .entrants
+                .iter()
+                .map(|entrant| entrant.name.clone())
+                .collect(),
+            pairings,
+        })
+    }
+}
+
+/// 1つの組み合わせの成績（playerから見た成績）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pairing {
+    pub player: usize,
+    pub opponent: usize,
+    pub record: Record,
+}
+
+/// 順位表の1行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    pub name: String,
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub score: f64,
+    pub elo: f64,
+    /// Eloの95%信頼区間
+    pub elo_lower: f64,
+    pub elo_upper: f64,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    standings: Vec<Standing>,
+    // crosstable[i][j]はiから見たjとの成績
+    crosstable: Vec<Vec<Option<Record>>>,
+    names: &'a [String],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TournamentResult {
+    pub names: Vec<String>,
+    pub pairings: Vec<Pairing>,
+}
+
+impl TournamentResult {
+    /// iから見たjとの成績．対局していなければNone
+    pub fn record(&self, i: usize, j: usize) -> Option<Record> {
+        self.pairings.iter().find_map(|pairing| {
+            if (pairing.player, pairing.opponent) == (i, j) {
+                Some(pairing.record)
+            } else if (pairing.player, pairing.opponent) == (j, i) {
+                Some(pairing.record.reversed())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Eloの高い順に並べた順位表
+    pub fn standings(&self) -> Vec<Standing> {
+        let results: Vec<(usize, usize, Record)> = self
+            .pairings
+            .iter()
+            .map(|pairing| (pairing.player, pairing.opponent, pairing.record))
+            .collect();
+        let estimates = estimate_elo(self.names.len(), &results);
+
+        let mut standings: Vec<Standing> = self
+            .names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mut total = Record::default();
+                for j in 0..self.names.len() {
+                    if let Some(record) = self.record(i, j) {
+                        total.wins += record.wins;
+                        total.draws += record.draws;
+                        total.losses += record.losses;
+                    }
+                }
+                let (elo_lower, elo_upper) = estimates[i].confidence_interval();
+                Standing {
+                    name: name.clone(),
+                    games: total.games(),
+                    wins: total.wins,
+                    draws: total.draws,
+                    losses: total.losses,
+                    score: total.score(),
+                    elo: estimates[i].elo,
+                    elo_lower,
+                    elo_upper,
+                }
+            })
+            .collect();
+        standings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+        standings
+    }
+
+    fn crosstable(&self) -> Vec<Vec<Option<Record>>> {
+        (0..self.names.len())
+            .map(|i| (0..self.names.len()).map(|j| self.record(i, j)).collect())
+            .collect()
+    }
+
+    /// 順位表とクロス表をJSONで出力する
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&Report {
+            standings: self.standings(),
+            crosstable: self.crosstable(),
+            names: &self.names,
+        })
+        .unwrap()
+    }
+
+    pub fn standings_to_csv(&self) -> String {
+        let mut csv =
+            String::from("rank,name,games,wins,draws,losses,score,elo,elo_lower,elo_upper\n");
+        for (rank, standing) in self.standings().iter().enumerate() {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{:.1},{:.1},{:.1}\n",
+                rank + 1,
+                escape_csv(&standing.name),
+                standing.games,
+                standing.wins,
+                standing.draws,
+                standing.losses,
+                standing.score,
+                standing.elo,
+                standing.elo_lower,
+                standing.elo_upper
+            );
+        }
+        csv
+    }
+
+    /// クロス表のCSV．各セルは行のプレイヤーから見た「勝ち-引き分け-負け」
+    pub fn crosstable_to_csv(&self) -> String {
+        let mut csv = String::from("name");
+        for name in self.names.iter() {
+            csv += &format!(",{}", escape_csv(name));
+        }
+        csv += "\n";
+        for (name, row) in self.names.iter().zip(self.crosstable()) {
+            csv += &escape_csv(name);
+            for record in row {
+                csv += &format!(",{}", format_record(record));
+            }
+            csv += "\n";
+        }
+        csv
+    }
+
+    /// 順位表とクロス表のMarkdown
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("## Standings\n\n");
+        markdown += "| Rank | Name | Games | W | D | L | Score | Elo | 95% CI |\n";
+        markdown += "|---:|---|---:|---:|---:|---:|---:|---:|---|\n";
+        for (rank, standing) in self.standings().iter().enumerate() {
+            markdown += &format!(
+                "| {} | {} | {} | {} | {} | {} | {} | {:.1} | [{:.1}, {:.1}] |\n",
+                rank + 1,
+                escape_markdown(&standing.name),
+                standing.games,
+                standing.wins,
+                standing.draws,
+                standing.losses,
+                standing.score,
+                standing.elo,
+                standing.elo_lower,
+                standing.elo_upper
+            );
+        }
+
+        markdown += "\n## Crosstable\n\n|   |";
+        for name in self.names.iter() {
+            markdown += &format!(" {} |", escape_markdown(name));
+        }
+        markdown += "\n|---|";
+        markdown += &"---|".repeat(self.names.len());
+        markdown += "\n";
+        for (name, row) in self.names.iter().zip(self.crosstable()) {
+            markdown += &format!("| {} |", escape_markdown(name));
+            for record in row {
+                markdown += &format!(" {} |", format_record(record));
+            }
+            markdown += "\n";
+        }
+        markdown
+    }
+
+    /// dirにtournament.json，standings.csv，crosstable.csv，tournament.mdを書き出す
+    pub fn save_reports(&self, dir: &str) -> Result<(), String> {
+        let dir = Path::new(dir);
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        for (file_name, contents) in [
+            ("tournament.json", self.to_json()),
+            ("standings.csv", self.standings_to_csv()),
+            ("crosstable.csv", self.crosstable_to_csv()),
+            ("tournament.md", self.to_markdown()),
+        ] {
+            std::fs::write(dir.join(file_name), contents).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+fn format_record(record: Option<Record>) -> String {
+    match record {
+        Some(record) => format!("{}-{}-{}", record.wins, record.draws, record.losses),
+        None => "-".to_string(),
+    }
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// 表のセルの区切りにならないように'|'をエスケープし，改行は空白にする
+fn escape_markdown(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tournament() -> Tournament {
+        Tournament {
+            entrants: vec![
+                Entrant {
+                    name: "random".to_string(),
+                    config: PolicyConfig::Random,
+                },
+                Entrant {
+                    name: "one_step".to_string(),
+                    config: PolicyConfig::OneStepLookAhead,
+                },
+                Entrant {
+                    name: "two_step".to_string(),
+                    config: PolicyConfig::TwoStepLookAhead,
+                },
+            ],
+            games_per_pairing: 4,
+            num_threads: 2,
+        }
+    }
+
+    #[test]
+    fn test_tournament_from_json() {
+        let json = r#"{
+            "entrants": [
+                {"name": "random", "config": {"type": "random"}},
+                {"name": "mcs", "config": {"type": "mcs", "max_time": 0.001}}
+            ]
+        }"#;
+        let tournament = Tournament::from_json(json).unwrap();
+        assert_eq!(tournament.entrants.len(), 2);
+        assert_eq!(tournament.games_per_pairing, 10);
+
+        let json = r#"{"entrants": [], "games_per_pairing": 0}"#;
+        assert!(Tournament::from_json(json).is_err());
+        let tournament = Tournament {
+            games_per_pairing: 0,
+            ..tournament
+        };
+        assert!(tournament.run().is_err(), "対局しない設定は受け付けない");
+    }
+
+    #[test]
+    fn test_tournament_run() {
+        let result = tournament().run().unwrap();
+        assert_eq!(result.pairings.len(), 3, "3人の総当たりは3組のはず");
+        for pairing in result.pairings.iter() {
+            assert_eq!(pairing.record.games(), 4);
+        }
+        assert_eq!(
+            result.record(2, 0),
+            result.record(0, 2).map(|record| record.reversed()),
+            "相手から見た成績は勝ちと負けが逆になるはず"
+        );
+        assert_eq!(result.record(1, 1), None);
+
+        let standings = result.standings();
+        assert_eq!(standings.len(), 3);
+        assert!(standings.windows(2).all(|pair| pair[0].elo >= pair[1].elo));
+        assert!(standings.iter().all(|standing| standing.games == 8));
+        let total_score: f64 = standings.iter().map(|standing| standing.score).sum();
+        assert_eq!(total_score, 12.0, "得点の合計は対局数と同じはず");
+    }
+
+    #[test]
+    fn test_tournament_reports() {
+        let result = TournamentResult {
+            names: vec!["a".to_string(), "b, c".to_string()],
+            pairings: vec![Pairing {
+                player: 0,
+                opponent: 1,
+                record: Record {
+                    wins: 3,
+                    draws: 1,
+                    losses: 0,
+                },
+            }],
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&result.to_json()).unwrap();
+        assert_eq!(json["standings"][0]["name"], "a");
+        assert_eq!(json["crosstable"][1][0]["wins"], 0);
+
+        let standings = result.standings_to_csv();
+        assert_eq!(standings.lines().count(), 3);
+        assert!(standings.contains("\"b, c\""), "カンマを含む名前は囲むはず");
+        let crosstable = result.crosstable_to_csv();
+        assert_eq!(crosstable.lines().nth(1).unwrap(), "a,-,3-1-0");
+
+        let markdown = result.to_markdown();
+        assert!(markdown.contains("| a | - | 3-1-0 |"));
+
+        let result = TournamentResult {
+            names: vec!["a|b".to_string(), "c".to_string()],
+            ..result
+        };
+        let markdown = result.to_markdown();
+        assert!(
+            markdown.contains("| a\\|b | - | 3-1-0 |"),
+            "名前の'|'で列が増えないはず: {}",
+            markdown
+        );
+        assert!(markdown.contains("|   | a\\|b | c |"));
+    }
+}
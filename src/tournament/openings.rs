@@ -0,0 +1,319 @@
+use crate::evaluators::HandcraftedEvaluator;
+use crate::game::symmetry::canonical_key;
+use crate::game::{Game, Player};
+use crate::policies::alpha_beta_policy::{is_decisive, AlphaBetaPolicy};
+use crate::policies::{Policy, PolicyConfig, RandomPolicy};
+use crate::runner::{available_threads, num_workers, run_jobs, Runner};
+use crate::tournament::Record;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// 対局を始める局面の集合
+#[derive(Debug, Clone, Default)]
+pub struct OpeningSuite {
+    pub positions: Vec<Game>,
+}
+
+impl OpeningSuite {
+    /// 1行に1局面の文字列（Game::from_position_string）を書いたテキストを読み込む．空行と#から始まる行は無視する
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let positions = text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(Game::from_position_string)
+            .collect::<Result<Vec<Game>, String>>()?;
+        Ok(OpeningSuite { positions })
+    }
+
+    /// 局面の文字列かGame::to_jsonの形式の局面を並べたJSONの配列を読み込む
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let values: Vec<serde_json::Value> =
+            serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let positions = values
+            .into_iter()
+            .map(|value| match value {
+                serde_json::Value::String(position) => Game::from_position_string(&position),
+                value => serde_json::from_value(value).map_err(|e| e.to_string()),
+            })
+            .collect::<Result<Vec<Game>, String>>()?;
+        Ok(OpeningSuite { positions })
+    }
+
+    pub fn to_text(&self) -> String {
+        self.positions
+            .iter()
+            .map(|game| game.to_position_string() + "\n")
+            .collect()
+    }
+
+    /// 中身が'['から始まればJSON，そうでなければテキストとして読み込む
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        if contents.trim_start().starts_with('[') {
+            Self::from_json(&contents)
+        } else {
+            Self::from_text(&contents)
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_text()).map_err(|e| e.to_string())
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+/// 1つの開始局面での，先後を入れ替えた2局の成績（playerから見た成績）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpeningResult {
+    pub position: String,
+    pub record: Record,
+}
+
+/// 開始局面ごとの成績
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpeningMatchResult {
+    pub openings: Vec<OpeningResult>,
+}
+
+impl OpeningMatchResult {
+    pub fn total(&self) -> Record {
+        let mut total = Record::default();
+        for opening in self.openings.iter() {
+            total.wins += opening.record.wins;
+            total.draws += opening.record.draws;
+            total.losses += opening.record.losses;
+        }
+        total
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("position,wins,draws,losses,score\n");
+        for opening in self.openings.iter() {
+            csv += &format!(
+                "{},{},{},{},{}\n",
+                opening.position,
+                opening.record.wins,
+                opening.record.draws,
+                opening.record.losses,
+                opening.record.score()
+            );
+        }
+        csv
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown =
+            String::from("| Position | W | D | L | Score |\n|---|---:|---:|---:|---:|\n");
+        for opening in self.openings.iter().chain(std::iter::once(&OpeningResult {
+            position: "**Total**".to_string(),
+            record: self.total(),
+        })) {
+            markdown += &format!(
+                "| {} | {} | {} | {} | {} |\n",
+                opening.position,
+                opening.record.wins,
+                opening.record.draws,
+                opening.record.losses,
+                opening.record.score()
+            );
+        }
+        markdown
+    }
+}
+
+/// 各開始局面でplayerとopponentが先後を入れ替えて2局ずつ対局する
+#[derive(Debug, Clone)]
+pub struct OpeningMatch {
+    pub player: PolicyConfig,
+    pub opponent: PolicyConfig,
+    pub suite: OpeningSuite,
+    /// 全体で使うスレッド数の上限
+    pub num_threads: usize,
+}
+
+impl OpeningMatch {
+    pub fn new(player: PolicyConfig, opponent: PolicyConfig, suite: OpeningSuite) -> Self {
+        OpeningMatch {
+            player,
+            opponent,
+            suite,
+            num_threads: available_threads(),
+        }
+    }
+
+    pub fn run(&self) -> OpeningMatchResult {
+        let threads_per_game = self
+            .player
+            .build()
+            .num_threads()
+            .max(self.opponent.build().num_threads());
+        // 2i局目はplayerがPlayer1，2i+1局目はopponentがPlayer1
+        let winners = run_jobs(
+            self.suite.len() * 2,
+            num_workers(self.num_threads, threads_per_game),
+            |index| {
+                let game = self.suite.positions[index / 2].clone();
+                let player_is_first = index % 2 == 0;
+                let (player1, player2) = if player_is_first {
+                    (self.player.build(), self.opponent.build())
+                } else {
+                    (self.opponent.build(), self.player.build())
+                };
+                Runner::with_game(game, player1, player2)
+                    .run()
+                    .map(|winner| (winner == Player::Player1) == player_is_first)
+            },
+        );
+
+        let openings = self
+            .suite
+            .positions
+            .iter()
+            .zip(winners.chunks(2))
+            .map(|(game, pair)| {
+                let mut record = Record::default();
+                for winner in pair {
+                    match winner {
+                        None => record.draws += 1,
+                        Some(true) => record.wins += 1,
+                        Some(false) => record.losses += 1,
+                    }
+                }
+                OpeningResult {
+                    position: game.to_position_string(),
+                    record,
+                }
+            })
+            .collect();
+        OpeningMatchResult { openings }
+    }
+}
+
+/// ランダムに数手進めた局面のうち，αβ探索の評価が互角に近いものを集めて開始局面の集合を作る
+pub struct SuiteGenerator {
+    /// 開始局面までに進める手数
+    pub plies: usize,
+    /// 互角とみなす評価値の絶対値の上限
+    pub max_abs_score: i32,
+    /// 局面を評価する探索
+    pub search: AlphaBetaPolicy<HandcraftedEvaluator>,
+}
+
+impl Default for SuiteGenerator {
+    fn default() -> Self {
+        SuiteGenerator {
+            plies: 4,
+            max_abs_score: 30,
+            search: AlphaBetaPolicy {
+                max_time: 0.05,
+                ..AlphaBetaPolicy::with_evaluator(HandcraftedEvaluator::default())
+            },
+        }
+    }
+}
+
+impl SuiteGenerator {
+    /// 互角に近い局面をn_positions個集める．対称な局面は1つとみなす．
+    /// n_positions × 100局面試しても集まらなければ集まった分だけ返す
+    pub fn generate(&self, n_positions: usize) -> OpeningSuite {
+        let policy = RandomPolicy::new();
+        let mut seen = HashSet::new();
+        let mut positions = vec![];
+        for _ in 0..n_positions * 100 {
+            if positions.len() >= n_positions {
+                break;
+            }
+            let mut game = Game::new();
+            for _ in 0..self.plies {
+                if game.is_game_over() {
+                    break;
+                }
+                let action = policy.action(&game);
+                game.play_turn(action.row, action.col, action.piece_index)
+                    .unwrap();
+            }
+            if game.is_game_over() || !seen.insert(canonical_key(&game).0) {
+                continue;
+            }
+            let score = self.search.search(&game).score;
+            if !is_decisive(score) && score.abs() <= self.max_abs_score {
+                positions.push(game);
+            }
+        }
+        OpeningSuite { positions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opening_suite_load() {
+        let text = "# openings\n0a3./..../..../.... 5\n\n..../.1../..../.... 2\n";
+        let suite = OpeningSuite::from_text(text).unwrap();
+        assert_eq!(suite.len(), 2, "コメントと空行は無視するはず");
+        assert_eq!(OpeningSuite::from_text(&suite.to_text()).unwrap().len(), 2);
+
+        let mut game = Game::new();
+        game.play_turn(1, 1, Some(0)).unwrap();
+        let json = format!(r#"["0a3./..../..../.... 5", {}]"#, game.to_json());
+        let suite = OpeningSuite::from_json(&json).unwrap();
+        assert_eq!(
+            suite.positions[1].to_position_string(),
+            game.to_position_string(),
+            "JSONの局面も読み込めるはず"
+        );
+        assert!(OpeningSuite::from_text("0a3./..../.... 5").is_err());
+    }
+
+    #[test]
+    fn test_opening_match_plays_each_position_twice() {
+        let suite =
+            OpeningSuite::from_text("0a3./..../..../.... 5\n..../.1../..../.... 2\n").unwrap();
+        let result = OpeningMatch {
+            num_threads: 2,
+            ..OpeningMatch::new(PolicyConfig::OneStepLookAhead, PolicyConfig::Random, suite)
+        }
+        .run();
+        assert_eq!(result.openings.len(), 2);
+        assert!(result
+            .openings
+            .iter()
+            .all(|opening| opening.record.games() == 2));
+        assert_eq!(result.openings[0].position, "0a3./..../..../.... 5 2");
+        assert_eq!(result.total().games(), 4);
+        assert_eq!(result.to_csv().lines().count(), 3);
+        assert!(result.to_markdown().contains("**Total**"));
+    }
+
+    #[test]
+    fn test_suite_generator() {
+        let generator = SuiteGenerator {
+            search: AlphaBetaPolicy {
+                max_time: 0.002,
+                ..AlphaBetaPolicy::with_evaluator(HandcraftedEvaluator::default())
+            },
+            ..SuiteGenerator::default()
+        };
+        let suite = generator.generate(3);
+        assert!(!suite.is_empty());
+        for game in suite.positions.iter() {
+            assert_eq!(game.board.empty_positions().len(), 16 - generator.plies);
+            let score = generator.search.search(game).score;
+            assert!(!is_decisive(score), "勝ち負けが決まった局面は選ばないはず");
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use crate::game::Player;
+use crate::policies::PolicyConfig;
+use crate::runner::{available_threads, num_workers, run_jobs, Runner};
+use crate::tournament::Record;
+
+// 全勝・全敗のように分散が0になっても計算できるように，五項分布の各値に足す度数
+const REGULARIZATION: f64 = 1e-3;
+
+/// 検定の結論
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtDecision {
+    /// candidateはelo1以上強い（H1を採択）
+    Stronger,
+    /// candidateはelo0以下（H0を採択）
+    NotStronger,
+    /// max_pairsまでに決着しなかった
+    Inconclusive,
+}
+
+/// 検定の途中経過・結果．成績はcandidateから見たもの
+#[derive(Debug, Clone, PartialEq)]
+pub struct SprtResult {
+    pub decision: SprtDecision,
+    pub llr: f64,
+    /// これを下回ったらH0を採択する
+    pub lower_bound: f64,
+    /// これを上回ったらH1を採択する
+    pub upper_bound: f64,
+    pub record: Record,
+    /// 先後を入れ替えた2局の得点(0, 0.5, 1, 1.5, 2)ごとのペア数
+    pub pentanomial: [u32; 5],
+    pub elo: f64,
+    /// Eloの95%信頼区間の半幅
+    pub elo_error: f64,
+}
+
+impl SprtResult {
+    pub fn pairs(&self) -> u32 {
+        self.pentanomial.iter().sum()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{:?}: LLR {:.2} [{:.2}, {:.2}], Elo {:.1} ± {:.1}, games {} (W/D/L {}/{}/{}), pairs {:?}",
+            self.decision,
+            self.llr,
+            self.lower_bound,
+            self.upper_bound,
+            self.elo,
+            self.elo_error,
+            self.record.games(),
+            self.record.wins,
+            self.record.draws,
+            self.record.losses,
+            self.pentanomial
+        )
+    }
+}
+
+/// candidateがbaselineよりelo1以上強い(H1)か，elo0以下(H0)かを逐次確率比検定で判定する．
+/// 先後を入れ替えた2局を1組とし，組ごとの得点からLLRを計算する
+#[derive(Debug, Clone)]
+pub struct Sprt {
+    pub candidate: PolicyConfig,
+    pub baseline: PolicyConfig,
+    pub elo0: f64,
+    pub elo1: f64,
+    /// H0が正しいのにH1を採択する確率
+    pub alpha: f64,
+    /// H1が正しいのにH0を採択する確率
+    pub beta: f64,
+    /// 決着しなくても打ち切る組数
+    pub max_pairs: usize,
+    /// 全体で使うスレッド数の上限
+    pub num_threads: usize,
+}
+
+impl Sprt {
+    pub fn new(candidate: PolicyConfig, baseline: PolicyConfig) -> Self {
+        Sprt {
+            candidate,
+            baseline,
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+            max_pairs: 10_000,
+            num_threads: available_threads(),
+        }
+    }
+
+    pub fn run(&self) -> SprtResult {
+        self.run_with_progress(|_| {})
+    }
+
+    /// 1組対局するごとにprogressを呼びながら検定する．
+    /// 並列に対局した組も対局を始めた順に数え，決着した時点で残りの組は捨てる
+    pub fn run_with_progress(&self, mut progress: impl FnMut(&SprtResult)) -> SprtResult {
+        let threads_per_game = self
+            .candidate
+            .build()
+            .num_threads()
+            .max(self.baseline.build().num_threads());
+        let n_workers = num_workers(self.num_threads, threads_per_game);
+
+        let mut record = Record::default();
+        let mut pentanomial = [0u32; 5];
+        let mut result = self.result(&record, &pentanomial);
+        let mut n_pairs = 0;
+        while n_pairs < self.max_pairs {
+            let batch_size = n_workers.min(self.max_pairs - n_pairs);
+            for pair in run_jobs(batch_size, n_workers, |_| self.play_pair()) {
+                let mut pair_score = 0;
+                for winner in pair {
+                    match winner {
+                        None => {
+                            record.draws += 1;
+                            pair_score += 1;
+                        }
+                        Some(true) => {
+                            record.wins += 1;
+                            pair_score += 2;
+                        }
+                        Some(false) => record.losses += 1,
+                    }
+                }
+                pentanomial[pair_score] += 1;
+                n_pairs += 1;
+
+                result = self.result(&record, &pentanomial);
+                progress(&result);
+                if result.decision != SprtDecision::Inconclusive {
+                    return result;
+                }
+            }
+        }
+        result
+    }
+
+    // 先手と後手で1局ずつ対局し，candidateが勝ったか（引き分けならNone）を返す
+    fn play_pair(&self) -> [Option<bool>; 2] {
+        [true, false].map(|candidate_first| {
+            let (player1, player2) = if candidate_first {
+                (self.candidate.build(), self.baseline.build())
+            } else {
+                (self.baseline.build(), self.candidate.build())
+            };
+            Runner::new(player1, player2)
+                .run()
+                .map(|winner| (winner == Player::Player1) == candidate_first)
+        })
+    }
+
+    fn result(&self, record: &Record, pentanomial: &[u32; 5]) -> SprtResult {
+        let lower_bound = (self.beta / (1.0 - self.alpha)).ln();
+        let upper_bound = ((1.0 - self.beta) / self.alpha).ln();
+        let llr = llr(pentanomial, self.elo0, self.elo1);
+        let decision = if llr >= upper_bound {
+            SprtDecision::Stronger
+        } else if llr <= lower_bound {
+            SprtDecision::NotStronger
+        } else {
+            SprtDecision::Inconclusive
+        };
+        let (elo, elo_error) = elo_estimate(pentanomial);
+        SprtResult {
+            decision,
+            llr,
+            lower_bound,
+            upper_bound,
+            record: *record,
+            pentanomial: *pentanomial,
+            elo,
+            elo_error,
+        }
+    }
+}
+
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+fn score_to_elo(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+// 組ごとの1局あたりの得点の平均と分散と組数
+fn pentanomial_stats(pentanomial: &[u32; 5]) -> (f64, f64, f64) {
+    let counts: Vec<f64> = pentanomial
+        .iter()
+        .map(|&count| count as f64 + REGULARIZATION)
+        .collect();
+    let n: f64 = counts.iter().sum();
+    let mean = counts
+        .iter()
+        .enumerate()
+        .map(|(score, count)| score as f64 / 4.0 * count)
+        .sum::<f64>()
+        / n;
+    let variance = counts
+        .iter()
+        .enumerate()
+        .map(|(score, count)| (score as f64 / 4.0 - mean).powi(2) * count)
+        .sum::<f64>()
+        / n;
+    (mean, variance, n)
+}
+
+/// 五項分布の度数から，H0: elo0 と H1: elo1 の対数尤度比を正規近似で求める
+pub fn llr(pentanomial: &[u32; 5], elo0: f64, elo1: f64) -> f64 {
+    if pentanomial.iter().all(|&count| count == 0) {
+        return 0.0;
+    }
+    let (mean, variance, n) = pentanomial_stats(pentanomial);
+    let score0 = expected_score(elo0);
+    let score1 = expected_score(elo1);
+    n * (score1 - score0) * (2.0 * mean - score0 - score1) / (2.0 * variance)
+}
+
+// 得点率から求めたEloと，その95%信頼区間の半幅
+fn elo_estimate(pentanomial: &[u32; 5]) -> (f64, f64) {
+    if pentanomial.iter().all(|&count| count == 0) {
+        return (0.0, f64::INFINITY);
+    }
+    let (mean, variance, n) = pentanomial_stats(pentanomial);
+    let margin = 1.96 * (variance / n).sqrt();
+    let elo = score_to_elo(mean);
+    let error = (score_to_elo(mean + margin) - score_to_elo(mean - margin)) / 2.0;
+    (elo, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llr_sign() {
+        let even = [10, 20, 40, 20, 10];
+        assert!(llr(&even, 0.0, 10.0) < 0.0, "五分ならH0寄りのはず");
+        let strong = [5, 10, 30, 30, 25];
+        assert!(
+            llr(&strong, 0.0, 10.0) > 0.0,
+            "勝ち越していればH1寄りのはず"
+        );
+        assert!(
+            llr(&[0, 0, 0, 0, 3], 0.0, 10.0).is_finite(),
+            "全勝でも計算できるはず"
+        );
+    }
+
+    #[test]
+    fn test_elo_estimate() {
+        let (elo, error) = elo_estimate(&[10, 20, 40, 20, 10]);
+        assert!(elo.abs() < 1e-6);
+        assert!(error > 0.0);
+        let (elo, _) = elo_estimate(&[0, 0, 0, 10, 10]);
+        assert!(elo > 300.0, "得点率87.5%はおよそ338 Elo: {}", elo);
+    }
+
+    #[test]
+    fn test_sprt_detects_stronger_policy() {
+        let sprt = Sprt {
+            elo1: 100.0,
+            max_pairs: 200,
+            ..Sprt::new(PolicyConfig::OneStepLookAhead, PolicyConfig::Random)
+        };
+        let mut n_reports = 0;
+        let result = sprt.run_with_progress(|_| n_reports += 1);
+        assert_eq!(
+            result.decision,
+            SprtDecision::Stronger,
+            "{}",
+            result.summary()
+        );
+        assert!(result.llr >= result.upper_bound);
+        assert_eq!(result.pairs(), n_reports, "1組ごとに報告するはず");
+        assert_eq!(result.record.games(), 2 * result.pairs());
+    }
+
+    #[test]
+    fn test_sprt_stops_at_max_pairs() {
+        let sprt = Sprt {
+            max_pairs: 2,
+            num_threads: 2,
+            ..Sprt::new(PolicyConfig::Random, PolicyConfig::Random)
+        };
+        let result = sprt.run();
+        assert!(result.pairs() <= 2);
+    }
+}
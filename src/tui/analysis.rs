@@ -0,0 +1,201 @@
+use crate::game::action::Action;
+use crate::game::symmetry::canonical_key;
+use crate::game::Game;
+use crate::policies::alpha_beta_policy::{is_decisive, WIN_SCORE};
+use crate::policies::{AlphaBetaPolicy, Policy};
+use crate::search::SearchLimits;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+/// 候補手1つの評価
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalysisLine {
+    pub action: Action,
+    /// 棋譜の表記
+    pub text: String,
+    /// 手番側から見たscore
+    pub score: i32,
+}
+
+/// 深さごとの解析結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalysisUpdate {
+    /// 候補手を指した後に読んだ深さ
+    pub depth: usize,
+    /// scoreの高い順．対称な局面になる手はまとめて1つにする
+    pub lines: Vec<AnalysisLine>,
+    pub nodes: u64,
+    pub elapsed: f64,
+    /// これ以上深く読んでも結果が変わらない
+    pub finished: bool,
+}
+
+/// 候補手を指した後の局面をdepthまで読み，すべての候補手を評価する．stopされたらNone
+pub fn analyze_depth(
+    policy: &AlphaBetaPolicy,
+    game: &Game,
+    depth: usize,
+    stop: &Arc<AtomicBool>,
+) -> Option<(Vec<AnalysisLine>, u64)> {
+    let limits = SearchLimits {
+        depth: Some(depth),
+        stop: stop.clone(),
+        ..SearchLimits::default()
+    };
+    let mut seen = HashSet::new();
+    let mut lines = vec![];
+    let mut nodes = 0;
+    for action in candidate_actions(game) {
+        let mut next_state = game.clone();
+        next_state
+            .play_turn(action.row, action.col, action.piece_index)
+            .unwrap();
+        if !seen.insert(canonical_key(&next_state).0) {
+            continue;
+        }
+        let score = if next_state.is_game_over() {
+            match next_state.judge_winner() {
+                Some(winner) if winner == game.current_player => WIN_SCORE,
+                Some(_) => -WIN_SCORE,
+                None => 0,
+            }
+        } else {
+            let result =
+                policy.search_with_limits(&next_state, f64::INFINITY, &limits, &mut |_| {});
+            nodes += result.nodes;
+            // 1手戻した分だけ決着までの手数を延ばす
+            let score = -result.score;
+            if is_decisive(score) {
+                score - score.signum()
+            } else {
+                score
+            }
+        };
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        lines.push(AnalysisLine {
+            text: game.format_move(&action),
+            action,
+            score,
+        });
+    }
+    lines.sort_by_key(|line| Reverse(line.score));
+    Some((lines, nodes))
+}
+
+// 置いて勝てるなら駒を渡さない手にする
+fn candidate_actions(game: &Game) -> impl Iterator<Item = Action> + '_ {
+    game.legal_actions().map(|action| {
+        let mut board = game.board;
+        board
+            .place_piece(action.row, action.col, game.selected_piece)
+            .unwrap();
+        if board.check_win() {
+            Action {
+                piece_index: None,
+                ..action
+            }
+        } else {
+            action
+        }
+    })
+}
+
+/// 別スレッドで局面を解析し続ける．dropすると解析を止める
+pub struct Analyzer {
+    stop: Arc<AtomicBool>,
+    receiver: Receiver<AnalysisUpdate>,
+}
+
+impl Analyzer {
+    pub fn start(game: Game) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel();
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            if game.is_game_over() {
+                return;
+            }
+            let policy = AlphaBetaPolicy::new();
+            let start = crate::utils::now();
+            let max_depth = game.placements().len().saturating_sub(1).max(1);
+            let mut nodes = 0;
+            for depth in 1..=max_depth {
+                let Some((lines, depth_nodes)) = analyze_depth(&policy, &game, depth, &thread_stop)
+                else {
+                    return;
+                };
+                nodes += depth_nodes;
+                // 勝ちが見つかるか，すべての手の勝敗が決まったらそれ以上読まない
+                let finished = depth == max_depth
+                    || lines[0].score > 0 && is_decisive(lines[0].score)
+                    || lines.iter().all(|line| is_decisive(line.score));
+                let update = AnalysisUpdate {
+                    depth,
+                    lines,
+                    nodes,
+                    elapsed: crate::utils::now() - start,
+                    finished,
+                };
+                if sender.send(update).is_err() || finished {
+                    return;
+                }
+            }
+        });
+        Analyzer { stop, receiver }
+    }
+
+    /// 届いている中で最新の解析結果
+    pub fn poll(&self) -> Option<AnalysisUpdate> {
+        self.receiver.try_iter().last()
+    }
+}
+
+impl Drop for Analyzer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::alpha_beta_policy::mate_plies;
+    use std::time::Duration;
+
+    #[test]
+    fn test_analyze_depth_finds_win() {
+        let game = Game::from_position_string("012./..../..../.... 3").unwrap();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (lines, _) = analyze_depth(&AlphaBetaPolicy::new(), &game, 1, &stop).unwrap();
+        assert_eq!(lines[0].text, "d1");
+        assert_eq!(mate_plies(lines[0].score), Some(1));
+        // 対称な局面になる手はまとめる
+        assert!(lines.len() < game.available_actions().len());
+        assert!(lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    }
+
+    #[test]
+    fn test_analyzer_stops_when_finished() {
+        let game = Game::from_position_string("012./..../..../.... 3").unwrap();
+        let analyzer = Analyzer::start(game);
+        let mut update = None;
+        for _ in 0..200 {
+            if let Some(latest) = analyzer.poll() {
+                update = Some(latest);
+            }
+            if update.as_ref().is_some_and(|update| update.finished) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let update = update.unwrap();
+        assert!(update.finished, "勝ちが見つかっても読み続けないはず");
+        assert_eq!(update.lines[0].text, "d1");
+    }
+}
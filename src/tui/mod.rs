@@ -0,0 +1,428 @@
+pub mod analysis;
+pub mod ui;
+
+pub use analysis::{AnalysisLine, AnalysisUpdate, Analyzer};
+
+use crate::cli::GameRecord;
+use crate::game::action::Action;
+use crate::game::Game;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+/// キー入力を受け付けている欄
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Board,
+    Pieces,
+    Moves,
+}
+
+/// TUIの状態．棋譜の途中の局面を表示しているときに手を指すと，それ以降の手は捨てる
+pub struct App {
+    // 開始局面と各手の後の局面
+    history: Vec<Game>,
+    moves: Vec<String>,
+    // 表示しているhistoryのインデックス
+    current: usize,
+    cursor: (usize, usize),
+    piece_cursor: usize,
+    // 駒を置くセルを選び，渡す駒を選んでいる途中
+    pending_cell: Option<(usize, usize)>,
+    focus: Focus,
+    message: String,
+    save_path: Option<String>,
+    analysis_enabled: bool,
+    analyzer: Option<Analyzer>,
+    analysis: Option<AnalysisUpdate>,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(start: Game) -> Self {
+        App {
+            history: vec![start],
+            moves: vec![],
+            current: 0,
+            cursor: (0, 0),
+            piece_cursor: 0,
+            pending_cell: None,
+            focus: Focus::Board,
+            message: String::new(),
+            save_path: None,
+            analysis_enabled: false,
+            analyzer: None,
+            analysis: None,
+            quit: false,
+        }
+    }
+
+    /// 保存した棋譜を読み込み，最後の局面を表示する
+    pub fn from_record(record: &GameRecord) -> Result<Self, String> {
+        let history = record.replay()?;
+        let mut app = App::new(history[0].clone());
+        app.current = history.len() - 1;
+        app.history = history;
+        app.moves = record.moves.clone();
+        Ok(app)
+    }
+
+    /// sキーで棋譜を保存するファイル
+    pub fn with_save_path(self, save_path: &str) -> Self {
+        App {
+            save_path: Some(save_path.to_string()),
+            ..self
+        }
+    }
+
+    /// 表示している局面の解析を始める（止める）
+    pub fn set_analysis(&mut self, enabled: bool) {
+        self.analysis_enabled = enabled;
+        self.restart_analysis();
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.history[self.current]
+    }
+
+    pub fn moves(&self) -> &[String] {
+        &self.moves
+    }
+
+    /// 表示している局面までに指した手の数
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    pub fn piece_cursor(&self) -> usize {
+        self.piece_cursor
+    }
+
+    pub fn pending_cell(&self) -> Option<(usize, usize)> {
+        self.pending_cell
+    }
+
+    pub fn focus(&self) -> Focus {
+        self.focus
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn analysis_enabled(&self) -> bool {
+        self.analysis_enabled
+    }
+
+    pub fn analysis(&self) -> Option<&AnalysisUpdate> {
+        self.analysis.as_ref()
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn record(&self) -> GameRecord {
+        GameRecord {
+            start: self.history[0].to_position_string(),
+            moves: self.moves.clone(),
+        }
+    }
+
+    /// 表示している局面に至る直前の手
+    pub fn last_action(&self) -> Option<Action> {
+        let text = self.moves.get(self.current.checked_sub(1)?)?;
+        self.history[self.current - 1].parse_move(text).ok()
+    }
+
+    /// 解析スレッドから届いた結果を取り込む
+    pub fn poll_analysis(&mut self) {
+        if let Some(update) = self.analyzer.as_ref().and_then(|analyzer| analyzer.poll()) {
+            self.analysis = Some(update);
+        }
+    }
+
+    fn restart_analysis(&mut self) {
+        self.analysis = None;
+        self.analyzer = if self.analysis_enabled {
+            Some(Analyzer::start(self.game().clone()))
+        } else {
+            None
+        };
+    }
+
+    fn go_to(&mut self, index: usize) {
+        let index = index.min(self.history.len() - 1);
+        if index != self.current {
+            self.current = index;
+            self.pending_cell = None;
+            self.piece_cursor = 0;
+            self.restart_analysis();
+        }
+    }
+
+    /// 表示している局面で手を指す．それより後の手は捨てる
+    pub fn play(&mut self, action: &Action) -> Result<(), String> {
+        let mut game = self.game().clone();
+        let text = game.format_move(action);
+        game.play_turn(action.row, action.col, action.piece_index)?;
+        self.history.truncate(self.current + 1);
+        self.moves.truncate(self.current);
+        self.history.push(game);
+        self.moves.push(text);
+        self.current += 1;
+        self.pending_cell = None;
+        self.piece_cursor = 0;
+        self.focus = Focus::Board;
+        self.restart_analysis();
+        Ok(())
+    }
+
+    /// 最後の手を取り消す
+    pub fn undo(&mut self) {
+        if self.moves.is_empty() {
+            self.message = "No moves to undo".to_string();
+            return;
+        }
+        self.moves.pop();
+        self.history.pop();
+        self.current = self.current.min(self.history.len() - 1);
+        self.pending_cell = None;
+        self.restart_analysis();
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        self.message.clear();
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc if self.pending_cell.is_some() => {
+                self.pending_cell = None;
+                self.focus = Focus::Board;
+            }
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Board => Focus::Pieces,
+                    Focus::Pieces => Focus::Moves,
+                    Focus::Moves => Focus::Board,
+                }
+            }
+            KeyCode::Char('[') | KeyCode::PageUp => self.go_to(self.current.saturating_sub(1)),
+            KeyCode::Char(']') | KeyCode::PageDown => self.go_to(self.current + 1),
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('a') => self.set_analysis(!self.analysis_enabled),
+            KeyCode::Char('m') => self.play_best(),
+            KeyCode::Char('s') => self.save(),
+            _ => match self.focus {
+                Focus::Board => self.handle_board_key(key.code),
+                Focus::Pieces => self.handle_pieces_key(key.code),
+                Focus::Moves => self.handle_moves_key(key.code),
+            },
+        }
+    }
+
+    fn handle_board_key(&mut self, code: KeyCode) {
+        let (row, col) = self.cursor;
+        match code {
+            KeyCode::Up => self.cursor = (row.saturating_sub(1), col),
+            KeyCode::Down => self.cursor = ((row + 1).min(3), col),
+            KeyCode::Left => self.cursor = (row, col.saturating_sub(1)),
+            KeyCode::Right => self.cursor = (row, (col + 1).min(3)),
+            KeyCode::Enter | KeyCode::Char(' ') => self.select_cell(row, col),
+            _ => {}
+        }
+    }
+
+    fn select_cell(&mut self, row: usize, col: usize) {
+        let game = self.game();
+        if game.is_game_over() {
+            self.message = "Game is over".to_string();
+            return;
+        }
+        if game.board.piece_at(row, col).is_some() {
+            self.message = "Cell is already occupied".to_string();
+            return;
+        }
+        let mut board = game.board;
+        board.place_piece(row, col, game.selected_piece).unwrap();
+        // 置いて勝てるとき，渡す駒が無いときはそのまま指す
+        if board.check_win() || game.available_pieces.is_empty() {
+            let action = Action {
+                row,
+                col,
+                piece_index: None,
+            };
+            if let Err(error) = self.play(&action) {
+                self.message = error;
+            }
+            return;
+        }
+        self.pending_cell = Some((row, col));
+        self.focus = Focus::Pieces;
+        self.message = "Choose a piece to give".to_string();
+    }
+
+    fn handle_pieces_key(&mut self, code: KeyCode) {
+        let n_pieces = self.game().available_pieces.len();
+        if n_pieces == 0 {
+            return;
+        }
+        match code {
+            KeyCode::Up => self.piece_cursor = self.piece_cursor.saturating_sub(1),
+            KeyCode::Down => self.piece_cursor = (self.piece_cursor + 1).min(n_pieces - 1),
+            KeyCode::Enter | KeyCode::Char(' ') => match self.pending_cell {
+                Some((row, col)) => {
+                    let action = Action {
+                        row,
+                        col,
+                        piece_index: Some(self.piece_cursor),
+                    };
+                    if let Err(error) = self.play(&action) {
+                        self.message = error;
+                    }
+                }
+                None => self.message = "Choose a cell first".to_string(),
+            },
+            _ => {}
+        }
+    }
+
+    fn handle_moves_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Up => self.go_to(self.current.saturating_sub(1)),
+            KeyCode::Down => self.go_to(self.current + 1),
+            KeyCode::Home => self.go_to(0),
+            KeyCode::End => self.go_to(self.history.len() - 1),
+            _ => {}
+        }
+    }
+
+    /// 解析で最善だった手を指す
+    fn play_best(&mut self) {
+        let Some(line) = self
+            .analysis
+            .as_ref()
+            .and_then(|update| update.lines.first())
+        else {
+            self.message = "No analysis yet (press a to start)".to_string();
+            return;
+        };
+        let action = line.action;
+        if let Err(error) = self.play(&action) {
+            self.message = error;
+        }
+    }
+
+    fn save(&mut self) {
+        let Some(path) = self.save_path.clone() else {
+            self.message = "No file to save to (start with --save <path>)".to_string();
+            return;
+        };
+        self.message = match self.record().save(&path) {
+            Ok(()) => format!("Saved to {}", path),
+            Err(error) => error,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    fn press(app: &mut App, codes: &[KeyCode]) {
+        for &code in codes {
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+    }
+
+    fn start() -> Game {
+        Game::from_position_string("..../..../..../.... 0").unwrap()
+    }
+
+    #[test]
+    fn test_play_with_cursor() {
+        let mut app = App::new(start());
+        press(
+            &mut app,
+            &[KeyCode::Right, KeyCode::Down, KeyCode::Enter, KeyCode::Down],
+        );
+        assert_eq!(app.pending_cell(), Some((1, 1)));
+        assert_eq!(app.focus(), Focus::Pieces);
+        press(&mut app, &[KeyCode::Enter]);
+        assert_eq!(app.moves(), ["b2:2"]);
+        assert_eq!(app.focus(), Focus::Board);
+
+        // 駒があるセルは選べない
+        press(&mut app, &[KeyCode::Enter]);
+        assert_eq!(app.pending_cell(), None);
+        assert_eq!(app.message(), "Cell is already occupied");
+    }
+
+    #[test]
+    fn test_winning_cell_plays_without_giving_piece() {
+        let mut app = App::new(Game::from_position_string("012./..../..../.... 3").unwrap());
+        press(
+            &mut app,
+            &[
+                KeyCode::Right,
+                KeyCode::Right,
+                KeyCode::Right,
+                KeyCode::Enter,
+            ],
+        );
+        assert_eq!(app.moves(), ["d1"]);
+        assert!(app.game().is_game_over());
+    }
+
+    #[test]
+    fn test_navigate_and_branch() {
+        let record = GameRecord {
+            start: "..../..../..../.... 0".to_string(),
+            moves: vec!["a1:1".to_string(), "b1:2".to_string(), "c1:3".to_string()],
+        };
+        let mut app = App::from_record(&record).unwrap();
+        assert_eq!(app.current(), 3);
+        assert_eq!(app.last_action().unwrap().col, 2);
+
+        press(&mut app, &[KeyCode::Char('['), KeyCode::Char('[')]);
+        assert_eq!(app.current(), 1);
+        assert_eq!(app.game().available_pieces.len(), 14);
+        press(&mut app, &[KeyCode::Tab, KeyCode::Tab, KeyCode::End]);
+        assert_eq!(app.current(), 3);
+        press(&mut app, &[KeyCode::Home]);
+        assert_eq!(app.current(), 0);
+        press(&mut app, &[KeyCode::Down]);
+        assert_eq!(app.current(), 1);
+
+        // 途中の局面で指すと，それより後の手は捨てる
+        let action = app.game().parse_move("d4:5").unwrap();
+        app.play(&action).unwrap();
+        assert_eq!(app.moves(), ["a1:1", "d4:5"]);
+        assert_eq!(app.record().replay().unwrap().len(), 3);
+
+        app.undo();
+        assert_eq!(app.moves(), ["a1:1"]);
+        assert_eq!(app.current(), 1);
+    }
+
+    #[test]
+    fn test_play_best_analysis_move() {
+        let mut app = App::new(Game::from_position_string("012./..../..../.... 3").unwrap());
+        press(&mut app, &[KeyCode::Char('m')]);
+        assert!(app.moves().is_empty());
+
+        app.set_analysis(true);
+        for _ in 0..200 {
+            app.poll_analysis();
+            if app.analysis().is_some() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        press(&mut app, &[KeyCode::Char('m')]);
+        assert_eq!(app.moves(), ["d1"]);
+    }
+}
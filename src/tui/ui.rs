@@ -0,0 +1,221 @@
+use super::{App, Focus};
+use crate::cli::piece_name;
+use crate::game::{Piece, Player};
+use crate::policies::alpha_beta_policy::mate_plies;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+const KEYS: &str = "arrows: move  enter: select  tab: focus  [ ]: back/forward  \
+u: undo  a: analysis  m: best move  s: save  q: quit";
+
+// 解析の欄に表示する候補手の数
+const MAX_LINES: usize = 10;
+
+fn piece_label(piece: Piece) -> String {
+    format!("{:x}:{}", piece.bits(), piece_name(piece))
+}
+
+fn player_name(player: Player) -> &'static str {
+    match player {
+        Player::Player1 => "Player 1",
+        Player::Player2 => "Player 2",
+    }
+}
+
+/// scoreを表示用の文字列にする．勝ち負けが決まっていれば決着までの手数
+pub fn format_score(score: i32) -> String {
+    match mate_plies(score) {
+        Some(plies) if plies > 0 => format!("win in {}", plies),
+        Some(plies) => format!("loss in {}", -plies),
+        None => format!("{:+}", score),
+    }
+}
+
+fn block(title: &str, focused: bool) -> Block<'_> {
+    let block = Block::bordered().title(title);
+    if focused {
+        block.border_style(Style::default().fg(Color::Yellow))
+    } else {
+        block
+    }
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+    let [board, pieces, side] = Layout::horizontal([
+        Constraint::Length(40),
+        Constraint::Length(14),
+        Constraint::Min(0),
+    ])
+    .areas(main);
+    let [moves, analysis] =
+        Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(side);
+
+    draw_board(frame, app, board);
+    draw_pieces(frame, app, pieces);
+    draw_moves(frame, app, moves);
+    draw_analysis(frame, app, analysis);
+
+    let message = if app.message().is_empty() {
+        KEYS
+    } else {
+        app.message()
+    };
+    frame.render_widget(Paragraph::new(message).block(Block::bordered()), status);
+}
+
+fn draw_board(frame: &mut Frame, app: &App, area: Rect) {
+    let game = app.game();
+    let last_cell = app.last_action().map(|action| (action.row, action.col));
+    let mut lines = vec![
+        Line::from("      a       b       c       d"),
+        Line::from(""),
+    ];
+    for row in 0..4 {
+        let mut spans = vec![Span::raw(format!("{} ", row + 1))];
+        for col in 0..4 {
+            let text = match game.board.piece_at(row, col) {
+                Some(piece) => format!(" {} ", piece_label(piece)),
+                None => "   .    ".to_string(),
+            };
+            let mut style = Style::default();
+            if last_cell == Some((row, col)) {
+                style = style.add_modifier(Modifier::BOLD).fg(Color::Cyan);
+            }
+            if app.pending_cell() == Some((row, col)) {
+                style = style.bg(Color::Blue);
+            }
+            if app.focus() == Focus::Board && app.cursor() == (row, col) {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            spans.push(Span::styled(text, style));
+        }
+        lines.push(Line::from(spans));
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(format!(
+        "Piece to place: {}",
+        piece_label(game.selected_piece)
+    )));
+    let state = match game.judge_winner() {
+        Some(winner) => format!("{} wins", player_name(winner)),
+        None if game.is_game_over() => "Draw".to_string(),
+        None => format!("{} to move", player_name(game.current_player)),
+    };
+    lines.push(Line::from(state));
+    frame.render_widget(
+        Paragraph::new(lines).block(block("Board", app.focus() == Focus::Board)),
+        area,
+    );
+}
+
+fn draw_pieces(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .game()
+        .available_pieces
+        .iter()
+        .map(|piece| ListItem::new(piece_label(*piece)))
+        .collect();
+    let focused = app.focus() == Focus::Pieces;
+    let list = List::new(items)
+        .block(block("Pieces", focused))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default();
+    if focused {
+        state.select(Some(app.piece_cursor()));
+    }
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_moves(frame: &mut Frame, app: &App, area: Rect) {
+    let mut items = vec![ListItem::new("   start")];
+    items.extend(
+        app.moves()
+            .iter()
+            .enumerate()
+            .map(|(i, text)| ListItem::new(format!("{:>3}. {}", i + 1, text))),
+    );
+    let list = List::new(items)
+        .block(block("Moves", app.focus() == Focus::Moves))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default();
+    state.select(Some(app.current()));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_analysis(frame: &mut Frame, app: &App, area: Rect) {
+    let mut lines = vec![];
+    match (app.analysis_enabled(), app.analysis()) {
+        (false, _) => lines.push(Line::from("Press a to start the analysis")),
+        (true, None) if app.game().is_game_over() => lines.push(Line::from("Game is over")),
+        (true, None) => lines.push(Line::from("Thinking...")),
+        (true, Some(update)) => {
+            lines.push(Line::from(format!(
+                "depth {}{}  nodes {}  {:.1}s",
+                update.depth + 1,
+                if update.finished { " (done)" } else { "" },
+                update.nodes,
+                update.elapsed
+            )));
+            for line in update.lines.iter().take(MAX_LINES) {
+                lines.push(Line::from(format!(
+                    "{:<6} {:>12}",
+                    line.text,
+                    format_score(line.score)
+                )));
+            }
+        }
+    }
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title("Analysis")),
+        area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::policies::alpha_beta_policy::WIN_SCORE;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn render(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_format_score() {
+        assert_eq!(format_score(12), "+12");
+        assert_eq!(format_score(WIN_SCORE), "win in 1");
+        assert_eq!(format_score(-(WIN_SCORE - 1)), "loss in 2");
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut app = App::new(Game::from_position_string("0a3./..../..../.... 5").unwrap());
+        let screen = render(&app);
+        assert!(screen.contains("a:BQTF"), "置かれている駒を表示するはず");
+        assert!(screen.contains("Piece to place: 5:WRSH"));
+        assert!(screen.contains("Player 2 to move"));
+        assert!(screen.contains("Press a to start the analysis"));
+
+        let action = app.game().parse_move("d4:1").unwrap();
+        app.play(&action).unwrap();
+        let screen = render(&app);
+        assert!(screen.contains("1. d4:1"));
+        assert!(screen.contains("Player 1 to move"));
+    }
+}
@@ -1,7 +1,39 @@
+use rand::rngs::{OsRng, StdRng};
+use rand::{RngCore, SeedableRng};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// 現在の時刻（秒）を返す関数．差だけを使うので基準はどこでもよい
+pub type TimeSource = fn() -> f64;
+
+static TIME_SOURCE: OnceLock<TimeSource> = OnceLock::new();
+
+/// TimeKeeperが使う時計を差し替える．
+/// wasm32-unknown-unknownではstd::time::Instantが使えないので，最初に1度だけ呼ぶ
+pub fn set_time_source(time_source: TimeSource) -> Result<(), String> {
+    TIME_SOURCE
+        .set(time_source)
+        .map_err(|_| "Time source is already set".to_string())
+}
+
+fn instant_time_source() -> f64 {
+    static EPOCH: OnceLock<std::time::Instant> = OnceLock::new();
+    EPOCH
+        .get_or_init(std::time::Instant::now)
+        .elapsed()
+        .as_secs_f64()
+}
+
+/// 現在の時刻（秒）
+pub fn now() -> f64 {
+    TIME_SOURCE.get_or_init(|| instant_time_source)()
+}
+
 // ref: https://zenn.dev/tipstar0125/articles/245bceec86e40a#time-keeper
 #[derive(Debug, Clone)]
 pub struct TimeKeeper {
-    start_time: std::time::Instant,
+    start_time: f64,
     time_threshold: f64,
     count: u64,
 }
@@ -9,7 +41,7 @@ pub struct TimeKeeper {
 impl TimeKeeper {
     pub fn new(time_threshold: f64) -> Self {
         TimeKeeper {
-            start_time: std::time::Instant::now(),
+            start_time: now(),
             time_threshold,
             count: 0,
         }
@@ -17,7 +49,7 @@ impl TimeKeeper {
     #[inline]
     pub fn is_time_over(&mut self) -> bool {
         self.count += 1;
-        let elapsed_time = self.start_time.elapsed().as_nanos() as f64 * 1e-9;
+        let elapsed_time = now() - self.start_time;
         elapsed_time >= self.time_threshold
     }
 
@@ -25,3 +57,83 @@ impl TimeKeeper {
         self.count
     }
 }
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(initial_rng());
+}
+
+// OSのエントロピーで初期化する．エントロピーが取れない環境ではpanicせずに時刻から作る
+fn initial_rng() -> StdRng {
+    StdRng::from_rng(OsRng).unwrap_or_else(|_| time_seeded_rng())
+}
+
+// 時刻と連番から作る．同じ時刻に初期化したスレッドでも別の系列になる
+fn time_seeded_rng() -> StdRng {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    StdRng::seed_from_u64(now().to_bits() ^ count.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// policyなどが使う乱数生成器．スレッドごとに1つあり，seed_rngで再現できるようにする．
+/// seed_rngを呼ばなければ最初に使ったときにOSのエントロピー（取れなければ時刻）で初期化する
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EngineRng;
+
+/// thread_rngの代わりに使う
+pub fn rng() -> EngineRng {
+    EngineRng
+}
+
+/// このスレッドの乱数生成器をseedで初期化する
+pub fn seed_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+impl RngCore for EngineRng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.with(|rng| rng.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.with(|rng| rng.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        RNG.with(|rng| rng.borrow_mut().try_fill_bytes(dest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_time_keeper() {
+        let mut time_keeper = TimeKeeper::new(0.01);
+        assert!(!time_keeper.is_time_over());
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(time_keeper.is_time_over());
+        assert_eq!(time_keeper.get_count(), 2);
+    }
+
+    #[test]
+    fn test_seed_rng() {
+        seed_rng(42);
+        let first: Vec<u32> = (0..8).map(|_| rng().gen()).collect();
+        seed_rng(42);
+        let second: Vec<u32> = (0..8).map(|_| rng().gen()).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_time_seeded_rng() {
+        let first = time_seeded_rng().next_u64();
+        let second = time_seeded_rng().next_u64();
+        assert_ne!(first, second, "続けて作っても別の系列になる");
+    }
+}
@@ -0,0 +1,141 @@
+use quart_engine::clock::{GameClock, TimeControl};
+use quart_engine::game::{Game, Player};
+use quart_engine::policies::{ExternalEnginePolicy, Policy, PolicyConfig, RandomPolicy};
+use quart_engine::runner::{EndReason, ForfeitReason, Runner};
+use quart_engine::search::SearchLimits;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+fn quart_engine() -> ExternalEnginePolicy {
+    let mut policy = ExternalEnginePolicy::with_command(env!("CARGO_BIN_EXE_quart-engine"), &[])
+        .with_option("Policy", "one-step");
+    policy.move_time = 0.02;
+    policy
+}
+
+// ハンドシェイクには答えるが，goの後はscriptのとおりに振る舞うエンジン
+fn script_engine(script: &str) -> ExternalEnginePolicy {
+    let script = format!(
+        "read line; echo qeiok; read line; echo readyok; read line; read line; {}",
+        script
+    );
+    let mut policy = ExternalEnginePolicy::with_command("sh", &["-c", &script]);
+    policy.move_time = 0.02;
+    policy.timeout_margin = 0.2;
+    policy
+}
+
+#[test]
+fn test_external_engine_plays_full_game() {
+    let mut runner = Runner::new(Box::new(quart_engine()), Box::new(RandomPolicy::new()));
+    let result = runner.run_game();
+    assert!(
+        matches!(result.reason, EndReason::Quarto | EndReason::Draw),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_external_engine_with_clock() {
+    let mut runner = Runner::new(Box::new(RandomPolicy::new()), Box::new(quart_engine()));
+    runner.clock = Some(GameClock::new(TimeControl::Fischer {
+        initial: 2.0,
+        increment: 0.1,
+    }));
+    let result = runner.run_game();
+    assert!(
+        matches!(result.reason, EndReason::Quarto | EndReason::Draw),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_external_engine_from_config() {
+    let config = PolicyConfig::from_json(&format!(
+        r#"{{"type": "external", "command": {:?}, "options": {{"Policy": "random"}}}}"#,
+        env!("CARGO_BIN_EXE_quart-engine")
+    ))
+    .unwrap();
+    let mut runner = Runner::new(config.build(), Box::new(RandomPolicy::new()));
+    let result = runner.run_game();
+    assert!(!matches!(result.reason, EndReason::Forfeit { .. }));
+}
+
+#[test]
+fn test_external_engine_crash_is_forfeit() {
+    let mut runner = Runner::new(
+        Box::new(script_engine("exit 1")),
+        Box::new(RandomPolicy::new()),
+    );
+    let result = runner.run_game();
+    assert_eq!(result.winner, Some(Player::Player2));
+    assert!(
+        matches!(
+            result.reason,
+            EndReason::Forfeit {
+                player: Player::Player1,
+                reason: ForfeitReason::Panic(_)
+            }
+        ),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_external_engine_hang_is_forfeit() {
+    let mut runner = Runner::new(
+        Box::new(script_engine("sleep 10")),
+        Box::new(RandomPolicy::new()),
+    );
+    let result = runner.run_game();
+    assert!(
+        matches!(
+            result.reason,
+            EndReason::Forfeit {
+                player: Player::Player1,
+                reason: ForfeitReason::Panic(_)
+            }
+        ),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_external_engine_hang_without_time_limit_panics() {
+    // 時間の指定が無くても，move_timeを過ぎて応答しなければ待ち続けない
+    let policy = script_engine("sleep 10");
+    let limits = SearchLimits {
+        depth: Some(3),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        policy.action_with_limits(&Game::new(), &limits, &mut |_| {})
+    }));
+    assert!(result.is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_external_engine_invalid_bestmove_is_forfeit() {
+    let mut runner = Runner::new(
+        Box::new(script_engine("echo bestmove z9")),
+        Box::new(RandomPolicy::new()),
+    );
+    let result = runner.run_game();
+    assert!(
+        matches!(
+            result.reason,
+            EndReason::Forfeit {
+                player: Player::Player1,
+                ..
+            }
+        ),
+        "{:?}",
+        result
+    );
+}
@@ -1,6 +1,11 @@
+use quart_engine::evaluators::HandcraftedEvaluator;
 use quart_engine::game::Player;
-use quart_engine::policies::{MCSPolicy, OneStepLookAheadPolicy, Policy, RandomPolicy};
+use quart_engine::policies::{
+    AlphaBetaPolicy, MCSPolicy, OneStepLookAheadPolicy, Policy, RandomPolicy,
+    TwoStepLookAheadPolicy,
+};
 use quart_engine::runner::Runner;
+use quart_engine::tournament::Record;
 use tqdm::tqdm;
 
 const NUM_TRIALS: u64 = 10;
@@ -11,19 +16,26 @@ where
     P1: Policy + Clone + 'static,
     P2: Policy + Clone + 'static,
 {
-    let mut win_count = 0;
+    let mut record = Record::default();
     for _ in tqdm(0..NUM_TRIALS) {
         let mut runner = Runner::new(
             Box::new(player1_policy.clone()),
             Box::new(player2_policy.clone()),
         );
-        let winner = runner.run();
-        if let Some(Player::Player1) = winner {
-            win_count += 1;
+        match runner.run() {
+            Some(Player::Player1) => record.wins += 1,
+            Some(Player::Player2) => record.losses += 1,
+            None => record.draws += 1,
         }
     }
-    let win_rate = win_count as f64 / NUM_TRIALS as f64;
-    println!("{} Win rate: {}", description, win_rate);
+    println!(
+        "{} W/D/L: {}/{}/{} (score: {})",
+        description,
+        record.wins,
+        record.draws,
+        record.losses,
+        record.score() / NUM_TRIALS as f64
+    );
 }
 
 #[test]
@@ -57,10 +69,31 @@ fn test_one_step_look_ahead_vs_one_step_look_ahead_policy() {
 fn test_mcs_policy_vs_one_step_look_ahead_policy() {
     test_policy_vs_policy(
         MCSPolicy {
-            policy: OneStepLookAheadPolicy::new(),
             max_time: 0.001,
+            ..MCSPolicy::with_policy(OneStepLookAheadPolicy::new())
         },
         OneStepLookAheadPolicy::new(),
         "MCS vs One Step Look Ahead Policy",
     );
 }
+
+#[test]
+fn test_alpha_beta_policy_vs_one_step_look_ahead_policy() {
+    test_policy_vs_policy(
+        AlphaBetaPolicy {
+            max_time: 0.001,
+            ..AlphaBetaPolicy::with_evaluator(HandcraftedEvaluator::default())
+        },
+        OneStepLookAheadPolicy::new(),
+        "Alpha Beta vs One Step Look Ahead Policy",
+    );
+}
+
+#[test]
+fn test_two_step_look_ahead_policy_vs_one_step_look_ahead_policy() {
+    test_policy_vs_policy(
+        TwoStepLookAheadPolicy::new(),
+        OneStepLookAheadPolicy::new(),
+        "Two Step Look Ahead vs One Step Look Ahead Policy",
+    );
+}
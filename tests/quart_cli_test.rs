@@ -0,0 +1,65 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run_quart(args: &[&str], input: &str) -> (bool, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_quart"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).to_string()
+            + &String::from_utf8_lossy(&output.stderr),
+    )
+}
+
+#[test]
+fn test_quart_play_human_vs_human() {
+    let path = std::env::temp_dir().join("quart_cli_test_game.json");
+    let path = path.to_str().unwrap();
+    let input = format!("a1:1\nb1:2\nc1:3\nd1:4\nsave {}\nquit\n", path);
+    let (success, output) = run_quart(
+        &[
+            "play",
+            "--opponent",
+            "human",
+            "--position",
+            "..../..../..../.... 0",
+        ],
+        &input,
+    );
+    assert!(success, "{}", output);
+    assert!(output.contains("Quarto! Player2 wins"), "{}", output);
+
+    let saved = std::fs::read_to_string(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(saved.contains("d1:4"), "{}", saved);
+}
+
+#[test]
+fn test_quart_play_against_engine() {
+    let (success, output) = run_quart(
+        &["play", "--opponent", "random", "--engine-first"],
+        "board\nquit\n",
+    );
+    assert!(success, "{}", output);
+    assert!(output.contains("Engine plays"), "{}", output);
+    assert!(output.contains("Player2 (you)> "), "{}", output);
+}
+
+#[test]
+fn test_quart_rejects_unknown_option() {
+    let (success, output) = run_quart(&["play", "--opponent", "unknown"], "");
+    assert!(!success);
+    assert!(output.contains("Unknown opponent"), "{}", output);
+}
@@ -0,0 +1,43 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_quart_engine_protocol() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_quart-engine"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut read_until = |expected: &str| -> Vec<String> {
+        let mut lines = vec![];
+        loop {
+            let mut line = String::new();
+            assert!(stdout.read_line(&mut line).unwrap() > 0, "{:?}", lines);
+            let line = line.trim_end().to_string();
+            let done = line.starts_with(expected);
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+    };
+
+    writeln!(stdin, "qei").unwrap();
+    let lines = read_until("qeiok");
+    assert!(lines[0].starts_with("id name quart-engine"));
+
+    writeln!(stdin, "setoption name Policy value mcs").unwrap();
+    writeln!(stdin, "isready").unwrap();
+    assert_eq!(read_until("readyok"), vec!["readyok"]);
+
+    writeln!(stdin, "position startpos moves a1:1 b2:2").unwrap();
+    writeln!(stdin, "go wtime 1000 btime 1000 winc 10 binc 10").unwrap();
+    let lines = read_until("bestmove");
+    let bestmove = lines.last().unwrap();
+    assert_eq!(bestmove.len(), "bestmove c3:a".len(), "{:?}", lines);
+
+    writeln!(stdin, "quit").unwrap();
+    assert!(child.wait().unwrap().success());
+}
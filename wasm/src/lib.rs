@@ -0,0 +1,215 @@
+//! quart-engineのWebAssemblyバインディング
+//!
+//! ブラウザでルールの判定と弱いAIを動かすためのもの．手は棋譜の表記（例: "c3:a"）でやり取りする．
+//! wasm32-unknown-unknownではstd::time::Instantが使えないので，時計はJavaScriptのDate.now()を使う
+use quart_engine::game::player::Player;
+use quart_engine::game::ActionList;
+use quart_engine::policies::{
+    MCSPolicy, OneStepLookAheadPolicy, Policy as EnginePolicy, RandomPolicy,
+};
+use quart_engine::utils;
+use wasm_bindgen::prelude::*;
+
+fn js_error(error: impl ToString) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+fn date_now() -> f64 {
+    js_sys::Date::now() * 1e-3
+}
+
+/// 時計をJavaScriptのものに差し替える．2回目以降は何もしない
+fn install_time_source() {
+    let _ = utils::set_time_source(date_now);
+}
+
+fn player_number(player: Player) -> u8 {
+    match player {
+        Player::Player1 => 1,
+        Player::Player2 => 2,
+    }
+}
+
+/// 乱数のseedを固定する．同じseedなら同じ局面で同じ手を選ぶ（MCSは回数で打ち切るとき）
+#[wasm_bindgen(js_name = seedRng)]
+pub fn seed_rng(seed: u32) {
+    utils::seed_rng(seed as u64);
+}
+
+/// 局面
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Game {
+    game: quart_engine::game::Game,
+}
+
+#[wasm_bindgen]
+impl Game {
+    /// 局面の文字列から作る．省略した場合は駒をランダムに1つ渡された初期局面
+    #[wasm_bindgen(constructor)]
+    pub fn new(position: Option<String>) -> Result<Game, JsError> {
+        let game = match position {
+            Some(position) => {
+                quart_engine::game::Game::from_position_string(&position).map_err(js_error)?
+            }
+            None => quart_engine::game::Game::new(),
+        };
+        Ok(Game { game })
+    }
+
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<Game, JsError> {
+        let game = serde_json::from_str(json).map_err(js_error)?;
+        Ok(Game { game })
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        self.game.to_json()
+    }
+
+    #[wasm_bindgen(js_name = toPosition)]
+    pub fn to_position(&self) -> String {
+        self.game.to_position_string()
+    }
+
+    /// 盤面の16マス（上の行から）．駒の4ビットの値で，空なら-1
+    pub fn cells(&self) -> Vec<i8> {
+        (0..16)
+            .map(|cell| match self.game.board.piece_at(cell / 4, cell % 4) {
+                Some(piece) => piece.bits() as i8,
+                None => -1,
+            })
+            .collect()
+    }
+
+    /// 渡されている（これから置く）駒
+    #[wasm_bindgen(js_name = selectedPiece)]
+    pub fn selected_piece(&self) -> u8 {
+        self.game.selected_piece.bits()
+    }
+
+    /// 相手に渡せる駒
+    #[wasm_bindgen(js_name = availablePieces)]
+    pub fn available_pieces(&self) -> Vec<u8> {
+        self.game
+            .available_pieces
+            .iter()
+            .map(|piece| piece.bits())
+            .collect()
+    }
+
+    /// 手番のプレイヤー（1または2）
+    #[wasm_bindgen(js_name = currentPlayer)]
+    pub fn current_player(&self) -> u8 {
+        player_number(self.game.current_player)
+    }
+
+    /// 合法手をすべて棋譜の表記で返す
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        legal_actions(&self.game)
+            .iter()
+            .map(|action| self.game.format_move(action))
+            .collect()
+    }
+
+    /// 棋譜の表記で手を指す．不正な手なら例外を投げ，局面は変わらない
+    pub fn play(&mut self, text: &str) -> Result<(), JsError> {
+        let action = self.game.parse_move(text).map_err(js_error)?;
+        self.game
+            .play_turn(action.row, action.col, action.piece_index)
+            .map_err(js_error)
+    }
+
+    /// 棋譜の表記の手が合法かどうか
+    #[wasm_bindgen(js_name = isLegal)]
+    pub fn is_legal(&self, text: &str) -> bool {
+        self.game
+            .parse_move(text)
+            .is_ok_and(|action| self.game.validate_action(&action).is_ok())
+    }
+
+    /// 盤面に4つ揃った列があるか
+    #[wasm_bindgen(js_name = checkWin)]
+    pub fn check_win(&self) -> bool {
+        self.game.board.check_win()
+    }
+
+    #[wasm_bindgen(js_name = isGameOver)]
+    pub fn is_game_over(&self) -> bool {
+        self.game.is_game_over()
+    }
+
+    /// 勝者（1または2）．引き分けまたは対局中ならundefined
+    pub fn winner(&self) -> Option<u8> {
+        self.game.judge_winner().map(player_number)
+    }
+
+    /// "in_progress", "player1_won", "player2_won", "draw"のいずれか
+    pub fn status(&self) -> String {
+        match self.game.judge_winner() {
+            Some(Player::Player1) => "player1_won",
+            Some(Player::Player2) => "player2_won",
+            None if self.game.is_game_over() => "draw",
+            None => "in_progress",
+        }
+        .to_string()
+    }
+
+    #[wasm_bindgen(js_name = clone)]
+    pub fn copy(&self) -> Game {
+        self.clone()
+    }
+}
+
+fn legal_actions(game: &quart_engine::game::Game) -> ActionList {
+    if game.is_game_over() {
+        return ActionList::new();
+    }
+    game.action_list()
+}
+
+/// クライアントで動かすAI
+#[wasm_bindgen]
+pub struct Policy {
+    policy: Box<dyn EnginePolicy>,
+}
+
+#[wasm_bindgen]
+impl Policy {
+    pub fn random() -> Policy {
+        Policy {
+            policy: Box::new(RandomPolicy::new()),
+        }
+    }
+
+    #[wasm_bindgen(js_name = oneStepLookAhead)]
+    pub fn one_step_look_ahead() -> Policy {
+        Policy {
+            policy: Box::new(OneStepLookAheadPolicy::new()),
+        }
+    }
+
+    /// 1手にplayOuts回プレイアウトするMCS．maxTime秒（省略時は1秒）経っても打ち切る
+    pub fn mcs(play_outs: u32, max_time: Option<f64>) -> Policy {
+        install_time_source();
+        Policy {
+            policy: Box::new(MCSPolicy {
+                max_time: max_time.unwrap_or(1.0),
+                max_play_outs: Some(play_outs as u64),
+                ..MCSPolicy::with_policy(OneStepLookAheadPolicy::new())
+            }),
+        }
+    }
+
+    /// 次の手を棋譜の表記で返す．終局していれば例外を投げる
+    pub fn action(&self, game: &Game) -> Result<String, JsError> {
+        if game.game.is_game_over() {
+            return Err(js_error("Game is already over"));
+        }
+        install_time_source();
+        let action = self.policy.action(&game.game);
+        Ok(game.game.format_move(&action))
+    }
+}
@@ -0,0 +1,70 @@
+//! Node.jsで動かすテスト: cargo test（wasm-bindgen-test-runnerが必要）
+use quart_engine_wasm::{seed_rng, Game, Policy};
+use wasm_bindgen_test::*;
+
+const START_POSITION: &str = "..../..../..../.... 0";
+
+#[wasm_bindgen_test]
+fn test_legal_moves() {
+    let game = Game::new(Some(START_POSITION.to_string())).unwrap();
+    assert_eq!(game.current_player(), 1);
+    assert_eq!(game.legal_moves().len(), 240);
+    assert!(game.is_legal("a1:1"));
+    assert!(!game.is_legal("a1"));
+    assert!(!game.is_legal("e5:1"));
+}
+
+#[wasm_bindgen_test]
+fn test_play_until_quarto() {
+    let mut game = Game::new(Some(START_POSITION.to_string())).unwrap();
+    for text in ["a1:1", "b1:2", "c1:3"] {
+        game.play(text).unwrap();
+        assert_eq!(game.status(), "in_progress");
+    }
+    assert!(game.play("a1:4").is_err(), "駒があるセルには置けないはず");
+    game.play("d1:4").unwrap();
+    assert!(game.check_win());
+    assert!(game.is_game_over());
+    assert_eq!(game.winner(), Some(2));
+    assert_eq!(game.status(), "player2_won");
+    assert!(game.legal_moves().is_empty());
+}
+
+#[wasm_bindgen_test]
+fn test_serialization() {
+    let game = Game::new(Some("0a3./..../..../.... 5".to_string())).unwrap();
+    assert_eq!(game.to_position(), "0a3./..../..../.... 5 2");
+    assert_eq!(&game.cells()[..4], &[0, 10, 3, -1]);
+    assert_eq!(game.selected_piece(), 5);
+    assert_eq!(game.available_pieces().len(), 12);
+    let copy = Game::from_json(&game.to_json()).unwrap();
+    assert_eq!(copy.to_position(), game.to_position());
+}
+
+#[wasm_bindgen_test]
+fn test_policies_play_to_the_end() {
+    for policy in [
+        Policy::random(),
+        Policy::one_step_look_ahead(),
+        Policy::mcs(200, None),
+    ] {
+        let mut game = Game::new(None).unwrap();
+        while !game.is_game_over() {
+            let text = policy.action(&game).unwrap();
+            game.play(&text).unwrap();
+        }
+        assert!(policy.action(&game).is_err());
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_mcs_is_reproducible_with_seed() {
+    let policy = Policy::mcs(300, Some(3600.0));
+    let mut game = Game::new(Some(START_POSITION.to_string())).unwrap();
+    game.play("a1:1").unwrap();
+    seed_rng(1);
+    let first = policy.action(&game).unwrap();
+    seed_rng(1);
+    let second = policy.action(&game).unwrap();
+    assert_eq!(first, second);
+}